@@ -1,6 +1,9 @@
 use std::error::Error;
 use serde_json::Value;
 use stock_pred::api::binance::Binance;
+use stock_pred::config;
+use stock_pred::trading::discovery::evaluate_klines;
+use stock_pred::types::{SignalParams, TrendDirection};
 use tokio::time::{sleep, Duration};
 use clap::Parser;
 
@@ -12,7 +15,7 @@ pub enum TrendType {
 }
 
 impl TrendType {
-    pub fn from_str(s: &str) -> Self {
+    pub fn parse(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "negative" => TrendType::Negative,
             _ => TrendType::Positive, // default to positive
@@ -20,6 +23,143 @@ impl TrendType {
     }
 }
 
+/// Kline intervals Binance accepts. Case matters: `1m` is a minute, `1M` a month.
+pub const VALID_INTERVALS: [&str; 15] = ["1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w", "1M"];
+
+/// Checks `interval` against `VALID_INTERVALS` so a typo fails before any request is sent.
+pub fn validate_interval(interval: &str) -> Result<String, String> {
+    if VALID_INTERVALS.contains(&interval) {
+        Ok(interval.to_string())
+    } else {
+        Err(format!("invalid interval '{}'; valid intervals are {}", interval, VALID_INTERVALS.join(", ")))
+    }
+}
+
+/// How trade results combine: `Compound` reinvests the whole balance each trade (multipliers
+/// multiply); `Fixed` risks the same stake every trade (per-trade returns add up).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StakeMode {
+    Fixed,
+    Compound,
+}
+
+/// Final balance as a multiple of the starting stake under `mode`.
+pub fn stake_multiplier(trades: &[Trade], mode: StakeMode) -> f64 {
+    match mode {
+        StakeMode::Compound => trades.iter().map(|t| t.multiplier).product(),
+        StakeMode::Fixed => 1.0 + trades.iter().map(|t| t.multiplier - 1.0).sum::<f64>(),
+    }
+}
+
+/// Small seedable PRNG (SplitMix64) so a Monte Carlo run can be reproduced from its seed.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform index in `0..n`; `n` must be non-zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Median and 5th/95th percentiles of one Monte Carlo statistic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Percentiles {
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+/// Distribution of final equity and max drawdown across resampled trade sequences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarloSummary {
+    pub runs: usize,
+    pub final_equity: Percentiles,
+    pub max_drawdown: Percentiles,
+}
+
+/// Nearest-rank percentile of an ascending-sorted slice; `q` in 0..=1.
+pub fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank.min(sorted.len() - 1)]
+}
+
+fn percentiles(mut values: Vec<f64>) -> Percentiles {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Percentiles { p5: percentile(&values, 0.05), median: percentile(&values, 0.5), p95: percentile(&values, 0.95) }
+}
+
+/// Final equity and max drawdown (as a fraction of the peak) of `multipliers` taken in order.
+pub fn equity_path_stats(multipliers: &[f64], mode: StakeMode) -> (f64, f64) {
+    let mut equity = 1.0_f64;
+    let mut peak = 1.0_f64;
+    let mut max_drawdown = 0.0_f64;
+    for m in multipliers {
+        equity = match mode {
+            StakeMode::Compound => equity * m,
+            StakeMode::Fixed => equity + (m - 1.0),
+        };
+        peak = peak.max(equity);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max((peak - equity) / peak);
+        }
+    }
+    (equity, max_drawdown)
+}
+
+/// Resamples the per-trade multipliers `runs` times and summarises the outcomes. Each run draws
+/// `multipliers.len()` trades with replacement: a pure reshuffle would leave the final equity
+/// unchanged (the product and the sum are order-independent) and only move the drawdown.
+pub fn monte_carlo(multipliers: &[f64], runs: usize, mode: StakeMode, seed: u64) -> Option<MonteCarloSummary> {
+    if multipliers.is_empty() || runs == 0 {
+        return None;
+    }
+    let mut rng = SplitMix64::new(seed);
+    let mut finals = Vec::with_capacity(runs);
+    let mut drawdowns = Vec::with_capacity(runs);
+    let mut sample = vec![0.0; multipliers.len()];
+    for _ in 0..runs {
+        for slot in sample.iter_mut() {
+            *slot = multipliers[rng.below(multipliers.len())];
+        }
+        let (equity, drawdown) = equity_path_stats(&sample, mode);
+        finals.push(equity);
+        drawdowns.push(drawdown);
+    }
+    Some(MonteCarloSummary { runs, final_equity: percentiles(finals), max_drawdown: percentiles(drawdowns) })
+}
+
+fn print_monte_carlo(trades: &[Trade], runs: usize, mode: StakeMode, seed: u64) {
+    let multipliers: Vec<f64> = trades.iter().map(|t| t.multiplier).collect();
+    let Some(summary) = monte_carlo(&multipliers, runs, mode, seed) else {
+        println!("Monte Carlo: no trades to resample.");
+        return;
+    };
+    println!("Monte Carlo ({} runs, seed {}, {:?} stake):", summary.runs, seed, mode);
+    println!(
+        "  Final multiplier: p5 {:.4} | median {:.4} | p95 {:.4}",
+        summary.final_equity.p5, summary.final_equity.median, summary.final_equity.p95
+    );
+    println!(
+        "  Max drawdown:     p5 {:.2}% | median {:.2}% | p95 {:.2}%",
+        summary.max_drawdown.p5 * 100.0, summary.max_drawdown.median * 100.0, summary.max_drawdown.p95 * 100.0
+    );
+}
+
 #[derive(Debug)]
 pub struct Candle {
     pub open: f64,
@@ -38,6 +178,47 @@ pub struct Trade {
     pub exit_index: Option<usize>,
 }
 
+/// Result of one backtest run over a candle series.
+#[derive(Debug)]
+pub struct BacktestOutcome {
+    pub final_multiplier: f64,
+    pub trades: Vec<Trade>,
+    /// Buy-and-hold over the same candles: last close / first open.
+    pub hold_multiplier: Option<f64>,
+}
+
+/// Multiplier of buying at the first candle's open and selling at the last candle's close.
+pub fn hold_multiplier(candles: &[Candle]) -> Option<f64> {
+    let first = candles.first()?.open;
+    let last = candles.last()?.close;
+    (first > 0.0).then(|| last / first)
+}
+
+/// Prints the strategy against buy-and-hold, or a JSON object with both when `json` is set.
+fn print_comparison(symbol: &str, trades: &[Trade], hold: Option<f64>, stake: StakeMode, json: bool) {
+    let strategy = stake_multiplier(trades, stake);
+    let difference = hold.map(|h| strategy - h);
+    if json {
+        let report = serde_json::json!({
+            "symbol": symbol,
+            "stake": format!("{:?}", stake).to_lowercase(),
+            "trades": trades.len(),
+            "strategy_multiplier": strategy,
+            "hold_multiplier": hold,
+            "difference": difference,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+        return;
+    }
+    match (hold, difference) {
+        (Some(hold), Some(difference)) => println!(
+            "Strategy vs buy-and-hold: {:.4} vs {:.4} (difference {:+.4}, {:+.2} pp)",
+            strategy, hold, difference, difference * 100.0
+        ),
+        _ => println!("Buy-and-hold: not available (no usable first open)."),
+    }
+}
+
 /// Parses raw candle data (Vec<Vec<Value>>) from Binance into a Vec<Candle>.
 fn parse_candles(raw: Vec<Vec<Value>>) -> Vec<Candle> {
     raw.into_iter()
@@ -63,8 +244,7 @@ fn simulate_trailing_trade(candles: &[Candle], stop_loss_percent: f64) -> (f64,
         let mut highest_price = entry_price;
         let mut exit_index = None;
 
-        for j in i..candles.len() {
-            let candle = &candles[j];
+        for (j, candle) in candles.iter().enumerate().skip(i) {
             if candle.high > highest_price {
                 highest_price = candle.high;
             }
@@ -116,8 +296,7 @@ fn simulate_trailing_trade_negative(candles: &[Candle], stop_loss_percent: f64)
         let mut lowest_price = entry_price;
         let mut exit_index = None;
 
-        for j in i..candles.len() {
-            let candle = &candles[j];
+        for (j, candle) in candles.iter().enumerate().skip(i) {
             if candle.low < lowest_price {
                 lowest_price = candle.low;
             }
@@ -157,6 +336,91 @@ fn simulate_trailing_trade_negative(candles: &[Candle], stop_loss_percent: f64)
     (final_multiplier, trades)
 }
 
+/// Replays the live entry rule over the klines: a trade opens at the close of every window
+/// where `evaluate_klines` fires while flat, then exits on a trailing stop.
+fn simulate_signal_trades(symbol: &str, raw: &[Vec<Value>], candles: &[Candle], lookback: u16, recent: u16, stop_loss_percent: f64) -> (f64, Vec<Trade>) {
+    let mut final_multiplier = 1.0;
+    let mut trades = Vec::new();
+    let window = lookback as usize;
+    let mut i = window;
+    let params = SignalParams {
+        lookback: lookback as u32,
+        recent: recent as u32,
+        green_candles: config::get_require_n_green(),
+        green_min_pct: config::get_green_candle_min_pct(),
+        green_min_body_ratio: config::get_green_candle_min_body_ratio(),
+    };
+
+    while i <= candles.len() {
+        let fired = evaluate_klines(symbol, &raw[i - window..i], params, TrendDirection::Positive).is_some();
+        if !fired {
+            i += 1;
+            continue;
+        }
+
+        let entry_index = i - 1;
+        let entry_price = candles[entry_index].close;
+        let mut highest_price = entry_price;
+        let mut exit_index = None;
+
+        for (j, candle) in candles.iter().enumerate().skip(i) {
+            if candle.high > highest_price {
+                highest_price = candle.high;
+            }
+            let stop_level = highest_price * (1.0 - stop_loss_percent / 100.0);
+            if candle.low <= stop_level {
+                exit_index = Some(j);
+                break;
+            }
+        }
+
+        let exit_price = match exit_index {
+            Some(_) => highest_price * (1.0 - stop_loss_percent / 100.0),
+            None => candles[candles.len() - 1].close,
+        };
+        let trade_multiplier = exit_price / entry_price;
+        final_multiplier *= trade_multiplier;
+        trades.push(Trade {
+            entry_price,
+            exit_price,
+            multiplier: trade_multiplier,
+            entry_index,
+            exit_index,
+        });
+
+        match exit_index {
+            Some(j) => i = j + 1,
+            None => break,
+        }
+    }
+    (final_multiplier, trades)
+}
+
+/// Backtests only the entries the live bot would have taken on `token_symbol`.
+pub async fn backtest_signal(
+    binance: &Binance,
+    token_symbol: &str,
+    interval: &str,
+    limit: u16,
+    stop_loss_percent: f64,
+) -> Result<BacktestOutcome, Box<dyn Error>> {
+    validate_interval(interval)?;
+    let lookback = config::get_lookback_period();
+    let recent = config::get_signal_recent_window();
+
+    let raw_klines = binance.get_klines(token_symbol, interval, limit).await?;
+    if raw_klines.len() < lookback as usize {
+        return Err(format!("Need at least {} klines, got {}", lookback, raw_klines.len()).into());
+    }
+    let candles = parse_candles(raw_klines.clone());
+    if candles.len() != raw_klines.len() {
+        return Err("Some klines could not be parsed".into());
+    }
+
+    let (final_multiplier, trades) = simulate_signal_trades(token_symbol, &raw_klines, &candles, lookback, recent, stop_loss_percent);
+    Ok(BacktestOutcome { final_multiplier, trades, hold_multiplier: hold_multiplier(&candles) })
+}
+
 /// Unified backtest function that uses trailing stop simulation for both positive and negative trends.
 pub async fn backtest_trade(
     binance: &Binance,
@@ -165,7 +429,8 @@ pub async fn backtest_trade(
     limit: u16,
     stop_loss_percent: f64,
     trend: TrendType,
-) -> Result<(f64, Vec<Trade>), Box<dyn Error>> {
+) -> Result<BacktestOutcome, Box<dyn Error>> {
+    validate_interval(interval)?;
     // Fetch historical klines from Binance.
     let raw_klines = binance.get_klines(token_symbol, interval, limit).await?;
     if raw_klines.is_empty() {
@@ -182,7 +447,7 @@ pub async fn backtest_trade(
         TrendType::Negative => simulate_trailing_trade_negative(&candles, stop_loss_percent),
     };
 
-    Ok((final_multiplier, trades))
+    Ok(BacktestOutcome { final_multiplier, trades, hold_multiplier: hold_multiplier(&candles) })
 }
 
 #[derive(Parser, Debug)]
@@ -191,6 +456,7 @@ struct Args {
     /// The trading pair (e.g. FARMUSDT)
     token: String,
     /// The kline interval (e.g. 1h, 15m)
+    #[arg(value_parser = validate_interval)]
     interval: String,
     /// The number of candles to fetch (e.g. 48)
     limit: u16,
@@ -198,35 +464,170 @@ struct Args {
     trend: String,
     /// The stop loss percentage to simulate (e.g. 5 for 5%)
     stop_loss: f64,
+    /// Reinvest everything each trade (compound) or trade a constant stake (fixed)
+    #[arg(long, value_enum, default_value = "compound")]
+    stake: StakeMode,
+    /// Resample the trades this many times and report the return distribution (0 = off)
+    #[arg(long, default_value_t = 0)]
+    monte_carlo: usize,
+    /// Seed for --monte-carlo, so a run can be reproduced
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Print only the strategy vs buy-and-hold comparison as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// `backtest signal <symbol>`: replay the live entry rule instead of entering every candle.
+#[derive(Parser, Debug)]
+struct SignalArgs {
+    /// The trading pair (e.g. FARMUSDT)
+    token: String,
+    /// The kline interval (e.g. 1h, 15m)
+    #[arg(default_value = "1h", value_parser = validate_interval)]
+    interval: String,
+    /// The number of candles to fetch (max 1000)
+    #[arg(default_value_t = 500)]
+    limit: u16,
+    /// The stop loss percentage to simulate; defaults to STOP_LOSS_PERCENT
+    stop_loss: Option<f64>,
+    /// Reinvest everything each trade (compound) or trade a constant stake (fixed)
+    #[arg(long, value_enum, default_value = "compound")]
+    stake: StakeMode,
+    /// Resample the trades this many times and report the return distribution (0 = off)
+    #[arg(long, default_value_t = 0)]
+    monte_carlo: usize,
+    /// Seed for --monte-carlo, so a run can be reproduced
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    /// Print only the strategy vs buy-and-hold comparison as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// Win rate, average win/loss and expectancy of a set of trades. A win is a multiplier above 1.0.
+#[derive(Debug, Default)]
+pub struct TradeStats {
+    pub trades: usize,
+    pub win_rate: f64,
+    pub avg_win_multiplier: f64,
+    pub avg_loss_multiplier: f64,
+    /// Expected return per trade as a fraction: win_rate * avg_win - loss_rate * avg_loss,
+    /// where avg_win / avg_loss are the average gain / loss sizes (multiplier - 1, 1 - multiplier).
+    pub expectancy: f64,
+}
+
+fn compute_trade_stats(trades: &[Trade]) -> TradeStats {
+    if trades.is_empty() {
+        return TradeStats::default();
+    }
+    let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    let wins: Vec<f64> = trades.iter().map(|t| t.multiplier).filter(|m| *m > 1.0).collect();
+    let losses: Vec<f64> = trades.iter().map(|t| t.multiplier).filter(|m| *m <= 1.0).collect();
+
+    let win_rate = wins.len() as f64 / trades.len() as f64;
+    let avg_win_multiplier = mean(&wins);
+    let avg_loss_multiplier = mean(&losses);
+    let avg_win = if wins.is_empty() { 0.0 } else { avg_win_multiplier - 1.0 };
+    let avg_loss = if losses.is_empty() { 0.0 } else { 1.0 - avg_loss_multiplier };
+
+    TradeStats {
+        trades: trades.len(),
+        win_rate,
+        avg_win_multiplier,
+        avg_loss_multiplier,
+        expectancy: win_rate * avg_win - (1.0 - win_rate) * avg_loss,
+    }
+}
+
+fn print_trade_stats(trades: &[Trade]) {
+    let stats = compute_trade_stats(trades);
+    println!("Trade statistics:");
+    println!("  Trades: {} | Win rate: {:.1}%", stats.trades, stats.win_rate * 100.0);
+    println!(
+        "  Avg winning multiplier: {:.4} | Avg losing multiplier: {:.4}",
+        stats.avg_win_multiplier, stats.avg_loss_multiplier
+    );
+    println!("  Expectancy: {:+.2}% per trade", stats.expectancy * 100.0);
+}
+
+fn print_trades(trades: &[Trade]) {
+    println!("Trade details:");
+    for trade in trades {
+        match trade.exit_index {
+            Some(_idx) => println!(
+                "  Trade from candle {}: entry at {:.2}, exit at {:.2}, multiplier: {:.4}",
+                trade.entry_index + 1, trade.entry_price, trade.exit_price, trade.multiplier
+            ),
+            None => println!(
+                "  Final trade starting at candle {}: entry at {:.2}, exit at {:.2} (final), multiplier: {:.4}",
+                trade.entry_index + 1, trade.entry_price, trade.exit_price, trade.multiplier
+            ),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
     let binance = Binance::new();
-    let trend = TrendType::from_str(&args.trend);
 
-    println!(
-        "Running backtest for {} over {} candles with interval {} for {:?} trend and stop loss {}%...",
-        args.token, args.limit, args.interval, trend, args.stop_loss
-    );
+    if std::env::args().nth(1).as_deref() == Some("signal") {
+        // Drop the binary name so "signal" takes its place for clap.
+        let args = SignalArgs::parse_from(std::env::args().skip(1));
+        let stop_loss = args.stop_loss.unwrap_or_else(config::get_stop_loss_percent);
+
+        // Keep stdout pure JSON for --json.
+        if !args.json {
+            println!(
+                "Running signal backtest for {} over {} candles with interval {} and stop loss {}%...",
+                args.token, args.limit, args.interval, stop_loss
+            );
+        }
+
+        match backtest_signal(&binance, &args.token, &args.interval, args.limit, stop_loss).await {
+            Ok(BacktestOutcome { trades, hold_multiplier, .. }) if args.json => {
+                print_comparison(&args.token, &trades, hold_multiplier, args.stake, true);
+            }
+            Ok(BacktestOutcome { trades, hold_multiplier, .. }) => {
+                let multiplier = stake_multiplier(&trades, args.stake);
+                let total_profit = (multiplier - 1.0) * 100.0;
+                println!("Live signal fired {} times.", trades.len());
+                println!("Backtest result ({:?} stake): Final multiplier = {:.4} (Total Profit: {:+.2}%)", args.stake, multiplier, total_profit);
+                print_trades(&trades);
+                print_trade_stats(&trades);
+                print_comparison(&args.token, &trades, hold_multiplier, args.stake, false);
+                if args.monte_carlo > 0 {
+                    print_monte_carlo(&trades, args.monte_carlo, args.stake, args.seed);
+                }
+            }
+            Err(e) => eprintln!("Backtest error: {}", e),
+        }
+        return Ok(());
+    }
+
+    let args = Args::parse();
+    let trend = TrendType::parse(&args.trend);
+
+    if !args.json {
+        println!(
+            "Running backtest for {} over {} candles with interval {} for {:?} trend and stop loss {}%...",
+            args.token, args.limit, args.interval, trend, args.stop_loss
+        );
+    }
 
     match backtest_trade(&binance, &args.token, &args.interval, args.limit, args.stop_loss, trend).await {
-        Ok((multiplier, trades)) => {
+        Ok(BacktestOutcome { trades, hold_multiplier, .. }) if args.json => {
+            print_comparison(&args.token, &trades, hold_multiplier, args.stake, true);
+        }
+        Ok(BacktestOutcome { trades, hold_multiplier, .. }) => {
+            let multiplier = stake_multiplier(&trades, args.stake);
             let total_profit = (multiplier - 1.0) * 100.0;
-            println!("Backtest result: Final multiplier = {:.4} (Total Profit: {:+.2}%)", multiplier, total_profit);
-            println!("Trade details:");
-            for trade in trades {
-                match trade.exit_index {
-                    Some(_idx) => println!(
-                        "  Trade from candle {}: entry at {:.2}, exit at {:.2}, multiplier: {:.4}",
-                        trade.entry_index + 1, trade.entry_price, trade.exit_price, trade.multiplier
-                    ),
-                    None => println!(
-                        "  Final trade starting at candle {}: entry at {:.2}, exit at {:.2} (final), multiplier: {:.4}",
-                        trade.entry_index + 1, trade.entry_price, trade.exit_price, trade.multiplier
-                    ),
-                }
+            println!("Backtest result ({:?} stake): Final multiplier = {:.4} (Total Profit: {:+.2}%)", args.stake, multiplier, total_profit);
+            print_trades(&trades);
+            print_trade_stats(&trades);
+            print_comparison(&args.token, &trades, hold_multiplier, args.stake, false);
+            if args.monte_carlo > 0 {
+                print_monte_carlo(&trades, args.monte_carlo, args.stake, args.seed);
             }
         },
         Err(e) => eprintln!("Backtest error: {}", e),
@@ -234,4 +635,161 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     sleep(Duration::from_secs(1)).await;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stock_pred::api::binance::mock::local_binance;
+
+    /// Rises 2% a candle, with a wick 6% below the open every 10th candle.
+    fn shaken_uptrend(n: usize) -> Vec<Candle> {
+        let mut open = 100.0;
+        (0..n)
+            .map(|i| {
+                let close = open * 1.02;
+                let low = if i % 10 == 9 { open * 0.94 } else { open };
+                let candle = Candle { open, high: close, low, close };
+                open = close;
+                candle
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_shaken_out_trail_underperforms_hold_in_a_strong_trend() {
+        let candles = shaken_uptrend(40);
+        let (_, trades) = simulate_trailing_trade(&candles, 5.0);
+        assert!(trades.len() > 1);
+
+        let hold = hold_multiplier(&candles).unwrap();
+        assert!((hold - 1.02_f64.powi(40)).abs() < 1e-9);
+        let difference = stake_multiplier(&trades, StakeMode::Compound) - hold;
+        assert!(difference < 0.0, "difference {}", difference);
+    }
+
+    #[test]
+    fn trend_names_parse_case_insensitively_with_a_positive_default() {
+        assert!(matches!(TrendType::parse("Negative"), TrendType::Negative));
+        assert!(matches!(TrendType::parse("whatever"), TrendType::Positive));
+    }
+
+    /// One back-to-back trade per multiplier, entered at 100.
+    fn trades_with(multipliers: &[f64]) -> Vec<Trade> {
+        multipliers
+            .iter()
+            .enumerate()
+            .map(|(i, m)| Trade { entry_price: 100.0, exit_price: 100.0 * m, multiplier: *m, entry_index: i, exit_index: Some(i + 1) })
+            .collect()
+    }
+
+    #[test]
+    fn three_wins_and_two_losses_give_the_exact_expectancy() {
+        let trades = trades_with(&[1.10, 0.95, 1.20, 0.85, 1.06]);
+        let stats = compute_trade_stats(&trades);
+        assert_eq!(stats.trades, 5);
+        assert!((stats.win_rate - 0.6).abs() < 1e-12);
+        assert!((stats.avg_win_multiplier - 1.12).abs() < 1e-12);
+        assert!((stats.avg_loss_multiplier - 0.90).abs() < 1e-12);
+        // 0.6 * 0.12 - 0.4 * 0.10
+        assert!((stats.expectancy - 0.032).abs() < 1e-12, "{}", stats.expectancy);
+        assert_eq!(compute_trade_stats(&[]).trades, 0);
+    }
+
+    #[test]
+    fn a_mistyped_interval_is_rejected_while_parsing_the_arguments() {
+        let err = Args::try_parse_from(["backtest", "FOOUSDT", "1hr", "48", "positive", "5"]).unwrap_err().to_string();
+        assert!(err.contains("invalid interval '1hr'") && err.contains("1h, 2h"), "{}", err);
+
+        let args = Args::try_parse_from(["backtest", "FOOUSDT", "1h", "48", "positive", "5"]).unwrap();
+        assert_eq!(args.interval, "1h");
+    }
+
+    #[test]
+    fn fixed_and_compound_stakes_differ_on_two_trades() {
+        let trades = trades_with(&[1.5, 0.8]);
+        // Compound: 1.5 * 0.8. Fixed: 1 + 0.5 - 0.2.
+        assert!((stake_multiplier(&trades, StakeMode::Compound) - 1.2).abs() < 1e-12);
+        assert!((stake_multiplier(&trades, StakeMode::Fixed) - 1.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn a_seeded_monte_carlo_run_is_reproducible() {
+        let multipliers = [1.1, 0.95, 1.05, 0.9, 1.2, 1.02, 0.97];
+        let summary = monte_carlo(&multipliers, 2_000, StakeMode::Compound, 42).unwrap();
+        assert_eq!(monte_carlo(&multipliers, 2_000, StakeMode::Compound, 42), Some(summary));
+        assert_ne!(monte_carlo(&multipliers, 2_000, StakeMode::Compound, 43), Some(summary));
+
+        let (p5, median, p95) = (summary.final_equity.p5, summary.final_equity.median, summary.final_equity.p95);
+        assert!(p5 < median && median < p95, "{:?}", summary.final_equity);
+        // Every resampled path lies between all-worst and all-best trades.
+        assert!(p5 >= 0.9_f64.powi(7) && p95 <= 1.2_f64.powi(7));
+        assert!(summary.max_drawdown.p5 <= summary.max_drawdown.p95);
+        assert_eq!(summary.runs, 2_000);
+        assert_eq!(monte_carlo(&[], 10, StakeMode::Compound, 42), None);
+    }
+
+    /// Raw `/klines` rows for `(open, high, low, close)` candles, an hour apart.
+    fn raw_klines(candles: &[(f64, f64, f64, f64)]) -> Vec<Value> {
+        candles
+            .iter()
+            .enumerate()
+            .map(|(i, (open, high, low, close))| {
+                let open_time = i as i64 * 3_600_000;
+                serde_json::json!([open_time, open.to_string(), high.to_string(), low.to_string(), close.to_string(), "1", open_time + 3_599_999])
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn the_signal_backtest_takes_one_trade_per_live_trigger() {
+        // Two 5% rallies, each followed by a drop through a 10% trail, with a flat stretch between.
+        let rise = |open: f64| (open, open * 1.05, open, open * 1.05);
+        let mut candles = Vec::new();
+        let mut price = 100.0;
+        for _ in 0..4 {
+            candles.push(rise(price));
+            price *= 1.05;
+        }
+        candles.push((price, price, 100.0, 100.0));
+        candles.extend([(100.0, 100.0, 100.0, 100.0); 3]);
+        price = 100.0;
+        for _ in 0..4 {
+            candles.push(rise(price));
+            price *= 1.05;
+        }
+        candles.push((price, price, 100.0, 100.0));
+
+        let saved = {
+            let mut cfg = config::SHARED_CONFIG.write().unwrap();
+            (
+                std::mem::replace(&mut cfg.lookback_period, 4),
+                std::mem::replace(&mut cfg.signal_recent_window, 2),
+                std::mem::replace(&mut cfg.require_n_green, 2),
+                std::mem::replace(&mut cfg.green_candle_min_pct, 1.0),
+                std::mem::replace(&mut cfg.green_candle_min_body_ratio, 0.5),
+            )
+        };
+        let binance = local_binance(vec![("/klines", Value::Array(raw_klines(&candles)).to_string())]).await;
+        let outcome = backtest_signal(&binance, "FOOUSDT", "1h", 13, 10.0).await;
+        {
+            let mut cfg = config::SHARED_CONFIG.write().unwrap();
+            (cfg.lookback_period, cfg.signal_recent_window, cfg.require_n_green, cfg.green_candle_min_pct, cfg.green_candle_min_body_ratio) = saved;
+        }
+        let outcome = outcome.unwrap();
+
+        // The first window fires at the 4th close; the second once the new rally is 10% above
+        // the flat stretch, at the 10th close.
+        assert_eq!(outcome.trades.len(), 2, "{:?}", outcome.trades);
+        let first = &outcome.trades[0];
+        assert_eq!((first.entry_index, first.exit_index), (3, Some(4)));
+        assert!((first.entry_price - 121.550625).abs() < 1e-9);
+        assert!((first.exit_price - 109.3955625).abs() < 1e-9);
+        assert!((first.multiplier - 0.9).abs() < 1e-12);
+        let second = &outcome.trades[1];
+        assert_eq!((second.entry_index, second.exit_index), (9, Some(12)));
+        // Entered at 110.25, trailed from 121.550625 and stopped at 109.3955625.
+        assert!((second.multiplier - 109.3955625 / 110.25).abs() < 1e-12);
+        assert!((outcome.final_multiplier - 0.9 * 109.3955625 / 110.25).abs() < 1e-12);
+    }
+}
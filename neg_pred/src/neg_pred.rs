@@ -4,13 +4,15 @@ use stock_pred::logging::init_tracing;
 use tokio::time::{sleep, Duration};
 #[allow(unused_imports)]
 use tracing::{debug, info, span, Level};
+use stock_pred::trading::strategy::MomentumStrategy;
 use stock_pred::types::TrendDirection;
-use stock_pred::config::SHARED_CONFIG;
+use stock_pred::config::{get_enable_shorts, SHARED_CONFIG};
+use stock_pred::status_println;
 
    
 /* 
 // Move update_orders_loop outside of main.
-async fn update_orders_loop(open_orders: Arc<Mutex<Vec<Order>>>) {
+async fn update_orders_loop(open_orders: Arc<Mutex<Vec<ManagedPosition>>>) {
     loop {
         {
             // Await the lock on the orders.
@@ -38,13 +40,65 @@ async fn update_orders_loop(open_orders: Arc<Mutex<Vec<Order>>>) {
     }
 }*/
 
+/// One bearish scan pass: discovers and prints the signals, and with ENABLE_SHORTS opens a
+/// short on each one without an open futures short. Nothing is shorted when the open shorts
+/// cannot be fetched.
+async fn scan_cycle(binance: &Binance, assets: &[String], transaction_amounts: &[f64]) {
+    let signals = discover_signals(
+        binance,
+        assets,
+        transaction_amounts,
+        //open_orders_clone,
+        TrendDirection::Negative,
+        &MomentumStrategy,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("❌ Market scan failed: {}", e);
+        info!("❌ Market scan failed: {}", e);
+        Vec::new()
+    });
+    let open_shorts = if get_enable_shorts() && !signals.is_empty() {
+        match binance.get_futures_short_symbols().await {
+            Ok(symbols) => Some(symbols),
+            Err(e) => {
+                eprintln!("❌ Could not fetch open futures shorts; not shorting this cycle: {}", e);
+                info!("❌ Could not fetch open futures shorts; not shorting this cycle: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    for signal in signals {
+        println!(
+            "🔻 Bearish: {:<12} | Drop: {:>6.2}% | Recent: {:>6.2}% | Fluct: {:>7.4} (~{:>5.2}%)",
+            signal.symbol,
+            signal.overall_growth,
+            signal.recent_growth,
+            signal.avg_fluct_raw,
+            signal.avg_fluct_pct,
+        );
+        let Some(open_shorts) = &open_shorts else {
+            continue;
+        };
+        if open_shorts.contains(&signal.symbol) {
+            continue;
+        }
+        if let Err(e) = binance.execute_short_with_stop(&signal.symbol).await {
+            eprintln!("❌ Short on {} failed: {}", signal.symbol, e);
+            info!("❌ Short on {} failed: {}", signal.symbol, e);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     // Initialize logging (this sets up the reloadable layer).
     let _guard = init_tracing(false, Level::INFO);
     let binance = Binance::new();
-    //let open_orders: Arc<Mutex<Vec<Order>>> = Arc::new(Mutex::new(Vec::new()));
-    //let converted_orders: Vec<Order> = open_orders_guard.iter().cloned().map(Order::from).collect();
+    //let open_orders: Arc<Mutex<Vec<ManagedPosition>>> = Arc::new(Mutex::new(Vec::new()));
+    //let converted_orders: Vec<ManagedPosition> = fills.into_iter().map(ManagedPosition::from).collect();
 
     // Parse the list of assets from the environment variable QUOTE_ASSETS and transaction amounts from the config.
     let (assets, transaction_amounts) = {
@@ -53,37 +107,29 @@ async fn main() {
     };
     println!("Assets to scan: {:?}", assets);
     info!("Assets to scan: {:?}", assets);
+    if !get_enable_shorts() {
+        println!("ℹ️ Spot mode: shorting is unsupported, bearish signals are only reported (set ENABLE_SHORTS=true to trade them on futures).");
+        info!("ℹ️ Spot mode: shorting is unsupported, bearish signals are only reported (set ENABLE_SHORTS=true to trade them on futures).");
+    }
+
+    if std::env::args().any(|a| a == "--once") {
+        scan_cycle(&binance, &assets, &transaction_amounts).await;
+        return;
+    }
 
     // Spawn the market-check loop.
     //let open_orders_clone = Arc::clone(&open_orders);
     let market_check_handle = tokio::spawn(async move {
         loop {
-            let signals = discover_signals(
-                &binance,
-                &assets,
-                &transaction_amounts,
-                //open_orders_clone,
-                TrendDirection::Negative,
-            )
-            .await;
-            for signal in signals {
-                println!(
-                    "🔻 Bearish: {:<12} | Drop: {:>6.2}% | Recent: {:>6.2}% | Fluct: {:>7.4} (~{:>5.2}%)",
-                    signal.symbol,
-                    signal.overall_growth,
-                    signal.recent_growth,
-                    signal.avg_fluct_raw,
-                    signal.avg_fluct_pct,
-                );
-            }
+            scan_cycle(&binance, &assets, &transaction_amounts).await;
         // Extract values from the shared config
         // Extract values and drop the guard immediately:
         let loop_time = {
            let current_config = SHARED_CONFIG.read().unwrap();
            current_config.loop_time_seconds
            };
-           println!("-------------------------------------------------------------------------");
-           println!("Sleeping for {} seconds before the next iteration...", loop_time);
+           status_println!("-------------------------------------------------------------------------");
+           status_println!("Sleeping for {} seconds before the next iteration...", loop_time);
            info!("Sleeping for {} seconds before the next iteration...", loop_time);
            // Now call sleep without holding the lock:
            sleep(Duration::from_secs(loop_time)).await;
@@ -1,6 +1,8 @@
 use dotenv::dotenv;
 use stock_pred::api::binance::Binance;
 use stock_pred::config::SHARED_CONFIG;
+use stock_pred::math::percent_change;
+use stock_pred::trading::discovery::tail_window;
 
 #[tokio::main]
 async fn main() {
@@ -40,7 +42,8 @@ async fn main() {
         // token is of type Ticker24hr; use its symbol for the klines call.
         match binance.get_klines(&token.symbol, interval, lookback_period).await {
             Ok(klines) => {
-                if klines.len() < lookback_period as usize {
+                // Delisted or halted symbols can return no candles at all; two are needed for the trend check.
+                if klines.len() < (lookback_period as usize).max(2) {
                     eprintln!("Not enough data for {}.", token.symbol);
                     continue;
                 }
@@ -48,23 +51,26 @@ async fn main() {
                 let second_last_candle = &klines[klines.len() - 2];
                 let last_candle = &klines[klines.len() - 1];
 
-                let open_price_str = first_candle[1].as_str().unwrap_or("0");
-                let last_close_str = last_candle[4].as_str().unwrap_or("0");
-                let prev_close_str = second_last_candle[4].as_str().unwrap_or("0");
+                let open_price_str = first_candle.get(1).and_then(|v| v.as_str()).unwrap_or("0");
+                let last_close_str = last_candle.get(4).and_then(|v| v.as_str()).unwrap_or("0");
+                let prev_close_str = second_last_candle.get(4).and_then(|v| v.as_str()).unwrap_or("0");
 
                 if let (Ok(open_price), Ok(last_close), Ok(prev_close)) = (
                     open_price_str.parse::<f64>(),
                     last_close_str.parse::<f64>(),
                     prev_close_str.parse::<f64>(),
                 ) {
-                    let overall_change = ((last_close - open_price) / open_price) * 100.0;
+                    let overall_change = percent_change(open_price, last_close).unwrap_or(0.0);
                     let current_trend_down = last_close < prev_close;
 
                     // For a dump, we expect an overall negative change.
                     if overall_change <= -10.0 && current_trend_down {
                         // Check recent trend over the last_hours_period.
                         let recent_range = last_hours_period as usize;
-                        let last_recent_candles = &klines[klines.len() - recent_range..];
+                        let Some(last_recent_candles) = tail_window(&klines, recent_range) else {
+                            eprintln!("Not enough recent data for {}.", token.symbol);
+                            continue;
+                        };
                         let first_recent_open_str = last_recent_candles[0][1].as_str().unwrap_or("0");
                         let last_recent_close_str = last_recent_candles[last_recent_candles.len() - 1][4].as_str().unwrap_or("0");
 
@@ -72,7 +78,7 @@ async fn main() {
                             first_recent_open_str.parse::<f64>(),
                             last_recent_close_str.parse::<f64>(),
                         ) {
-                            let recent_change = ((last_recent_close - first_recent_open) / first_recent_open) * 100.0;
+                            let recent_change = percent_change(first_recent_open, last_recent_close).unwrap_or(0.0);
                             if recent_change < 0.0 {
                                 println!(
                                     "🔻 {} is dumping with {:.2}% overall change and {:.2}% recent change over the last {} hours!",
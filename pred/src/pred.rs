@@ -1,25 +1,483 @@
-use stock_pred::api::binance::Binance;
-use stock_pred::logging::init_tracing;
+use stock_pred::api::binance::{AccountInfo, Binance, SystemStatus};
+use stock_pred::logging::{flush_trade_log, init_tracing, log_trade_event};
 use tokio::time::{sleep, Duration};
 #[allow(unused_imports)]
 use tracing::{debug, info, span, Level};
 use stock_pred::config::SHARED_CONFIG;
-use stock_pred::trading::discovery::discover_signals;
-use stock_pred::types::TrendDirection;
+use stock_pred::control::{self, Control};
+use stock_pred::trading::discovery::{discover_signals, evaluate, explain, load_klines_file, rotate_by_headroom, split_symbol};
+use stock_pred::trading::paper::PaperPortfolio;
+use stock_pred::trading::strategy::MomentumStrategy;
+use stock_pred::types::{CycleSummary, DailySpend, SignalParams, TrendDirection, MARKET_TREND};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::path::Path;
 use stock_pred::config::is_trading_day;
-use chrono::Datelike;
+use chrono::{Datelike, Utc};
 use stock_pred::config;
 use stock_pred::config::watch_config;
+use stock_pred::status_println;
 
 
-   
+/// How long the market loop waits before re-checking a maintenance window.
+const MAINTENANCE_RETRY_SECS: u64 = 300;
+
+/// How long the market loop waits before retrying after a failed `/ping`.
+const CONNECTIVITY_RETRY_SECS: u64 = 60;
+
+/// Maximum tolerated difference between local and exchange clocks (Binance rejects > recvWindow).
+const MAX_CLOCK_SKEW_MS: i64 = 1000;
+
+/// Print one checklist line and pass the result through.
+fn check(label: &str, ok: bool, detail: &str) -> bool {
+    let mark = if ok { "✅ PASS" } else { "❌ FAIL" };
+    println!("{} {:<22} {}", mark, label, detail);
+    info!("{} {:<22} {}", mark, label, detail);
+    ok
+}
+
+/// Checks that the account can trade spot and holds every quote asset. Prints each check.
+fn account_checks(account: &AccountInfo, quote_assets: &[String]) -> bool {
+    let mut all_ok = check("can_trade", account.can_trade, &format!("can_trade: {}", account.can_trade));
+    let spot = account.permissions.iter().any(|p| p == "SPOT");
+    all_ok &= check("spot permission", spot, &format!("permissions: {:?}", account.permissions));
+    for asset in quote_assets {
+        let balance = account.balances.iter().find(|b| &b.asset == asset);
+        let detail = match balance {
+            Some(b) => format!("{} free: {}", asset, b.free),
+            None => format!("{} not found in balances", asset),
+        };
+        all_ok &= check("quote asset", balance.is_some(), &detail);
+    }
+    all_ok
+}
+
+/// Verify API keys, permissions, quote assets and clock sync before trading.
+async fn preflight(binance: &Binance) -> bool {
+    println!("🔎 Running preflight checks...");
+    let mut all_ok = true;
+
+    let account = match binance.get_account_info().await {
+        Ok(account) => {
+            check("account info", true, "signed request accepted");
+            Some(account)
+        }
+        Err(e) => {
+            all_ok &= check("account info", false, &e.to_string());
+            None
+        }
+    };
+
+    if let Some(account) = &account {
+        all_ok &= account_checks(account, &config::get_quote_assets());
+    }
+
+    match binance.get_server_time().await {
+        Ok(server_time) => {
+            let local = chrono::Utc::now().timestamp_millis();
+            let skew = local - server_time as i64;
+            all_ok &= check("server time sync", skew.abs() <= MAX_CLOCK_SKEW_MS, &format!("skew {} ms", skew));
+        }
+        Err(e) => {
+            all_ok &= check("server time sync", false, &e.to_string());
+        }
+    }
+
+    if all_ok {
+        println!("✅ Preflight passed.");
+    } else {
+        println!("❌ Preflight failed.");
+    }
+    all_ok
+}
+
+/// Loads the config, prints every resolved value (secrets redacted) and reports what
+/// `Config::validate` finds. Returns false when there is any problem.
+fn dry_validate() -> bool {
+    config::load_env_file();
+    let config = config::Config::from_env();
+    println!("🔧 Resolved configuration:");
+    println!("{:#?}", config);
+    println!("BINANCE_API_KEY: {}", config::redact(&std::env::var("BINANCE_API_KEY").unwrap_or_default()));
+    println!("BINANCE_SECRET_KEY: {}", config::redact(&std::env::var("BINANCE_SECRET_KEY").unwrap_or_default()));
+
+    let problems = config.validate();
+    if problems.is_empty() {
+        println!("✅ Configuration is valid.");
+        return true;
+    }
+    for problem in &problems {
+        println!("❌ {}", problem);
+    }
+    println!("❌ {} configuration problem(s) found.", problems.len());
+    false
+}
+
+/// Starting balance, fee and slippage of `pred simulate`, from its flags.
+fn simulate_settings(args: &[String]) -> (f64, f64, f64) {
+    let flag = |name: &str, default: f64| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(default)
+    };
+    (flag("--balance", 1000.0), flag("--fee-pct", 0.1), flag("--slippage-pct", 0.05))
+}
+
+/// `pred simulate`: runs the real scanner and stop rules against a paper portfolio and writes
+/// paper BUY/SET/SELL rows to `<TRADE_LOG_FOLDER>/paper`. Only reads from Binance; no orders.
+/// Returns false when a `--once` pass could not fetch prices.
+async fn simulate(binance: &Binance, assets: &[String], transaction_amounts: &[f64], args: &[String]) -> bool {
+    let (starting_balance, fee_pct, slippage_pct) = simulate_settings(args);
+    let folder = format!("{}/paper", config::get_trade_log_folder().trim_end_matches('/'));
+    config::set_trade_log_folder(&folder);
+    let mut portfolio = PaperPortfolio::new(assets, starting_balance, fee_pct, slippage_pct);
+    println!(
+        "🧪 Paper trading with {} per quote asset (fee {}%, slippage {}%). Rows go to {}",
+        starting_balance, fee_pct, slippage_pct, folder
+    );
+    info!("🧪 Paper trading started; rows go to {}", folder);
+
+    let once = args.iter().any(|a| a == "--once");
+    let mut last_scan: Option<std::time::Instant> = None;
+    loop {
+        let trend = MARKET_TREND.read().await.clone();
+        let prices = match binance.get_all_prices().await {
+            Ok(prices) => prices,
+            Err(e) if once => {
+                println!("❌ Failed to fetch prices: {}.", e);
+                return false;
+            }
+            Err(e) => {
+                println!("❌ Failed to fetch prices: {}. Retrying in 60 seconds.", e);
+                sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+        };
+
+        // Stops first, so a position stopped out this pass frees its balance for new entries.
+        let held: Vec<String> = portfolio.positions.keys().cloned().collect();
+        for symbol in held {
+            let Some(&price) = prices.get(&symbol) else { continue };
+            if let Some(fill) = portfolio.check_stop(&symbol, price) {
+                log_trade_event(&symbol, "SELL", fill.price, fill.quantity, fill.quote_amount, 0.0, "paper_stop_hit", &trend).await;
+                println!("🧪 📉 Paper SELL {} {:.6} @ {:.6} → {:.4} quote", symbol, fill.quantity, fill.price, fill.quote_amount);
+                continue;
+            }
+            let entry = portfolio.positions[&symbol].entry_price;
+            let trailing = price * (1.0 - Binance::trailing_stop_percent(&symbol, price, entry) / 100.0);
+            let existing = portfolio.positions[&symbol].stop_price;
+            let locked = Binance::profit_locked_stop(trailing, existing, entry, price, config::get_profit_lock_trail_pct());
+            if locked > existing {
+                if let Some(stop) = portfolio.ratchet_stop(&symbol, locked) {
+                    let quantity = portfolio.positions[&symbol].quantity;
+                    log_trade_event(&symbol, "SET", stop, quantity, stop * quantity, stop, "paper_stop_update", &trend).await;
+                }
+            }
+        }
+
+        let scan_due = last_scan.is_none_or(|t| t.elapsed() >= Duration::from_secs(config::get_loop_time_seconds()));
+        if scan_due {
+            last_scan = Some(std::time::Instant::now());
+            match discover_signals(binance, assets, transaction_amounts, TrendDirection::Positive, &MomentumStrategy).await {
+                Ok(signals) => {
+                    for signal in signals {
+                        let Some(&price) = prices.get(&signal.symbol) else { continue };
+                        let Some((_, quote)) = split_symbol(&signal.symbol, assets) else { continue };
+                        let (quote_amount, stop_loss_percent) = config::get_quote_amount_and_stop_loss(quote);
+                        let stop_pct = Binance::floored_stop_percent(stop_loss_percent, config::get_min_stop_distance_pct());
+                        let Some(fill) = portfolio.buy(&signal.symbol, quote, price, quote_amount, stop_pct) else { continue };
+                        let stop = portfolio.positions[&signal.symbol].stop_price;
+                        log_trade_event(&signal.symbol, "BUY", fill.price, fill.quantity, fill.quote_amount, stop, "paper_buy", &trend).await;
+                        log_trade_event(&signal.symbol, "SET", stop, fill.quantity, stop * fill.quantity, stop, "paper_initial_stop", &trend).await;
+                        println!("🧪 📈 Paper BUY {} {:.6} @ {:.6} (stop {:.6})", signal.symbol, fill.quantity, fill.price, stop);
+                    }
+                }
+                Err(e) => println!("❌ Market scan failed: {}", e),
+            }
+        }
+
+        let equity = portfolio.equity(&prices);
+        println!(
+            "🧪 Paper balance: {:?} | equity: {:?} | open: {} | realized P&L: {:.4}",
+            portfolio.balances, equity, portfolio.positions.len(), portfolio.realized_pnl
+        );
+        info!("🧪 Paper equity: {:?} | realized P&L: {:.4}", equity, portfolio.realized_pnl);
+
+        if once {
+            flush_trade_log();
+            return true;
+        }
+        sleep(Duration::from_secs(config::get_stop_loss_loop_seconds())).await;
+    }
+}
+
+/// Runs the live entry rules over a recorded kline file without touching the network.
+fn replay(path: &str) -> bool {
+    let klines = match load_klines_file(Path::new(path)) {
+        Ok(klines) => klines,
+        Err(e) => {
+            println!("❌ Could not load klines from {}: {}", path, e);
+            return false;
+        }
+    };
+    let params = SignalParams {
+        lookback: config::get_lookback_period() as u32,
+        recent: config::get_signal_recent_window() as u32,
+        green_candles: config::get_require_n_green(),
+        green_min_pct: config::get_green_candle_min_pct(),
+        green_min_body_ratio: config::get_green_candle_min_body_ratio(),
+    };
+    // Same window the live scan evaluates: the most recent `lookback` candles.
+    let window = &klines[klines.len().saturating_sub(params.lookback as usize)..];
+    println!("🔁 Replaying {} ({} klines, lookback {}, recent {})", path, klines.len(), params.lookback, params.recent);
+
+    let Some(evaluation) = explain(window, params, TrendDirection::Positive) else {
+        println!("❌ Not enough klines: need {}, got {}", params.lookback, klines.len());
+        return false;
+    };
+    println!("Overall growth:   {:>7.2}%", evaluation.overall_growth);
+    println!("Recent growth:    {:>7.2}%", evaluation.recent_growth);
+    println!("Last close up:    {}", evaluation.current_trend_up);
+    println!("Strong green ({}): {}", params.green_candles, evaluation.strong_green);
+    match evaluate(window, params, TrendDirection::Positive) {
+        Some(signal) => println!(
+            "✅ Signal fires | Score: {:.2} | Fluct: {:.4} (~{:.2}%)",
+            signal.score, signal.avg_fluct_raw, signal.avg_fluct_pct
+        ),
+        None => println!("⛔ No signal"),
+    }
+    true
+}
+
+/// Waits `spacing` before every buy of a cycle but the first (`i` = 0), so a burst of
+/// signals does not hit the rate limits or enter all at the same local top.
+async fn space_buy(i: usize, spacing: Duration) {
+    if i > 0 && !spacing.is_zero() {
+        sleep(spacing).await;
+    }
+}
+
+/// One scan + execute pass of the market loop. Returns how long to wait before the next pass.
+async fn market_cycle(binance: &Binance, control: &Control, assets: &[String], transaction_amounts: &[f64], daily_spend: &mut DailySpend) -> Duration {
+    // ⏸ Paused over the control interface: no scan, no buys
+    if control.is_paused() {
+        status_println!("⏸ Trading paused. Send 'resume' to open new trades again.");
+        return Duration::from_secs(config::get_loop_time_seconds());
+    }
+
+    // 🛑 Check if trading is allowed today
+    if !is_trading_day() {
+        println!("⛔ Skipping trading — {} is excluded", chrono::Local::now().weekday());
+        return Duration::from_secs(60 * 60 * 4);
+    }
+
+    // 🌐 No connectivity: skip the whole cycle instead of failing every request in it
+    if let Err(e) = binance.ping().await {
+        println!("🌐 Binance unreachable ({}). Skipping this cycle for {} seconds.", e, CONNECTIVITY_RETRY_SECS);
+        info!("🌐 Binance unreachable ({}). Skipping this cycle for {} seconds.", e, CONNECTIVITY_RETRY_SECS);
+        return Duration::from_secs(CONNECTIVITY_RETRY_SECS);
+    }
+
+    // 🛠 Binance maintenance: every request would fail, so sit this cycle out
+    match binance.system_status().await {
+        Ok(SystemStatus::Maintenance) => {
+            println!("🛠 Binance is under maintenance. Skipping this cycle for {} seconds.", MAINTENANCE_RETRY_SECS);
+            info!("🛠 Binance is under maintenance. Skipping this cycle for {} seconds.", MAINTENANCE_RETRY_SECS);
+            return Duration::from_secs(MAINTENANCE_RETRY_SECS);
+        }
+        Ok(SystemStatus::Normal) => {}
+        Err(e) => {
+            eprintln!("Could not check system status: {}", e);
+        }
+    }
+
+    // 🛑 Safe-mode: refuse to buy if the account holds far more than expected
+    match binance.exceeds_max_account_value(assets).await {
+        Ok(true) => {
+            let loop_time = config::get_loop_time_seconds();
+            println!("⛔ Safe-mode: quote balance above MAX_ACCOUNT_VALUE. Skipping buys for {} seconds.", loop_time);
+            info!("⛔ Safe-mode: quote balance above MAX_ACCOUNT_VALUE. Skipping buys for {} seconds.", loop_time);
+            return Duration::from_secs(loop_time);
+        }
+        Ok(false) => {}
+        Err(e) => {
+            // Fail closed: without the balance we cannot rule out the wrong account.
+            let loop_time = config::get_loop_time_seconds();
+            eprintln!("⛔ Safe-mode: could not check account value ({}). Skipping buys for {} seconds.", e, loop_time);
+            info!("⛔ Safe-mode: could not check account value ({}). Skipping buys for {} seconds.", e, loop_time);
+            return Duration::from_secs(loop_time);
+        }
+    }
+    /*
+    if loss_tracker.is_on_cooldown() {
+        println!("Bot is in global cooldown. Skipping this cycle.");
+        info!("Bot is in global cooldown. Skipping this cycle.");
+        let remaining = loss_tracker
+            .cooldown_until
+            .unwrap()
+            .saturating_duration_since(Instant::now());
+        sleep(remaining).await;
+        continue;
+    }
+
+    if let Ok(true) = binance.should_pause_for_losses().await {
+        println!("⛔ Daily loss threshold reached. Entering cooldown for 24h.");
+        info!("⛔ Daily loss threshold reached. Entering cooldown for 24h.");
+        loss_tracker.cooldown_until = Some(Instant::now() + Duration::from_secs(60 * 60 * 24));
+        sleep(Duration::from_secs(120)).await;
+        continue;
+    }
+ */
+    let signals = match discover_signals(binance, assets, transaction_amounts,
+        //open_orders_clone,
+        TrendDirection::Positive,
+        &MomentumStrategy,
+    ).await {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("❌ Market scan failed: {}. Retrying in 60 seconds.", e);
+            info!("❌ Market scan failed: {}. Retrying in 60 seconds.", e);
+            return Duration::from_secs(60);
+        }
+    };
+
+    // 🔄 Quote rotation: spend from the quote asset with the most buys left before the others
+    let signals = if config::get_quote_rotation() {
+        let mut headroom = HashMap::new();
+        for quote in assets {
+            let (quote_amount, _) = config::get_quote_amount_and_stop_loss(quote);
+            match binance.get_account_balance(quote).await {
+                Ok(balance) if quote_amount > 0.0 => {
+                    headroom.insert(quote.clone(), (balance / quote_amount).floor() as u32);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Could not fetch {} balance for quote rotation: {}", quote, e),
+            }
+        }
+        status_println!("🔄 Quote headroom (buys left): {:?}", headroom);
+        rotate_by_headroom(signals, &headroom, assets)
+    } else {
+        signals
+    };
+
+    let mut summary = CycleSummary { signals: signals.len(), ..CycleSummary::default() };
+    let buy_spacing = Duration::from_millis(config::get_buy_spacing_ms());
+    for (i, signal) in signals.into_iter().enumerate() {
+        space_buy(i, buy_spacing).await;
+        println!(
+            "Signal: {:<12} | Score: {:>6.2} | Growth: {:>5.2}% | Recent: {:>5.2}% | Fluct: {:>5.4} (~{:>4.2}%)",
+            signal.symbol,
+            signal.score,
+            signal.overall_growth,
+            signal.recent_growth,
+            signal.avg_fluct_raw,
+            signal.avg_fluct_pct,
+        );
+        
+
+        if control.is_paused() {
+            println!("⏸ Trading paused mid-cycle. Skipping the remaining signals.");
+            info!("⏸ Trading paused mid-cycle. Skipping the remaining signals.");
+            break;
+        }
+
+        // 💰 Daily budget: stop opening trades once today's buys would exceed MAX_DAILY_SPEND
+        let quote = split_symbol(&signal.symbol, assets).map(|(_, q)| q).unwrap_or_default();
+        let (quote_amount, _) = config::get_quote_amount_and_stop_loss(quote);
+        let budget = config::get_max_daily_spend();
+        let today = Utc::now().date_naive();
+        if !daily_spend.allows(today, quote_amount, budget) {
+            println!("💰 Daily spend budget reached ({:.2} of {:.2} quote). No new trades until tomorrow.", daily_spend.spent, budget);
+            info!("💰 Daily spend budget reached ({:.2} of {:.2} quote). No new trades until tomorrow.", daily_spend.spent, budget);
+            break;
+        }
+
+        //Execute trade and trailing stop logic
+        match binance
+        .execute_trade_with_fallback_stop(
+            &signal.symbol,
+            None,    // no activation price, trail immediately
+        )
+        .await{
+            Ok(spent) => {
+                summary.record_attempt(true);
+                daily_spend.record(today, spent);
+            }
+            Err(e) => {
+                summary.record_attempt(false);
+                eprintln!("❌ Failed to execute for token {} : {}", signal.symbol, e);
+                info!("❌ Failed to execute trade for token {} : {}", signal.symbol, e);
+            }
+        }
+    } 
+    summary.finish(binance.positions().lock().await.len());
+    println!("📋 Cycle summary: {}", summary);
+    info!("📋 Cycle summary: {}", summary);
+
+    Duration::from_secs(config::get_loop_time_seconds())
+}
+
+/// `--once`: a single market pass (and stop-loss pass unless `scan_only`), without the loop's sleep.
+async fn run_once(binance: &Binance, control: &Control, assets: &[String], transaction_amounts: &[f64], scan_only: bool) {
+    let mut daily_spend = DailySpend::new(Utc::now().date_naive());
+    market_cycle(binance, control, assets, transaction_amounts, &mut daily_spend).await;
+    if !scan_only {
+        binance.run_stop_loss_cycle().await;
+    }
+    flush_trade_log();
+}
+
+/// Spawns the stop-loss management task; none in scan-only mode, so existing holdings are left alone.
+fn spawn_stop_loss_loop(binance: &Arc<Binance>, scan_only: bool) -> Option<tokio::task::JoinHandle<()>> {
+    if scan_only {
+        println!("👀 Scan-only mode: stop-loss management is disabled.");
+        info!("👀 Scan-only mode: stop-loss management is disabled.");
+        return None;
+    }
+    let binance = Arc::clone(binance);
+    Some(tokio::spawn(async move {
+        if config::get_stop_loss_stream() {
+            binance.manage_stop_loss_stream().await;
+        } else {
+            binance.manage_stop_loss_limit_loop().await;
+        }
+    }))
+}
+
 #[tokio::main]
 async fn main() {
     println!("Starting progam");
     info!("Starting progam:");
     let _guard = init_tracing(false, Level::INFO);
+    let args: Vec<String> = std::env::args().collect();
+    let once = args.iter().any(|a| a == "--once");
+    let scan_only = args.iter().any(|a| a == "--scan-only" || a == "--no-stop-loss-loop");
+    // `--control stdin` or `--control <socket path>`: accept pause/resume/scan/liquidate commands
+    let control_source = args.iter().position(|a| a == "--control").and_then(|i| args.get(i + 1)).cloned();
+    if let Some(dir) = args.iter().position(|a| a == "--output-dir").and_then(|i| args.get(i + 1)) {
+        config::set_trade_log_folder(dir);
+        println!("Writing trade logs to {}", dir);
+    }
+    if let Some(path) = args.iter().position(|a| a == "--replay").and_then(|i| args.get(i + 1)) {
+        std::process::exit(if replay(path) { 0 } else { 1 });
+    }
+    if args.iter().any(|a| a == "--dry-validate") {
+        std::process::exit(if dry_validate() { 0 } else { 1 });
+    }
+    if args.get(1).map(String::as_str) == Some("simulate") {
+        let ok = simulate(&Binance::new(), &config::get_quote_assets(), &config::get_transaction_amounts(), &args).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    if args.get(1).map(String::as_str) == Some("preflight") {
+        let ok = preflight(&Binance::new()).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
     watch_config(SHARED_CONFIG.clone());
-    let binance = Binance::new();
+    // One client for both loops, so they share its caches, clock offset and position store.
+    let binance = Arc::new(Binance::new());
+    let control = Arc::new(Control::new());
    // let mut loss_tracker = GlobalLossTracker::new(); // Initialize the loss tracker
     // Parse the list of assets from the environment variable QUOTE_ASSETS and transaction amounts from the config.
     let assets = config::get_quote_assets();
@@ -28,86 +486,206 @@ async fn main() {
     info!("Assets to scan: {:?}", assets);
     
 
+    if once {
+        run_once(&binance, &control, &assets, &transaction_amounts, scan_only).await;
+        return;
+    }
+
+    // 🎛 Command interface
+    if let Some(source) = control_source {
+        let (control, binance) = (Arc::clone(&control), Arc::clone(&binance));
+        tokio::spawn(async move {
+            if source == "stdin" {
+                control::run_stdin(control, binance).await;
+            } else {
+                control::run_unix_socket(Path::new(&source), control, binance).await;
+            }
+        });
+    }
+
+    // 📸 Daily account equity snapshot
+    let equity_binance = Arc::clone(&binance);
+    tokio::spawn(async move { equity_binance.equity_snapshot_loop().await });
+
     // Spawn the market-check loop.
+    let market_binance = Arc::clone(&binance);
+    let market_control = Arc::clone(&control);
     let market_check_handle = tokio::spawn(async move {
+        let (binance, control) = (market_binance, market_control);
+        let mut daily_spend = DailySpend::new(Utc::now().date_naive());
         loop {
-            // 🛑 Check if trading is allowed today
-            if !is_trading_day() {
-                println!("⛔ Skipping trading — {} is excluded", chrono::Local::now().weekday());
-                tokio::time::sleep(Duration::from_secs(60 * 60 * 4)).await;
-                continue;
-            }
-            /*
-            if loss_tracker.is_on_cooldown() {
-                println!("Bot is in global cooldown. Skipping this cycle.");
-                info!("Bot is in global cooldown. Skipping this cycle.");
-                let remaining = loss_tracker
-                    .cooldown_until
-                    .unwrap()
-                    .saturating_duration_since(Instant::now());
-                sleep(remaining).await;
-                continue;
+            let wait = market_cycle(&binance, &control, &assets, &transaction_amounts, &mut daily_spend).await;
+            status_println!("-------------------------------------------------------------------------");
+            status_println!("Sleeping for {} seconds before the next iteration...", wait.as_secs());
+            info!("Sleeping for {} seconds before the next iteration...", wait.as_secs());
+            tokio::select! {
+                _ = sleep(wait) => {}
+                _ = control.scan_requested() => status_println!("🎛 Scan requested, starting the next iteration now"),
             }
-        
-            if let Ok(true) = binance.should_pause_for_losses().await {
-                println!("⛔ Daily loss threshold reached. Entering cooldown for 24h.");
-                info!("⛔ Daily loss threshold reached. Entering cooldown for 24h.");
-                loss_tracker.cooldown_until = Some(Instant::now() + Duration::from_secs(60 * 60 * 24));
-                sleep(Duration::from_secs(120)).await;
-                continue;
-            }
-         */
-            let signals = discover_signals(&binance,&assets, &transaction_amounts,
-                //open_orders_clone,
-                TrendDirection::Positive,
-            ).await;
-
-            for signal in signals {
-                println!(
-                    "Signal: {:<12} | Growth: {:>5.2}% | Recent: {:>5.2}% | Fluct: {:>5.4} (~{:>4.2}%)",
-                    signal.symbol,
-                    signal.overall_growth,
-                    signal.recent_growth,
-                    signal.avg_fluct_raw,
-                    signal.avg_fluct_pct,
-                );
-                
-
-                //Execute trade and trailing stop logic
-                if let Err(e) = binance
-                .execute_trade_with_fallback_stop(
-                    &signal.symbol,
-                    None,    // no activation price, trail immediately
-                )
-                .await{
-                    eprintln!("❌ Failed to execute for token {} : {}", signal.symbol, e);
-                    info!("❌ Failed to execute trade for token {} : {}", signal.symbol, e);
-                }
-            } 
-            
-            // Extract values from the shared config
-            // Extract values and drop the guard immediately:
-            let loop_time = config::get_loop_time_seconds();
-            println!("-------------------------------------------------------------------------");
-            println!("Sleeping for {} seconds before the next iteration...", loop_time);
-            info!("Sleeping for {} seconds before the next iteration...", loop_time);
-            // Now call sleep without holding the lock:
-            sleep(Duration::from_secs(loop_time)).await;
         }});    
     // 🛡️ Stop-loss check loop
-    let stop_loss_loop = {
-        let binance2 = Binance::new();
-        tokio::spawn(async move {
-            binance2.manage_stop_loss_limit_loop().await;
-        })
-    };
+    let stop_loss_loop = spawn_stop_loss_loop(&binance, scan_only);
 
     // ⏱ Run both loops
-    let result = tokio::join!(market_check_handle, stop_loss_loop);
-    if let Err(e) = result.1 {
-        eprintln!("❌ stop_loss_loop panicked: {:?}", e);
-    }
-    if let Err(e) = result.0 {
+    if let Some(stop_loss_loop) = stop_loss_loop {
+        let result = tokio::join!(market_check_handle, stop_loss_loop);
+        if let Err(e) = result.1 {
+            eprintln!("❌ stop_loss_loop panicked: {:?}", e);
+        }
+        if let Err(e) = result.0 {
+            eprintln!("❌ market_check_handle panicked: {:?}", e);
+        }
+    } else if let Err(e) = market_check_handle.await {
         eprintln!("❌ market_check_handle panicked: {:?}", e);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stock_pred::api::binance::mock::local_binance_recording;
+
+    fn account(json: &str) -> AccountInfo {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn read_only_keys_fail_preflight_and_trading_keys_pass() {
+        let quotes = vec!["USDT".to_string()];
+        let balances = r#"[{"asset":"USDT","free":"120.5","locked":"0"}]"#;
+        let read_only = account(&format!(r#"{{"canTrade":false,"permissions":["SPOT"],"balances":{}}}"#, balances));
+        assert!(!account_checks(&read_only, &quotes));
+
+        let trading = account(&format!(r#"{{"canTrade":true,"permissions":["SPOT"],"balances":{}}}"#, balances));
+        assert!(account_checks(&trading, &quotes));
+        assert!(!account_checks(&trading, &["FDUSD".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn three_spaced_buys_take_at_least_two_spacings() {
+        let spacing = Duration::from_millis(500);
+        let started = std::time::Instant::now();
+        for i in 0..3 {
+            space_buy(i, spacing).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(1000), "{:?}", started.elapsed());
+
+        let started = std::time::Instant::now();
+        space_buy(0, spacing).await;
+        space_buy(1, Duration::ZERO).await;
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn a_single_pass_returns_without_sleeping() {
+        // Paused, so the pass touches no network; the loop would sleep LOOP_TIME_SECONDS here.
+        let control = Control::new();
+        control.apply(&control::Command::Pause);
+        let (binance, assets) = (Binance::new(), vec!["USDT".to_string()]);
+        let pass = run_once(&binance, &control, &assets, &[10.0], true);
+        assert!(tokio::time::timeout(Duration::from_secs(5), pass).await.is_ok());
+        assert!(config::get_loop_time_seconds() > 5);
+    }
+
+    #[tokio::test]
+    async fn scan_only_mode_starts_no_stop_loss_task() {
+        let binance = Arc::new(Binance::new());
+        assert!(spawn_stop_loss_loop(&binance, true).is_none());
+
+        let stop_loss_loop = spawn_stop_loss_loop(&binance, false).expect("the stop-loss task is started");
+        stop_loss_loop.abort();
+        assert!(stop_loss_loop.await.unwrap_err().is_cancelled());
+    }
+
+    /// Serialises the tests that drive `market_cycle`, since they share the global config.
+    static CYCLE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Runs one `market_cycle` against a mock serving `routes` on a day that is never excluded,
+    /// with MAX_ACCOUNT_VALUE at `max_account_value`; returns the wait and the requests it made.
+    async fn cycle_against(routes: Vec<(&'static str, String)>, max_account_value: f64) -> (Duration, Vec<String>) {
+        let _serial = CYCLE_LOCK.lock().await;
+        let (excluded_days, max_value) = {
+            let mut cfg = SHARED_CONFIG.write().unwrap();
+            (
+                std::mem::take(&mut cfg.excluded_days),
+                std::mem::replace(&mut cfg.max_account_value, max_account_value),
+            )
+        };
+        let (binance, requests) = local_binance_recording(routes).await;
+        let assets = vec!["USDC".to_string()];
+        let mut daily_spend = DailySpend::new(Utc::now().date_naive());
+        let wait = market_cycle(&binance, &Control::new(), &assets, &[10.0], &mut daily_spend).await;
+        {
+            let mut cfg = SHARED_CONFIG.write().unwrap();
+            cfg.excluded_days = excluded_days;
+            cfg.max_account_value = max_value;
+        }
+        let requests = requests.lock().unwrap().clone();
+        (wait, requests)
+    }
+
+    /// True when the cycle went on to discovery or placed an order.
+    fn scanned_or_traded(requests: &[String]) -> bool {
+        requests
+            .iter()
+            .any(|r| ["/ticker/24hr", "/klines", "/order"].iter().any(|path| r.contains(path)))
+    }
+
+    #[tokio::test]
+    async fn an_account_above_the_max_value_skips_discovery_and_buys() {
+        let routes = vec![
+            ("/ping", "{}".to_string()),
+            ("/system/status", r#"{"status":0,"msg":"normal"}"#.to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDC","free":"5000","locked":"0"}]}"#.to_string()),
+        ];
+        let (wait, requests) = cycle_against(routes.clone(), 1000.0).await;
+        assert_eq!(wait, Duration::from_secs(config::get_loop_time_seconds()));
+        assert!(requests.iter().any(|r| r.contains("/account")), "{:?}", requests);
+        assert!(!scanned_or_traded(&requests), "{:?}", requests);
+
+        // Under the limit the same account goes on to scan.
+        let (_, requests) = cycle_against(routes, 10_000.0).await;
+        assert!(scanned_or_traded(&requests), "{:?}", requests);
+    }
+
+    #[tokio::test]
+    async fn maintenance_skips_discovery_and_buys() {
+        let routes = vec![
+            ("/ping", "{}".to_string()),
+            ("/system/status", r#"{"status":1,"msg":"system maintenance"}"#.to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDC","free":"100","locked":"0"}]}"#.to_string()),
+        ];
+        let (wait, requests) = cycle_against(routes, 0.0).await;
+        assert_eq!(wait, Duration::from_secs(MAINTENANCE_RETRY_SECS));
+        assert!(requests.iter().any(|r| r.contains("/sapi/v1/system/status")), "{:?}", requests);
+        assert!(!requests.iter().any(|r| r.contains("/account")), "{:?}", requests);
+        assert!(!scanned_or_traded(&requests), "{:?}", requests);
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_binance_skips_the_cycle() {
+        // No /ping route: the mock answers it with a 404.
+        let routes = vec![
+            ("/system/status", r#"{"status":0,"msg":"normal"}"#.to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDC","free":"100","locked":"0"}]}"#.to_string()),
+            ("/ticker/24hr", "[]".to_string()),
+        ];
+        let (wait, requests) = cycle_against(routes, 0.0).await;
+        assert_eq!(wait, Duration::from_secs(CONNECTIVITY_RETRY_SECS));
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert!(requests[0].contains("/api/v3/ping"), "{:?}", requests);
+        assert!(!scanned_or_traded(&requests), "{:?}", requests);
+    }
+
+    #[tokio::test]
+    async fn a_single_simulation_pass_fails_instead_of_retrying_a_price_fetch() {
+        // No /ticker/price route: the price fetch gets a 404.
+        let (binance, requests) = local_binance_recording(vec![]).await;
+        let args: Vec<String> = ["pred", "simulate", "--once"].iter().map(|a| a.to_string()).collect();
+        let assets = vec!["USDC".to_string()];
+        let pass = simulate(&binance, &assets, &[10.0], &args);
+        let ok = tokio::time::timeout(Duration::from_secs(5), pass).await.expect("no 60 second retry");
+        assert!(!ok);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+}
@@ -1,6 +1,8 @@
 use dotenv::dotenv;
 use stock_pred::api::binance::Binance;
 use stock_pred::config::SHARED_CONFIG;
+use stock_pred::math::percent_change;
+use stock_pred::trading::discovery::tail_window;
 
 #[tokio::main]
 async fn main() {
@@ -43,29 +45,33 @@ async fn main() {
     for token_symbol in filtered_tickers {
         match binance.get_klines(&token_symbol.symbol, interval, lookback_period).await {
             Ok(klines) => {
-                if klines.len() < lookback_period as usize {
+                // Delisted or halted symbols can return no candles at all; two are needed for the trend check.
+                if klines.len() < (lookback_period as usize).max(2) {
                     eprintln!("Not enough data for {}.", token_symbol.symbol);
                     continue;
                 }
                 let first_candle = &klines[0];
                 let second_last_candle = &klines[klines.len() - 2];
                 let last_candle = &klines[klines.len() - 1];
-                let open_price_str = first_candle[1].as_str().unwrap_or("0");
-                let last_close_str = last_candle[4].as_str().unwrap_or("0");
-                let prev_close_str = second_last_candle[4].as_str().unwrap_or("0");
+                let open_price_str = first_candle.get(1).and_then(|v| v.as_str()).unwrap_or("0");
+                let last_close_str = last_candle.get(4).and_then(|v| v.as_str()).unwrap_or("0");
+                let prev_close_str = second_last_candle.get(4).and_then(|v| v.as_str()).unwrap_or("0");
     
                 if let (Ok(open_price), Ok(last_close), Ok(prev_close)) = (
                     open_price_str.parse::<f64>(),
                     last_close_str.parse::<f64>(),
                     prev_close_str.parse::<f64>(),
                 ) {
-                    let overall_growth = ((last_close - open_price) / open_price) * 100.0;
+                    let overall_growth = percent_change(open_price, last_close).unwrap_or(0.0);
                     let current_trend_up = last_close > prev_close;
     
                     if overall_growth >= 10.0 && current_trend_up {
                         // Use last_hours_period for recent trend analysis.
                         let recent_range = last_hours_period as usize;
-                        let last_recent_candles = &klines[klines.len() - recent_range..];
+                        let Some(last_recent_candles) = tail_window(&klines, recent_range) else {
+                            eprintln!("Not enough recent data for {}.", token_symbol.symbol);
+                            continue;
+                        };
                         let first_recent_open_str = last_recent_candles[0][1].as_str().unwrap_or("0");
                         let last_recent_close_str = last_recent_candles[last_recent_candles.len() - 1][4].as_str().unwrap_or("0");
     
@@ -73,7 +79,7 @@ async fn main() {
                             first_recent_open_str.parse::<f64>(),
                             last_recent_close_str.parse::<f64>(),
                         ) {
-                            let recent_growth = ((last_recent_close - first_recent_open) / first_recent_open) * 100.0;
+                            let recent_growth = percent_change(first_recent_open, last_recent_close).unwrap_or(0.0);
                             if recent_growth > 0.0 {
                                 println!(
                                     "🚀 {} is pumping with {:.2}% overall growth in the last {} hours and {:.2}% growth in the last {} hours!",
@@ -85,8 +91,8 @@ async fn main() {
                                 let mut raw_fluctuations = Vec::new();
                                 let mut percent_fluctuations = Vec::new();
                                 for candle in &klines {
-                                    let high_str = candle[2].as_str().unwrap_or("0");
-                                    let low_str = candle[3].as_str().unwrap_or("0");
+                                    let high_str = candle.get(2).and_then(|v| v.as_str()).unwrap_or("0");
+                                    let low_str = candle.get(3).and_then(|v| v.as_str()).unwrap_or("0");
                                     if let (Ok(high), Ok(low)) = (high_str.parse::<f64>(), low_str.parse::<f64>()) {
                                         let diff = high - low;
                                         raw_fluctuations.push(diff);
@@ -1,12 +1,47 @@
-use chrono::{DateTime, Datelike, NaiveDate, Utc};
-use serde::Deserialize;
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use csv::Reader;
-use stock_pred::config::get_trade_log_folder;
+use stock_pred::api::binance::Binance;
+use stock_pred::config::{get_report_timezone, get_trade_log_folder};
+use stock_pred::logging::breadth_log_path;
+use stock_pred::math::percent_change;
+use chrono_tz::Tz;
+use once_cell::sync::Lazy;
 use itertools::Itertools;
 use chrono::Timelike;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+
+/// Timezone used for day/week/month/hour buckets, from REPORT_TIMEZONE (defaults to UTC).
+static REPORT_TZ: Lazy<Tz> = Lazy::new(|| {
+    let name = get_report_timezone();
+    name.parse::<Tz>().unwrap_or_else(|_| {
+        eprintln!("⚠️ Unknown REPORT_TIMEZONE '{}', using UTC", name);
+        Tz::UTC
+    })
+});
+
+/// Converts a log timestamp into the report timezone before bucketing.
+pub fn to_report_tz(timestamp: &DateTime<Utc>) -> DateTime<Tz> {
+    timestamp.with_timezone(&*REPORT_TZ)
+}
+
+/// UTC dates (i.e. daily log files) that overlap `date` in the report timezone.
+fn utc_dates_for_report_day(date: NaiveDate) -> Vec<NaiveDate> {
+    let start = date.and_hms_opt(0, 0, 0).unwrap();
+    let end = date.and_hms_opt(23, 59, 59).unwrap();
+    let to_utc = |naive: chrono::NaiveDateTime| {
+        REPORT_TZ
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc).date_naive())
+            .unwrap_or(naive.date())
+    };
+    let (first, last) = (to_utc(start), to_utc(end));
+    first.iter_days().take_while(|d| *d <= last).collect()
+}
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct TradeLogEntry {
@@ -27,24 +62,29 @@ pub struct RealizedTrade {
     pub qty: f64,
     pub profit: f64,
     pub profit_pct: f64,
+    /// When the position was closed (the SELL row).
     pub timestamp: DateTime<Utc>,
+    /// When the position was opened (the BUY row).
+    pub entry_timestamp: DateTime<Utc>,
+    /// Holding time from the BUY to the SELL row.
+    pub duration: chrono::Duration,
     pub trend: String,
 }
 
+fn read_trades_from_file(path: &Path, trades: &mut Vec<TradeLogEntry>) {
+    if let Ok(file) = fs::File::open(path) {
+        let mut rdr = Reader::from_reader(file);
+        trades.extend(rdr.deserialize::<TradeLogEntry>().flatten());
+    }
+}
+
 pub fn load_trades_from_dir(folder: &Path) -> Vec<TradeLogEntry> {
     let mut trades: Vec<TradeLogEntry> = vec![];
-    println!("📁 Scanning {:?}", folder);
+    eprintln!("📁 Scanning {:?}", folder);
     if let Ok(entries) = fs::read_dir(folder) {
         for entry in entries.flatten() {
-            if entry.path().extension().map_or(false, |ext| ext == "csv") {
-                if let Ok(file) = fs::File::open(entry.path()) {
-                    let mut rdr = Reader::from_reader(file);
-                    for result in rdr.deserialize::<TradeLogEntry>() {
-                        if let Ok(entry) = result {
-                            trades.push(entry);
-                        }
-                    }
-                }
+            if entry.path().extension().is_some_and(|ext| ext == "csv") {
+                read_trades_from_file(&entry.path(), &mut trades);
             }
         }
     }
@@ -52,39 +92,151 @@ pub fn load_trades_from_dir(folder: &Path) -> Vec<TradeLogEntry> {
     trades
 }
 
+/// Loads only the `YYYY-MM-DD.csv` log for `date` instead of the whole folder. Round trips
+/// that span midnight need the earlier days too; see `realized_trades_between`.
+pub fn load_trades_for_date(folder: &Path, date: NaiveDate) -> Vec<TradeLogEntry> {
+    let mut trades: Vec<TradeLogEntry> = vec![];
+    let path = folder.join(format!("{}.csv", date.format("%Y-%m-%d")));
+    eprintln!("📄 Reading {:?}", path);
+    read_trades_from_file(&path, &mut trades);
+    trades.sort_by_key(|e| e.timestamp);
+    trades
+}
+
+/// Date of the oldest `YYYY-MM-DD.csv` log in `folder`, from the file names alone.
+pub fn earliest_log_date(folder: &Path) -> Option<NaiveDate> {
+    fs::read_dir(folder)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            NaiveDate::parse_from_str(name.strip_suffix(".csv")?, "%Y-%m-%d").ok()
+        })
+        .min()
+}
+
+/// Trades closed from `from` to `to` (inclusive) in the report timezone. Reads the UTC logs
+/// overlapping that window, then earlier days one at a time while a SELL in the window still
+/// has no BUY, so positions held over midnight pair up as they do with the full log.
+pub fn realized_trades_between(folder: &Path, from: NaiveDate, to: NaiveDate) -> Vec<RealizedTrade> {
+    realized_trades_loading(folder, from, to, |d| load_trades_for_date(folder, d))
+}
+
+/// `realized_trades_between` reading each day's log through `load`.
+fn realized_trades_loading(
+    folder: &Path,
+    from: NaiveDate,
+    to: NaiveDate,
+    mut load: impl FnMut(NaiveDate) -> Vec<TradeLogEntry>,
+) -> Vec<RealizedTrade> {
+    let (Some(&first), Some(&last)) = (utc_dates_for_report_day(from).first(), utc_dates_for_report_day(to).last()) else {
+        return Vec::new();
+    };
+    let mut trades: Vec<TradeLogEntry> = first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .flat_map(&mut load)
+        .collect();
+
+    let earliest = earliest_log_date(folder).unwrap_or(first);
+    let mut day = first.pred_opt();
+    while let Some(d) = day.filter(|d| *d >= earliest) {
+        let unmatched_sell = find_orphans(&trades)
+            .iter()
+            .any(|o| o.kind == OrphanKind::SellWithoutBuy && o.entry.timestamp.date_naive() >= first);
+        if !unmatched_sell {
+            break;
+        }
+        trades.extend(load(d));
+        day = d.pred_opt();
+    }
+
+    trades.sort_by_key(|e| e.timestamp);
+    generate_realized_report(&trades)
+        .into_iter()
+        .filter(|t| (from..=to).contains(&to_report_tz(&t.timestamp).date_naive()))
+        .collect()
+}
+
+/// Trades closed on `date` in the report timezone.
+pub fn realized_trades_for_day(folder: &Path, date: NaiveDate) -> Vec<RealizedTrade> {
+    realized_trades_between(folder, date, date)
+}
+
+/// A buy price must be positive and finite for `profit_pct` to be meaningful.
+fn is_valid_price(price: f64) -> bool {
+    price.is_finite() && price > 0.0
+}
+
+/// Log actions that sell part of an open position: max-position trims, RSI trims and
+/// take-profit limit sells.
+const PARTIAL_EXIT_ACTIONS: [&str; 2] = ["TRIM", "TAKE_PROFIT"];
+
+/// Open BUY of one symbol while pairing the log: the last SET seen after it and the quantity
+/// not yet sold by partial exits.
+struct OpenTrade {
+    buy: TradeLogEntry,
+    set: Option<TradeLogEntry>,
+    remaining: f64,
+}
+
+fn realized_row(entry: &TradeLogEntry, buy: &TradeLogEntry, sell_price: f64, qty: f64) -> RealizedTrade {
+    RealizedTrade {
+        symbol: entry.symbol.clone(),
+        buy_price: buy.price,
+        sell_price,
+        qty,
+        profit: (sell_price - buy.price) * qty,
+        profit_pct: percent_change(buy.price, sell_price).unwrap_or(0.0),
+        timestamp: entry.timestamp,
+        entry_timestamp: buy.timestamp,
+        duration: entry.timestamp - buy.timestamp,
+        trend: entry.action.clone(),
+    }
+}
+
+/// Pairs BUY/SELL rows into realized trades. TRIM and TAKE_PROFIT rows are realized as their
+/// own rows at their logged price and quantity, and the closing SELL books only what is left.
 pub fn generate_realized_report(trades: &[TradeLogEntry]) -> Vec<RealizedTrade> {
     let mut result = vec![];
-    let mut state: HashMap<String, (Option<TradeLogEntry>, Option<TradeLogEntry>)> = HashMap::new();
+    let mut state: HashMap<String, OpenTrade> = HashMap::new();
 
     for entry in trades {
         match entry.action.as_str() {
             "BUY" => {
-                state.insert(entry.symbol.clone(), (Some(entry.clone()), None));
+                state.insert(entry.symbol.clone(), OpenTrade { buy: entry.clone(), set: None, remaining: entry.qty });
             }
             "SET_" | "SET"  => {
-                if let Some((Some(buy), _)) = state.get(&entry.symbol) {
-                    if entry.timestamp > buy.timestamp {
-                        state.insert(entry.symbol.clone(), (Some(buy.clone()), Some(entry.clone())));
+                if let Some(open) = state.get_mut(&entry.symbol) {
+                    if entry.timestamp > open.buy.timestamp {
+                        open.set = Some(entry.clone());
+                    }
+                }
+            }
+            action if PARTIAL_EXIT_ACTIONS.contains(&action) => {
+                if let Some(open) = state.get_mut(&entry.symbol) {
+                    let qty = entry.qty.min(open.remaining);
+                    if is_valid_price(open.buy.price) && qty > 0.0 {
+                        result.push(realized_row(entry, &open.buy, entry.price, qty));
+                        open.remaining -= qty;
                     }
                 }
             }
             "SELL" => {
-                if let Some((Some(buy), Some(set))) = state.get(&entry.symbol) {
-                    let sell_price = set.stop_loss;
-                    let qty = buy.qty;
-                    let profit = (sell_price - buy.price) * qty;
-                    let profit_pct = ((sell_price / buy.price) - 1.0) * 100.0;
-
-                    result.push(RealizedTrade {
-                        symbol: entry.symbol.clone(),
-                        buy_price: buy.price,
-                        sell_price,
-                        qty,
-                        profit,
-                        profit_pct,
-                        timestamp: entry.timestamp,
-                        trend: entry.action.clone(),
-                    });
+                if let Some(OpenTrade { buy, set: Some(set), remaining }) = state.get(&entry.symbol) {
+                    if !is_valid_price(buy.price) {
+                        eprintln!(
+                            "⚠️ Skipping invalid trade: {} bought @ {} on {}",
+                            entry.symbol,
+                            buy.price,
+                            buy.timestamp.format("%Y-%m-%d %H:%M")
+                        );
+                        state.remove(&entry.symbol);
+                        continue;
+                    }
+                    if *remaining > 0.0 {
+                        result.push(realized_row(entry, buy, set.stop_loss, *remaining));
+                    }
                 }
                 state.remove(&entry.symbol);
             }
@@ -96,9 +248,14 @@ pub fn generate_realized_report(trades: &[TradeLogEntry]) -> Vec<RealizedTrade>
 }
 
 pub fn summarize_by_day(trades: &[RealizedTrade]) -> HashMap<NaiveDate, (f64, f64)> {
+    summarize_by_day_in(trades, *REPORT_TZ)
+}
+
+/// `summarize_by_day` with the days taken in `tz` instead of REPORT_TIMEZONE.
+pub fn summarize_by_day_in(trades: &[RealizedTrade], tz: Tz) -> HashMap<NaiveDate, (f64, f64)> {
     let mut map = HashMap::new();
     for trade in trades {
-        let date = trade.timestamp.date_naive();
+        let date = trade.timestamp.with_timezone(&tz).date_naive();
         let entry = map.entry(date).or_insert((0.0, 0.0));
         entry.0 += trade.profit;
         entry.1 += trade.qty;
@@ -109,7 +266,7 @@ pub fn summarize_by_day(trades: &[RealizedTrade]) -> HashMap<NaiveDate, (f64, f6
 pub fn summarize_by_week(trades: &[RealizedTrade]) -> HashMap<(i32, u32), (f64, f64)> {
     let mut map = HashMap::new();
     for trade in trades {
-        let week = trade.timestamp.iso_week();
+        let week = to_report_tz(&trade.timestamp).iso_week();
         let entry = map.entry((week.year(), week.week())).or_insert((0.0, 0.0));
         entry.0 += trade.profit;
         entry.1 += trade.qty;
@@ -120,7 +277,8 @@ pub fn summarize_by_week(trades: &[RealizedTrade]) -> HashMap<(i32, u32), (f64,
 pub fn summarize_by_month(trades: &[RealizedTrade]) -> HashMap<(i32, u32), (f64, f64)> {
     let mut map = HashMap::new();
     for trade in trades {
-        let key = (trade.timestamp.year(), trade.timestamp.month());
+        let local = to_report_tz(&trade.timestamp);
+        let key = (local.year(), local.month());
         let entry = map.entry(key).or_insert((0.0, 0.0));
         entry.0 += trade.profit;
         entry.1 += trade.qty;
@@ -128,56 +286,72 @@ pub fn summarize_by_month(trades: &[RealizedTrade]) -> HashMap<(i32, u32), (f64,
     map
 }
 
+/// Realized round trips of `symbol`, paired the same way as `generate_realized_report`.
+pub fn realized_trades_for_symbol(symbol: &str, trades: &[TradeLogEntry]) -> Vec<RealizedTrade> {
+    let entries: Vec<TradeLogEntry> = trades.iter().filter(|t| t.symbol == symbol).cloned().collect();
+    generate_realized_report(&entries)
+}
+
 pub fn print_trades_for_symbol(symbol: &str, trades: &[TradeLogEntry]) {
     println!("\n🔍 Realized trades for token: {}\n", symbol);
 
-    let mut buy: Option<&TradeLogEntry> = None;
-    let mut set: Option<&TradeLogEntry> = None;
-    let mut total_profit = 0.0;
-
-    for trade in trades.iter().filter(|t| t.symbol == symbol) {
-        match trade.action.as_str() {
-            "BUY" => {
-                buy = Some(trade);
-                set = None;
-            }
-            "SET_" | "SET" => {
-                if let Some(b) = buy {
-                    if trade.timestamp > b.timestamp {
-                        set = Some(trade);
-                    }
-                }
-            }
-            "SELL" => {
-                if let (Some(b), Some(s)) = (buy, set) {
-                    let sell_price = s.stop_loss;
-                    let qty = b.qty;
-                    let profit = (sell_price - b.price) * qty;
-                    let profit_pct = ((sell_price / b.price) - 1.0) * 100.0;
-                    total_profit += profit;
-
-                    println!(
-                        "📅 {} → {} | 🟢 Buy @ {:.5} → Sell @ {:.5} | Qty: {:<7.4} | Profit: {:>6.2} USDC ({:+.2}%)",
-                        b.timestamp.format("%Y-%m-%d %H:%M"),
-                        trade.timestamp.format("%Y-%m-%d %H:%M"),
-                        b.price,
-                        sell_price,
-                        qty,
-                        profit,
-                        profit_pct
-                    );
-                }
-                buy = None;
-                set = None;
-            }
-            _ => {}
-        }
+    let realized = realized_trades_for_symbol(symbol, trades);
+    for t in &realized {
+        println!(
+            "📅 {} → {} | 🟢 Buy @ {:.5} → Sell @ {:.5} | Qty: {:<7.4} | Profit: {:>6.2} USDC ({:+.2}%)",
+            t.entry_timestamp.format("%Y-%m-%d %H:%M"),
+            t.timestamp.format("%Y-%m-%d %H:%M"),
+            t.buy_price,
+            t.sell_price,
+            t.qty,
+            t.profit,
+            t.profit_pct
+        );
     }
 
+    let total_profit: f64 = realized.iter().map(|t| t.profit).sum();
     println!("\n💰 Total profit on {}: {:.2} USDC", symbol, total_profit);
 }
 
-fn print_grouped_summary<F, K>(trades: &[RealizedTrade], key_fn: F)   where F: Fn(&RealizedTrade) -> K,  K: std::cmp::Ord + std::hash::Hash + std::fmt::Display,{
+/// Output format for the default summary, selected with `--format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PeriodSummary {
+    pub period: String,
+    pub profit: f64,
+    pub avg_pct: f64,
+    pub trades: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GlobalMetrics {
+    pub total_trades: usize,
+    pub avg_win: f64,
+    pub win_count: usize,
+    pub avg_loss: f64,
+    pub loss_count: usize,
+    pub win_rate: f64,
+    /// Average holding time in minutes over all trades, winners and losers.
+    pub avg_hold_minutes: f64,
+    pub avg_win_hold_minutes: f64,
+    pub avg_loss_hold_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryReport {
+    pub daily: Vec<PeriodSummary>,
+    pub weekly: Vec<PeriodSummary>,
+    pub monthly: Vec<PeriodSummary>,
+    pub global: GlobalMetrics,
+}
+
+fn grouped_summary<F, K>(trades: &[RealizedTrade], key_fn: F) -> Vec<PeriodSummary>   where F: Fn(&RealizedTrade) -> K,  K: std::cmp::Ord + std::hash::Hash + std::fmt::Display,{
         let mut grouped: HashMap<K, Vec<&RealizedTrade>> = HashMap::new();
         for trade in trades {
             grouped.entry(key_fn(trade)).or_default().push(trade);
@@ -186,28 +360,57 @@ fn print_grouped_summary<F, K>(trades: &[RealizedTrade], key_fn: F)   where F: F
         let mut sorted: Vec<_> = grouped.into_iter().collect();
         sorted.sort_by(|a, b| a.0.cmp(&b.0));
 
-        for (key, group) in sorted {
-            let profit: f64 = group.iter().map(|t| t.profit).sum();
-            let avg_pct: f64 = group.iter().map(|t| t.profit_pct).sum::<f64>() / group.len() as f64;
-            let color = if avg_pct >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        sorted
+            .into_iter()
+            .map(|(key, group)| PeriodSummary {
+                period: key.to_string(),
+                profit: group.iter().map(|t| t.profit).sum(),
+                avg_pct: group.iter().map(|t| t.profit_pct).sum::<f64>() / group.len() as f64,
+                trades: group.len(),
+            })
+            .collect()
+}
+
+fn print_grouped_summary<F, K>(trades: &[RealizedTrade], key_fn: F)   where F: Fn(&RealizedTrade) -> K,  K: std::cmp::Ord + std::hash::Hash + std::fmt::Display,{
+        for summary in grouped_summary(trades, key_fn) {
+            let color = if summary.avg_pct >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
             let reset = "\x1b[0m";
-            println!("{} → Profit: {:.2} USDC → W/L: {}{:+.1}%{}",key, profit, color, avg_pct, reset);
+            println!("{} → Profit: {:.2} USDC → W/L: {}{:+.1}%{}",summary.period, summary.profit, color, summary.avg_pct, reset);
         }
 }
 
-pub fn compute_global_win_loss_averages(trades: &[RealizedTrade]) {
+fn daily_key(t: &RealizedTrade) -> NaiveDate {
+    to_report_tz(&t.timestamp).date_naive()
+}
+
+fn weekly_key(t: &RealizedTrade) -> String {
+    let w = to_report_tz(&t.timestamp).iso_week();
+    format!("Week {}-W{:02}", w.year(), w.week())
+}
+
+fn monthly_key(t: &RealizedTrade) -> String {
+    let local = to_report_tz(&t.timestamp);
+    format!("{}-{:02}", local.year(), local.month())
+}
+
+pub fn global_metrics(trades: &[RealizedTrade]) -> GlobalMetrics {
     let mut win_total = 0.0;
     let mut win_count = 0;
     let mut loss_total = 0.0;
     let mut loss_count = 0;
+    let mut win_hold = 0.0;
+    let mut loss_hold = 0.0;
 
     for trade in trades {
+        let hold = trade.duration.num_seconds() as f64 / 60.0;
         if trade.profit >= 0.0 {
             win_total += trade.profit;
             win_count += 1;
+            win_hold += hold;
         } else {
             loss_total += trade.profit;
             loss_count += 1;
+            loss_hold += hold;
         }
     }
 
@@ -219,46 +422,192 @@ pub fn compute_global_win_loss_averages(trades: &[RealizedTrade]) {
         0.0
     };
 
+    GlobalMetrics {
+        total_trades: trades.len(),
+        avg_win,
+        win_count,
+        avg_loss,
+        loss_count,
+        win_rate,
+        avg_hold_minutes: if trades.is_empty() { 0.0 } else { (win_hold + loss_hold) / trades.len() as f64 },
+        avg_win_hold_minutes: if win_count > 0 { win_hold / win_count as f64 } else { 0.0 },
+        avg_loss_hold_minutes: if loss_count > 0 { loss_hold / loss_count as f64 } else { 0.0 },
+    }
+}
+
+/// Formats minutes as "2h 05m" for hold-time lines.
+fn format_hold(minutes: f64) -> String {
+    let total = minutes.round() as i64;
+    format!("{}h {:02}m", total / 60, total % 60)
+}
+
+/// Minutes in one kline interval such as "15m", "1h", "4h", "1d" or "1w"; None if unrecognised.
+/// "1M" (a month) is taken as 30 days.
+pub fn interval_minutes(interval: &str) -> Option<f64> {
+    let split = interval.find(|c: char| !c.is_ascii_digit())?;
+    let (count, unit) = interval.split_at(split);
+    let count = count.parse::<f64>().ok().filter(|c| *c > 0.0)?;
+    let unit_minutes = match unit {
+        "m" => 1.0,
+        "h" => 60.0,
+        "d" => 60.0 * 24.0,
+        "w" => 60.0 * 24.0 * 7.0,
+        "M" => 60.0 * 24.0 * 30.0,
+        _ => return None,
+    };
+    Some(count * unit_minutes)
+}
+
+fn parse_interval(interval: &str) -> Result<String, String> {
+    interval_minutes(interval)
+        .map(|_| interval.to_string())
+        .ok_or_else(|| format!("invalid interval '{}'; use e.g. 15m, 1h, 4h, 1d", interval))
+}
+
+/// Rounds a hold time to the unit a person would say: "≈45m", "≈3h", "≈1.5d".
+pub fn humanize_hold(minutes: f64) -> String {
+    if minutes < 60.0 {
+        format!("≈{:.0}m", minutes)
+    } else if minutes < 60.0 * 24.0 {
+        format!("≈{:.0}h", minutes / 60.0)
+    } else {
+        let days = minutes / (60.0 * 24.0);
+        let rounded = (days * 10.0).round() / 10.0;
+        if rounded.fract() == 0.0 { format!("≈{:.0}d", rounded) } else { format!("≈{:.1}d", rounded) }
+    }
+}
+
+/// "≈3h average hold (≈3.0 candles of 1h)" for the global summary.
+pub fn average_hold_line(avg_hold_minutes: f64, interval: &str) -> String {
+    let mut line = format!("{} average hold", humanize_hold(avg_hold_minutes));
+    if let Some(candle) = interval_minutes(interval) {
+        line.push_str(&format!(" (≈{:.1} candles of {})", avg_hold_minutes / candle, interval));
+    }
+    line
+}
+
+pub fn compute_global_win_loss_averages(trades: &[RealizedTrade], interval: &str) {
+    let metrics = global_metrics(trades);
+
     println!("\n-------------------------------------------------------------------------:");
     println!("📊 Global Profit Metrics:");
-    println!("🔹 Average Win:  {:.2} USDC ({} wins)", avg_win, win_count);
-    println!("🔸 Average Loss: {:.2} USDC ({} losses)", avg_loss, loss_count);
-    println!("📈 Win Rate:     {:.1}% → {}/{}", win_rate, win_count, trades.len());
+    println!("🔹 Average Win:  {:.2} USDC ({} wins)", metrics.avg_win, metrics.win_count);
+    println!("🔸 Average Loss: {:.2} USDC ({} losses)", metrics.avg_loss, metrics.loss_count);
+    println!("📈 Win Rate:     {:.1}% → {}/{}", metrics.win_rate, metrics.win_count, metrics.total_trades);
+    println!(
+        "⏳ Avg Hold:     {} (wins {}, losses {})",
+        format_hold(metrics.avg_hold_minutes),
+        format_hold(metrics.avg_win_hold_minutes),
+        format_hold(metrics.avg_loss_hold_minutes)
+    );
+    if metrics.total_trades > 0 {
+        println!("⏳ {}", average_hold_line(metrics.avg_hold_minutes, interval));
+    }
+}
+
+pub fn build_summary_report(trades: &[RealizedTrade]) -> SummaryReport {
+    SummaryReport {
+        daily: grouped_summary(trades, daily_key),
+        weekly: grouped_summary(trades, weekly_key),
+        monthly: grouped_summary(trades, monthly_key),
+        global: global_metrics(trades),
+    }
+}
+
+/// The summaries and global metrics as the single JSON document `--format json` prints.
+pub fn summary_report_json(report: &SummaryReport) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(report)
+}
+
+/// Emits the summaries and global metrics as CSV rows or a single JSON document, without colors.
+pub fn print_summary_report(trades: &[RealizedTrade], format: OutputFormat) {
+    let report = build_summary_report(trades);
+    match format {
+        OutputFormat::Json => match summary_report_json(&report) {
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("❌ Failed to serialize report: {}", e),
+        },
+        OutputFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(std::io::stdout());
+            let _ = wtr.write_record(["section", "period", "profit", "avg_pct", "trades"]);
+            for (section, rows) in [("daily", &report.daily), ("weekly", &report.weekly), ("monthly", &report.monthly)] {
+                for row in rows {
+                    let _ = wtr.write_record([
+                        section.to_string(),
+                        row.period.clone(),
+                        format!("{:.4}", row.profit),
+                        format!("{:.4}", row.avg_pct),
+                        row.trades.to_string(),
+                    ]);
+                }
+            }
+            let g = &report.global;
+            let _ = wtr.write_record(["global", "avg_win", &format!("{:.4}", g.avg_win), "", &g.win_count.to_string()]);
+            let _ = wtr.write_record(["global", "avg_loss", &format!("{:.4}", g.avg_loss), "", &g.loss_count.to_string()]);
+            let _ = wtr.write_record(["global", "win_rate", "", &format!("{:.4}", g.win_rate), &g.total_trades.to_string()]);
+            let _ = wtr.write_record(["global", "avg_hold_minutes", &format!("{:.1}", g.avg_hold_minutes), "", &g.total_trades.to_string()]);
+            let _ = wtr.flush();
+        }
+        OutputFormat::Table => {}
+    }
 }
 
-pub fn analyze_hourly_trade_performance(trades: &[RealizedTrade]) {
-    let mut hourly_stats: HashMap<u32, Vec<&RealizedTrade>> = HashMap::new();
+/// Hours with fewer closed trades than this show "insufficient data" instead of a win rate.
+pub const DEFAULT_MIN_TRADES_PER_HOUR: usize = 3;
+
+/// Aggregated closed trades for one hour of the day (report timezone).
+#[derive(Debug, Clone)]
+pub struct HourStats {
+    pub hour: u32,
+    pub trades: usize,
+    pub wins: usize,
+    pub avg_profit: f64,
+    /// None when the hour has fewer than the minimum number of trades.
+    pub win_rate: Option<f64>,
+}
+
+/// Groups trades by SELL hour; hours without trades are omitted.
+pub fn hourly_stats(trades: &[RealizedTrade], min_trades: usize) -> Vec<HourStats> {
+    let mut hourly_trades: HashMap<u32, Vec<&RealizedTrade>> = HashMap::new();
 
     for trade in trades {
-        let hour = trade.timestamp.hour();
-        hourly_stats.entry(hour).or_default().push(trade);
+        let hour = to_report_tz(&trade.timestamp).hour();
+        hourly_trades.entry(hour).or_default().push(trade);
     }
 
-    println!("\n⏰ Hourly Trade Performance (based on SELL time):");
+    (0..24)
+        .filter_map(|hour| {
+            let trades = hourly_trades.get(&hour)?;
+            let total = trades.len();
+            let wins = trades.iter().filter(|t| t.profit >= 0.0).count();
+            let avg_profit = trades.iter().map(|t| t.profit).sum::<f64>() / total as f64;
+            let win_rate = (total >= min_trades).then(|| wins as f64 / total as f64 * 100.0);
+            Some(HourStats { hour, trades: total, wins, avg_profit, win_rate })
+        })
+        .collect()
+}
+
+pub fn analyze_hourly_trade_performance(trades: &[RealizedTrade], min_trades: usize) {
+    println!("\n⏰ Hourly Trade Performance (based on SELL time, min {} trades):", min_trades);
     println!("{:<5} {:>6} {:>6} {:>8} {:>10}", "Hour", "Trades", "Wins", "Avg PnL", "Win Rate");
     println!("{:-<42}", "");
 
-    for hour in 0..24 {
-        let trades = hourly_stats.get(&hour).cloned().unwrap_or_default();
-        if trades.is_empty() {
+    for stats in hourly_stats(trades, min_trades) {
+        let hour = format!("{:02}:00", stats.hour);
+        let Some(win_rate) = stats.win_rate else {
+            println!("{:<5} {:>6} {:>6} {:>8.2} \x1b[90minsufficient data\x1b[0m", hour, stats.trades, stats.wins, stats.avg_profit);
             continue;
-        }
-
-        let total = trades.len();
-        let wins = trades.iter().filter(|t| t.profit >= 0.0).count();
-        let total_profit: f64 = trades.iter().map(|t| t.profit).sum();
-        let avg_profit = total_profit / total as f64;
-        let win_rate = wins as f64 / total as f64 * 100.0;
+        };
 
-        let color = if avg_profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let color = if stats.avg_profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
         let reset = "\x1b[0m";
 
         println!(
             "{:<5} {:>6} {:>6} {:>8.2} {}{:>8.1}%{}",
-            format!("{:02}:00", hour),
-            total,
-            wins,
-            avg_profit,
+            hour,
+            stats.trades,
+            stats.wins,
+            stats.avg_profit,
             color,
             win_rate,
             reset
@@ -300,7 +649,7 @@ pub fn find_underperforming_tokens_against_thresholds(trades: &[RealizedTrade],
     if losers.is_empty() {
         println!("✅ No underperforming tokens found.");
     } else {
-        losers.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+        losers.sort_by(|a, b| a.2.total_cmp(&b.2));
 
         for (symbol, win_rate, avg_profit, count) in losers {
             println!(
@@ -311,117 +660,719 @@ pub fn find_underperforming_tokens_against_thresholds(trades: &[RealizedTrade],
     }
 }
 
-fn main() {
-    let folder = get_trade_log_folder();
-    let trades = load_trades_from_dir(Path::new(&folder));
-    let realized = generate_realized_report(&trades);
-    let args: Vec<String> = std::env::args().collect();
-    let symbol_filter = args.get(1).map(|s| s.to_uppercase());
+/// One market scan's breadth (share of green tickers, 0..1) from `breadth_log_path`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BreadthEntry {
+    pub timestamp: DateTime<Utc>,
+    pub breadth: f64,
+    pub trend: String,
+}
+
+pub fn load_breadth(path: &Path) -> Vec<BreadthEntry> {
+    let mut entries: Vec<BreadthEntry> = match fs::File::open(path) {
+        Ok(file) => Reader::from_reader(file).deserialize().flatten().collect(),
+        Err(_) => vec![],
+    };
+    entries.sort_by_key(|e| e.timestamp);
+    entries
+}
+
+/// Average breadth of an hour next to the trades closed in it.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimePeriod {
+    pub period: String,
+    pub avg_breadth: f64,
+    /// MARKET_TREND logged with the hour's last sample.
+    pub trend: String,
+    pub samples: usize,
+    pub trades: usize,
+    /// None when no trade closed in the period.
+    pub win_rate: Option<f64>,
+}
+
+fn hourly_key(timestamp: &DateTime<Utc>) -> String {
+    to_report_tz(timestamp).format("%Y-%m-%d %H:00").to_string()
+}
+
+/// Buckets breadth samples by hour (report timezone) and attaches the win rate of trades closed
+/// in the same hour. Hours without breadth samples are left out.
+pub fn regime_summary(breadth: &[BreadthEntry], trades: &[RealizedTrade]) -> Vec<RegimePeriod> {
+    let mut samples: HashMap<String, Vec<&BreadthEntry>> = HashMap::new();
+    for entry in breadth {
+        samples.entry(hourly_key(&entry.timestamp)).or_default().push(entry);
+    }
+
+    let mut outcomes: HashMap<String, (usize, usize)> = HashMap::new();
+    for trade in trades {
+        let (wins, total) = outcomes.entry(hourly_key(&trade.timestamp)).or_default();
+        if trade.profit >= 0.0 {
+            *wins += 1;
+        }
+        *total += 1;
+    }
+
+    samples
+        .into_iter()
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .map(|(period, entries)| {
+            let (wins, total) = outcomes.get(&period).copied().unwrap_or((0, 0));
+            RegimePeriod {
+                avg_breadth: entries.iter().map(|e| e.breadth).sum::<f64>() / entries.len() as f64,
+                trend: entries.iter().max_by_key(|e| e.timestamp).map(|e| e.trend.clone()).unwrap_or_default(),
+                samples: entries.len(),
+                trades: total,
+                win_rate: (total > 0).then(|| wins as f64 / total as f64 * 100.0),
+                period,
+            }
+        })
+        .collect()
+}
 
-    let args: Vec<String> = std::env::args().collect();
+pub fn print_regime_report(breadth: &[BreadthEntry], trades: &[RealizedTrade]) {
+    let periods = regime_summary(breadth, trades);
+    if periods.is_empty() {
+        println!("No market breadth recorded yet.");
+        return;
+    }
 
-    if args.len() == 2 && (args[1] == "help" || args[1] == "h") {
+    println!("\n🌡 Market breadth vs. trade outcomes (hourly):");
+    println!("{:<17} {:>9} {:<9} {:>8} {:>7} {:>9}", "Hour", "Breadth", "Trend", "Scans", "Trades", "Win rate");
+    println!("{:-<64}", "");
+    for p in &periods {
+        let color = if p.avg_breadth >= 0.5 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        let win_rate = p.win_rate.map(|w| format!("{:.1}%", w)).unwrap_or_else(|| "-".to_string());
         println!(
-            "📘 Reporting CLI Usage:\n\n  \
-            reporting                  → Full report (daily/weekly/monthly + summaries)\n  \
-            reporting SYMBOL           → Show detailed trades for a specific token (e.g. APEUSDC)\n  \
-            reporting day YYYY-MM-DD   → Show closed trades for a specific day\n  \
-            reporting negative         → Show tokens with negative profit \n  \
-            reporting underperforming PROFIT WINRATE  → Show hourly trade performance (based on SELL time) \n  \
-            reporting times            → Show tokens with average profit < PROFIT and win rate < WINRATE\n\n  \
-            reporting help | h         → Show this help message"
+            "{:<17} {}{:>8.1}%{} {:<9} {:>8} {:>7} {:>9}",
+            p.period,
+            color,
+            p.avg_breadth * 100.0,
+            reset,
+            p.trend,
+            p.samples,
+            p.trades,
+            win_rate
         );
-        return;
     }
+}
 
-    if args.get(1).map(|s| s.to_lowercase()) == Some("times".to_string()) {
-        analyze_hourly_trade_performance(&realized);
-        std::process::exit(0);
+/// Profit, wins and trade count of one token (or the whole day) on one day.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DayStats {
+    pub profit: f64,
+    pub wins: usize,
+    pub trades: usize,
+}
+
+impl DayStats {
+    fn add(&mut self, trade: &RealizedTrade) {
+        self.profit += trade.profit;
+        self.trades += 1;
+        if trade.profit >= 0.0 {
+            self.wins += 1;
+        }
     }
 
-    if args.get(1).map(|s| s.to_lowercase()) == Some("underperforming".to_string()) {
-    let profit_threshold = args.get(2).and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-    let win_rate_threshold = args
-        .get(3)
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.4); // 40% by default
+    pub fn win_rate(&self) -> f64 {
+        if self.trades == 0 { 0.0 } else { self.wins as f64 / self.trades as f64 * 100.0 }
+    }
+}
 
-    find_underperforming_tokens_against_thresholds(&realized, profit_threshold, win_rate_threshold);
-    std::process::exit(0);
+/// One token side by side on two days. A token traded on only one day has `DayStats::default()`
+/// on the other.
+#[derive(Debug, Clone)]
+pub struct DayComparison {
+    pub symbol: String,
+    pub a: DayStats,
+    pub b: DayStats,
 }
 
-    if args.get(1).map(String::as_str) == Some("day") {
-        if let Some(date_str) = args.get(2) {
-            if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                let day_trades: Vec<_> = realized
-                    .into_iter()
-                    .filter(|t| t.timestamp.date_naive() == date)
-                    .collect();
+/// Per-token rows sorted by symbol, plus the totals of both days.
+pub fn compare_days(a: &[RealizedTrade], b: &[RealizedTrade]) -> (Vec<DayComparison>, DayStats, DayStats) {
+    let mut rows: HashMap<String, DayComparison> = HashMap::new();
+    let (mut total_a, mut total_b) = (DayStats::default(), DayStats::default());
+    for trade in a {
+        rows.entry(trade.symbol.clone())
+            .or_insert_with(|| DayComparison { symbol: trade.symbol.clone(), a: DayStats::default(), b: DayStats::default() })
+            .a
+            .add(trade);
+        total_a.add(trade);
+    }
+    for trade in b {
+        rows.entry(trade.symbol.clone())
+            .or_insert_with(|| DayComparison { symbol: trade.symbol.clone(), a: DayStats::default(), b: DayStats::default() })
+            .b
+            .add(trade);
+        total_b.add(trade);
+    }
+    let rows = rows.into_values().sorted_by(|x, y| x.symbol.cmp(&y.symbol)).collect();
+    (rows, total_a, total_b)
+}
 
-                if day_trades.is_empty() {
-                    println!("No trades closed on {}", date);
-                    std::process::exit(0);
-                }
+pub fn print_day_comparison(day_a: NaiveDate, a: &[RealizedTrade], day_b: NaiveDate, b: &[RealizedTrade]) {
+    let (rows, total_a, total_b) = compare_days(a, b);
 
-                println!("\n📆 Closed trades on {}:", date);
-                let mut profit_by_token: HashMap<String, f64> = HashMap::new();
-                for trade in &day_trades {
-                    profit_by_token
-                        .entry(trade.symbol.clone())
-                        .and_modify(|p| *p += trade.profit)
-                        .or_insert(trade.profit);
+    println!("\n📊 {} vs {}:", day_a, day_b);
+    println!("{:<12} {:>10} {:>10} {:>10}   {:>7} {:>7}   {:>5} {:>5}", "Symbol", "P&L A", "P&L B", "Δ P&L", "Win A", "Win B", "# A", "# B");
+    println!("{:-<80}", "");
+    let print_row = |label: &str, a: &DayStats, b: &DayStats| {
+        let delta = b.profit - a.profit;
+        let color = if delta >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        println!(
+            "{:<12} {:>10.2} {:>10.2} {}{:>+10.2}{}   {:>6.1}% {:>6.1}%   {:>5} {:>5}",
+            label, a.profit, b.profit, color, delta, reset, a.win_rate(), b.win_rate(), a.trades, b.trades
+        );
+    };
+    for row in &rows {
+        print_row(&row.symbol, &row.a, &row.b);
+    }
+    println!("{:-<80}", "");
+    print_row("TOTAL", &total_a, &total_b);
+}
+
+/// Sort key of `reporting symbols`, selected with `--sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SymbolSort {
+    Profit,
+    #[value(name = "winrate")]
+    WinRate,
+    Count,
+}
+
+/// Closed trades of one symbol, for the `reporting symbols` overview.
+#[derive(Debug, Clone)]
+pub struct SymbolStats {
+    pub symbol: String,
+    pub trades: usize,
+    pub wins: usize,
+    pub total_profit: f64,
+    pub avg_profit_pct: f64,
+}
+
+impl SymbolStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.trades == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.trades as f64 * 100.0
+        }
+    }
+}
+
+/// Groups `trades` by symbol, ranked by `sort` (highest first, then by symbol).
+pub fn symbol_stats(trades: &[RealizedTrade], sort: SymbolSort) -> Vec<SymbolStats> {
+    let mut grouped: HashMap<&str, Vec<&RealizedTrade>> = HashMap::new();
+    for trade in trades {
+        grouped.entry(trade.symbol.as_str()).or_default().push(trade);
+    }
+
+    let mut stats: Vec<SymbolStats> = grouped
+        .into_iter()
+        .map(|(symbol, group)| SymbolStats {
+            symbol: symbol.to_string(),
+            trades: group.len(),
+            wins: group.iter().filter(|t| t.profit >= 0.0).count(),
+            total_profit: group.iter().map(|t| t.profit).sum(),
+            avg_profit_pct: group.iter().map(|t| t.profit_pct).sum::<f64>() / group.len() as f64,
+        })
+        .collect();
+
+    stats.sort_by(|a, b| {
+        let order = match sort {
+            SymbolSort::Profit => b.total_profit.total_cmp(&a.total_profit),
+            SymbolSort::WinRate => b.win_rate().total_cmp(&a.win_rate()),
+            SymbolSort::Count => b.trades.cmp(&a.trades),
+        };
+        order.then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    stats
+}
+
+pub fn print_symbol_stats(trades: &[RealizedTrade], sort: SymbolSort) {
+    let stats = symbol_stats(trades, sort);
+
+    println!("\n🏷️ Symbols ranked by {:?}:", sort);
+    println!("{:<12} {:>6} {:>9} {:>12} {:>10}", "Symbol", "Trades", "Win Rate", "Total P&L", "Avg P&L%");
+    println!("{:-<53}", "");
+    for row in &stats {
+        let color = if row.total_profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        println!(
+            "{:<12} {:>6} {:>8.1}% {}{:>12.4}{} {:>9.2}%",
+            row.symbol, row.trades, row.win_rate(), color, row.total_profit, reset, row.avg_profit_pct
+        );
+    }
+    println!("{:-<53}", "");
+    let total_trades: usize = stats.iter().map(|r| r.trades).sum();
+    let total_profit: f64 = stats.iter().map(|r| r.total_profit).sum();
+    println!("{:<12} {:>6} {:>9} {:>12.4}", "TOTAL", total_trades, "", total_profit);
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenPosition {
+    pub symbol: String,
+    pub entry_price: f64,
+    pub current_price: f64,
+    pub qty: f64,
+    pub unrealized: f64,
+    pub unrealized_pct: f64,
+    pub opened: DateTime<Utc>,
+}
+
+/// Why a log entry could not be paired into a round trip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanKind {
+    /// A BUY followed by another BUY of the same symbol before any SELL.
+    BuyWithoutSell,
+    /// A SELL with no open BUY of that symbol before it.
+    SellWithoutBuy,
+}
+
+/// A BUY or SELL that `generate_realized_report` silently leaves out.
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub kind: OrphanKind,
+    pub entry: TradeLogEntry,
+}
+
+/// BUYs superseded by a later BUY without a SELL in between, and SELLs with no open BUY, in
+/// timestamp order. The latest BUY of a symbol is still open, not orphaned (see `open_buys`).
+pub fn find_orphans(trades: &[TradeLogEntry]) -> Vec<Orphan> {
+    let mut sorted: Vec<&TradeLogEntry> = trades.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut open: HashMap<&str, &TradeLogEntry> = HashMap::new();
+    let mut orphans = Vec::new();
+    for entry in sorted {
+        match entry.action.as_str() {
+            "BUY" => {
+                if let Some(previous) = open.insert(&entry.symbol, entry) {
+                    orphans.push(Orphan { kind: OrphanKind::BuyWithoutSell, entry: previous.clone() });
                 }
+            }
+            "SELL" if open.remove(entry.symbol.as_str()).is_none() => {
+                orphans.push(Orphan { kind: OrphanKind::SellWithoutBuy, entry: entry.clone() });
+            }
+            _ => {}
+        }
+    }
+    orphans.sort_by_key(|o| o.entry.timestamp);
+    orphans
+}
 
-                for (symbol, profit) in profit_by_token.iter().sorted_by_key(|(s, _)| *s) {
-                    let color = if *profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
-                    let reset = "\x1b[0m";
-                    println!("{} → Profit: {}{:.2} USDC{}",symbol, color, profit, reset);
+pub fn print_orphans(trades: &[TradeLogEntry]) {
+    let orphans = find_orphans(trades);
+    if orphans.is_empty() {
+        println!("✅ Every BUY and SELL in the trade log pairs up.");
+    } else {
+        println!("\n🧩 Unmatched trade log entries ({}):", orphans.len());
+        println!("{:<17} {:<12} {:<6} {:>12} {:>12}  Problem", "Time", "Symbol", "Action", "Price", "Qty");
+        println!("{:-<84}", "");
+        for orphan in &orphans {
+            let problem = match orphan.kind {
+                OrphanKind::BuyWithoutSell => "BUY without SELL (another BUY followed)",
+                OrphanKind::SellWithoutBuy => "SELL without a preceding BUY",
+            };
+            let e = &orphan.entry;
+            println!(
+                "{:<17} {:<12} {:<6} {:>12.5} {:>12.4}  {}",
+                to_report_tz(&e.timestamp).format("%Y-%m-%d %H:%M"),
+                e.symbol,
+                e.action,
+                e.price,
+                e.qty,
+                problem
+            );
+        }
+    }
+    let open = open_buys(trades).len();
+    if open > 0 {
+        println!("\nℹ️ {} BUY(s) have no SELL yet and are treated as open; see `reporting openpositions`.", open);
+    }
+}
+
+/// Latest BUY of every symbol that has no SELL after it, in timestamp order, with its
+/// quantity reduced by the partial exits since.
+pub fn open_buys(trades: &[TradeLogEntry]) -> Vec<TradeLogEntry> {
+    let mut sorted: Vec<&TradeLogEntry> = trades.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut open: HashMap<String, TradeLogEntry> = HashMap::new();
+    for entry in sorted {
+        match entry.action.as_str() {
+            "BUY" => {
+                open.insert(entry.symbol.clone(), entry.clone());
+            }
+            action if PARTIAL_EXIT_ACTIONS.contains(&action) => {
+                if let Some(buy) = open.get_mut(&entry.symbol) {
+                    buy.qty = (buy.qty - entry.qty).max(0.0);
                 }
-                let total_profit: f64 = day_trades.iter().map(|t| t.profit).sum();
-                let wins = day_trades.iter().filter(|t| t.profit >= 0.0).count();
-                let total = day_trades.len();
-                let win_pct = (wins as f64 / total as f64) * 100.0;
+            }
+            "SELL" => {
+                open.remove(&entry.symbol);
+            }
+            _ => {}
+        }
+    }
+
+    let mut buys: Vec<TradeLogEntry> = open.into_values().collect();
+    buys.sort_by_key(|b| b.timestamp);
+    buys
+}
+
+/// Unrealized P&L of each open BUY at `prices`. Buys without a valid entry or current price are skipped.
+pub fn unrealized_positions(open: &[TradeLogEntry], prices: &HashMap<String, f64>) -> Vec<OpenPosition> {
+    open.iter()
+        .filter(|buy| is_valid_price(buy.price))
+        .filter_map(|buy| {
+            let current_price = *prices.get(&buy.symbol).filter(|p| is_valid_price(**p))?;
+            Some(OpenPosition {
+                symbol: buy.symbol.clone(),
+                entry_price: buy.price,
+                current_price,
+                qty: buy.qty,
+                unrealized: (current_price - buy.price) * buy.qty,
+                unrealized_pct: percent_change(buy.price, current_price).unwrap_or(0.0),
+                opened: buy.timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Fetches live prices and prints unrealized P&L for every position the trade log still shows open.
+pub fn print_open_positions(trades: &[TradeLogEntry]) {
+    let open = open_buys(trades);
+    if open.is_empty() {
+        println!("✅ No open positions in the trade log.");
+        return;
+    }
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start tokio runtime");
+    let prices = match runtime.block_on(Binance::new().get_all_prices()) {
+        Ok(prices) => prices,
+        Err(e) => {
+            println!("❌ Failed to fetch current prices: {}", e);
+            return;
+        }
+    };
+    let positions = unrealized_positions(&open, &prices);
+
+    println!("\n📂 Open positions ({}):", positions.len());
+    println!("{:<12} {:>12} {:>12} {:>12} {:>10} {:>8}  Opened", "Symbol", "Entry", "Current", "Qty", "P&L", "P&L %");
+    println!("{:-<88}", "");
+    for pos in &positions {
+        let color = if pos.unrealized >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        println!(
+            "{:<12} {:>12.5} {:>12.5} {:>12.4} {}{:>10.2} {:>7.2}%{}  {}",
+            pos.symbol,
+            pos.entry_price,
+            pos.current_price,
+            pos.qty,
+            color,
+            pos.unrealized,
+            pos.unrealized_pct,
+            reset,
+            pos.opened.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    let total: f64 = positions.iter().map(|p| p.unrealized).sum();
+    println!("\n💰 Total unrealized P&L: {:.2} USDC", total);
+    for buy in open.iter().filter(|b| !positions.iter().any(|p| p.symbol == b.symbol)) {
+        eprintln!("⚠️ No usable entry or current price for {}; skipped", buy.symbol);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EquityPoint {
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub profit: f64,
+    pub equity: f64,
+    /// How far `equity` is below its highest point so far (starting capital included).
+    pub drawdown: f64,
+}
+
+/// Running realized balance and drawdown after each trade, in timestamp order.
+pub fn compute_equity_curve(trades: &[RealizedTrade], initial_capital: f64) -> Vec<EquityPoint> {
+    let mut sorted: Vec<&RealizedTrade> = trades.iter().collect();
+    sorted.sort_by_key(|t| t.timestamp);
+
+    let mut equity = initial_capital;
+    let mut peak = initial_capital;
+    sorted
+        .into_iter()
+        .map(|t| {
+            equity += t.profit;
+            peak = peak.max(equity);
+            EquityPoint {
+                timestamp: t.timestamp,
+                symbol: t.symbol.clone(),
+                profit: t.profit,
+                equity,
+                drawdown: peak - equity,
+            }
+        })
+        .collect()
+}
+
+pub fn print_equity_curve(trades: &[RealizedTrade], initial_capital: f64, export_path: &Path) {
+    let curve = compute_equity_curve(trades, initial_capital);
 
-                println!("\n💰 Total profit on {}: {:.2} USDC",date, total_profit);
-                println!("📊 Win/Loss ratio: {} wins / {} total → {:.1}% win rate",wins, total, win_pct);
+    println!("\n💹 Equity curve (starting capital {:.2} USDC):", initial_capital);
+    println!("{:<17} {:<12} {:>10} {:>12} {:>10}", "Date", "Symbol", "Profit", "Equity", "Drawdown");
+    println!("{:-<65}", "");
+    for point in &curve {
+        let color = if point.profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        println!(
+            "{:<17} {:<12} {}{:>10.2}{} {:>12.2} {:>10.2}",
+            point.timestamp.format("%Y-%m-%d %H:%M"),
+            point.symbol,
+            color,
+            point.profit,
+            reset,
+            point.equity,
+            point.drawdown
+        );
+    }
 
-                std::process::exit(0);
+    match csv::Writer::from_path(export_path) {
+        Ok(mut wtr) => {
+            let _ = wtr.write_record(["timestamp", "symbol", "profit", "equity", "drawdown"]);
+            for point in &curve {
+                let _ = wtr.write_record([
+                    point.timestamp.to_rfc3339(),
+                    point.symbol.clone(),
+                    format!("{:.4}", point.profit),
+                    format!("{:.4}", point.equity),
+                    format!("{:.4}", point.drawdown),
+                ]);
+            }
+            if let Err(e) = wtr.flush() {
+                eprintln!("❌ Failed to write {:?}: {}", export_path, e);
             } else {
-                println!("❌ Invalid date format. Use YYYY-MM-DD.");
-                std::process::exit(1);
+                println!("\n📤 Exported equity curve to {:?}", export_path);
             }
         }
+        Err(e) => eprintln!("❌ Could not create {:?}: {}", export_path, e),
     }
-    if args.get(1).map(|s| s.to_lowercase()) == Some("negative".to_string()) {
-        let mut profit_by_token = std::collections::HashMap::new();
-        for trade in &realized {
-            profit_by_token
-                .entry(trade.symbol.clone())
-                .and_modify(|p| *p += trade.profit)
-                .or_insert(trade.profit);
-        }
+}
 
-        let mut losses: Vec<_> = profit_by_token
-            .iter()
-            .filter(|(_, profit)| **profit < 0.0)
-            .collect();
+#[derive(Parser, Debug)]
+#[command(name = "reporting", about = "📘 Reports over the trade logs in TRADE_LOG_FOLDER", disable_help_subcommand = true)]
+struct Cli {
+    /// Full summary as colored table, CSV or a JSON document
+    #[arg(long, value_enum, default_value = "table")]
+    format: OutputFormat,
+    /// Hours with fewer closed trades show insufficient data in the hourly breakdown
+    #[arg(long, global = true, default_value_t = DEFAULT_MIN_TRADES_PER_HOUR)]
+    min_trades: usize,
+    /// Kline interval the bot trades on; hold times are also shown in candles of it
+    #[arg(long, global = true, default_value = "1h", value_parser = parse_interval)]
+    interval: String,
+    /// Without a subcommand: full report (daily/weekly/monthly + summaries)
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-        losses.sort_by(|a, b| a.1.partial_cmp(b.1).unwrap()); // sort by profit (ascending: worst first)
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show closed trades for a specific day (YYYY-MM-DD)
+    Day { date: NaiveDate },
+    /// Show closed trades from FROM to TO (YYYY-MM-DD, inclusive)
+    Range { from: NaiveDate, to: NaiveDate },
+    /// Compare per-token P&L, win rate and trade count of two days
+    Compare { day_a: NaiveDate, day_b: NaiveDate },
+    /// Rank every traded token by total P&L, win rate or trade count
+    Symbols {
+        #[arg(long, value_enum, default_value = "profit")]
+        sort: SymbolSort,
+    },
+    /// Show tokens with negative profit
+    Negative,
+    /// Show tokens with average profit < PROFIT and win rate < WINRATE
+    Underperforming {
+        #[arg(default_value_t = 0.0)]
+        profit: f64,
+        #[arg(default_value_t = 0.4)]
+        win_rate: f64,
+    },
+    /// Show hourly trade performance (based on SELL time)
+    Times,
+    /// Show cumulative equity curve and export it to CSV
+    Equity {
+        #[arg(default_value_t = 0.0)]
+        capital: f64,
+        #[arg(default_value = "equity_curve.csv")]
+        file: String,
+    },
+    /// Show open positions with live unrealized P&L
+    Openpositions,
+    /// List BUYs without a SELL and SELLs without a BUY, for reconciling the log
+    Orphans,
+    /// Show hourly market breadth next to the win rate of that hour
+    Regime,
+    /// Show this help message
+    #[command(alias = "h")]
+    Help,
+    /// SYMBOL: show detailed trades for a specific token (e.g. APEUSDC)
+    #[command(external_subcommand)]
+    Symbol(Vec<String>),
+}
 
-        println!("\n📉 Tokens with net negative profit:");
-        if losses.is_empty() {
-            println!("✅ No losing tokens!");
-        } else {
-            for (symbol, profit) in losses {
-                println!("{} → {:.2} USDC", symbol, profit);
+/// Per-token profit, total and win rate of the trades closed on `label` (a day or a range).
+pub fn print_closed_trades(label: &str, trades: &[RealizedTrade]) {
+    if trades.is_empty() {
+        println!("No trades closed on {}", label);
+        return;
+    }
+
+    println!("\n📆 Closed trades on {}:", label);
+    let mut profit_by_token: HashMap<String, f64> = HashMap::new();
+    for trade in trades {
+        profit_by_token
+            .entry(trade.symbol.clone())
+            .and_modify(|p| *p += trade.profit)
+            .or_insert(trade.profit);
+    }
+
+    for (symbol, profit) in profit_by_token.iter().sorted_by_key(|(s, _)| *s) {
+        let color = if *profit >= 0.0 { "\x1b[32m" } else { "\x1b[31m" };
+        let reset = "\x1b[0m";
+        println!("{} → Profit: {}{:.2} USDC{}",symbol, color, profit, reset);
+    }
+    let total_profit: f64 = trades.iter().map(|t| t.profit).sum();
+    let wins = trades.iter().filter(|t| t.profit >= 0.0).count();
+    let total = trades.len();
+    let win_pct = (wins as f64 / total as f64) * 100.0;
+
+    println!("\n💰 Total profit on {}: {:.2} USDC",label, total_profit);
+    println!("📊 Win/Loss ratio: {} wins / {} total → {:.1}% win rate",wins, total, win_pct);
+}
+
+/// Parses the command line. A word that is no subcommand is read as a SYMBOL and uppercased;
+/// anything else there (several words, punctuation) is a usage error.
+fn parse_cli<I, T>(args: I) -> Result<Cli, clap::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    let mut cli = Cli::try_parse_from(args)?;
+    if let Some(Command::Symbol(words)) = &cli.command {
+        match words.as_slice() {
+            [symbol] if !symbol.is_empty() && symbol.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                cli.command = Some(Command::Symbol(vec![symbol.to_uppercase()]));
+            }
+            _ => {
+                let message = format!("unknown command '{}'", words.join(" "));
+                return Err(Cli::command().error(clap::error::ErrorKind::InvalidSubcommand, message));
             }
         }
+    }
+    Ok(cli)
+}
 
-        std::process::exit(0);
+fn main() {
+    let folder = get_trade_log_folder();
+    let cli = parse_cli(std::env::args_os()).unwrap_or_else(|e| e.exit());
+    let (format, min_trades, interval) = (cli.format, cli.min_trades, cli.interval);
+
+    let command = match cli.command {
+        Some(Command::Help) => {
+            let _ = Cli::command().print_help();
+            return;
+        }
+        command => command,
+    };
+
+    match command {
+        Some(Command::Day { date }) => {
+            print_closed_trades(&date.to_string(), &realized_trades_for_day(Path::new(&folder), date));
+            return;
+        }
+        Some(Command::Range { from, to }) => {
+            print_closed_trades(&format!("{}..{}", from, to), &realized_trades_between(Path::new(&folder), from, to));
+            return;
+        }
+        Some(Command::Compare { day_a, day_b }) => {
+            let a = realized_trades_for_day(Path::new(&folder), day_a);
+            let b = realized_trades_for_day(Path::new(&folder), day_b);
+            print_day_comparison(day_a, &a, day_b, &b);
+            return;
+        }
+        _ => {}
     }
 
-    if let Some(symbol) = symbol_filter {
-        print_trades_for_symbol(&symbol, &trades);
+    let trades = load_trades_from_dir(Path::new(&folder));
+    let realized = generate_realized_report(&trades);
+
+    match command {
+        Some(Command::Times) => {
+            analyze_hourly_trade_performance(&realized, min_trades);
+            return;
+        }
+        Some(Command::Equity { capital, file }) => {
+            print_equity_curve(&realized, capital, Path::new(&file));
+            return;
+        }
+        Some(Command::Regime) => {
+            print_regime_report(&load_breadth(Path::new(&breadth_log_path())), &realized);
+            return;
+        }
+        Some(Command::Openpositions) => {
+            print_open_positions(&trades);
+            return;
+        }
+        Some(Command::Orphans) => {
+            print_orphans(&trades);
+            return;
+        }
+        Some(Command::Underperforming { profit, win_rate }) => {
+            find_underperforming_tokens_against_thresholds(&realized, profit, win_rate);
+            return;
+        }
+        Some(Command::Symbols { sort }) => {
+            print_symbol_stats(&realized, sort);
+            return;
+        }
+        Some(Command::Negative) => {
+            let mut profit_by_token = std::collections::HashMap::new();
+            for trade in &realized {
+                profit_by_token
+                    .entry(trade.symbol.clone())
+                    .and_modify(|p| *p += trade.profit)
+                    .or_insert(trade.profit);
+            }
+
+            let mut losses: Vec<_> = profit_by_token
+                .iter()
+                .filter(|(_, profit)| **profit < 0.0)
+                .collect();
+
+            losses.sort_by(|a, b| a.1.total_cmp(b.1)); // sort by profit (ascending: worst first)
+
+            println!("\n📉 Tokens with net negative profit:");
+            if losses.is_empty() {
+                println!("✅ No losing tokens!");
+            } else {
+                for (symbol, profit) in losses {
+                    println!("{} → {:.2} USDC", symbol, profit);
+                }
+            }
+            return;
+        }
+        Some(Command::Symbol(words)) => {
+            print_trades_for_symbol(&words[0], &trades);
+            return;
+        }
+        _ => {}
+    }
+
+    if format != OutputFormat::Table {
+        print_summary_report(&realized, format);
         return;
     }
 
@@ -429,19 +1380,14 @@ fn main() {
     println!("Total Realized Trades: {}", realized.len());
 
     println!("📆 Daily Summary:");
-    print_grouped_summary(&realized, |t| t.timestamp.date_naive());
+    print_grouped_summary(&realized, daily_key);
     
 
     println!("📅 Weekly Summary:");
-    print_grouped_summary(&realized, |t| {
-        let w = t.timestamp.iso_week();
-        format!("Week {}-W{:02}", w.year(), w.week())
-    });
+    print_grouped_summary(&realized, weekly_key);
 
     println!("🗓 Monthly Summary:");
-    print_grouped_summary(&realized, |t| {
-        format!("{}-{:02}", t.timestamp.year(), t.timestamp.month())
-    });
+    print_grouped_summary(&realized, monthly_key);
 
     // Token-level profit summary
     let mut profit_by_token = std::collections::HashMap::new();
@@ -452,15 +1398,15 @@ fn main() {
             .or_insert(trade.profit);
     }
 
-    if let Some((best_token, best_profit)) = profit_by_token.iter().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()) {
+    if let Some((best_token, best_profit)) = profit_by_token.iter().max_by(|a, b| a.1.total_cmp(b.1)) {
         println!("\n🚀 Most profitable token: {} → {:.2} USDC", best_token, best_profit);
     }
 
-    if let Some((worst_token, worst_profit)) = profit_by_token.iter().min_by(|a, b| a.1.partial_cmp(b.1).unwrap()) {
+    if let Some((worst_token, worst_profit)) = profit_by_token.iter().min_by(|a, b| a.1.total_cmp(b.1)) {
         println!("❌ Least profitable token: {} → {:.2} USDC", worst_token, worst_profit);
     }
 
-    if let Some(best) = realized.iter().max_by(|a, b| a.profit_pct.partial_cmp(&b.profit_pct).unwrap_or(std::cmp::Ordering::Equal)) {
+    if let Some(best) = realized.iter().max_by(|a, b| a.profit_pct.total_cmp(&b.profit_pct)) {
     println!(
         "\n🏆 Best Trade: {} → {:.2}% | Buy @ {:.5} → Sell @ {:.5} | Qty: {:.4} | Date: {}",
         best.symbol,
@@ -472,7 +1418,7 @@ fn main() {
     );
     }
 
-    if let Some(worst) = realized.iter().min_by(|a, b| a.profit_pct.partial_cmp(&b.profit_pct).unwrap_or(std::cmp::Ordering::Equal)) {
+    if let Some(worst) = realized.iter().min_by(|a, b| a.profit_pct.total_cmp(&b.profit_pct)) {
         println!(
             "🔻 Worst Trade: {} → {:.2}% | Buy @ {:.5} → Sell @ {:.5} | Qty: {:.4} | Date: {}",
             worst.symbol,
@@ -491,6 +1437,437 @@ fn main() {
 
     println!("\n📈 Token win/loss ratio: {:.1}% win vs {:.1}% loss ({} unique tokens)", win_ratio, loss_ratio, total_tokens);
 
-    compute_global_win_loss_averages(&realized);
-    analyze_hourly_trade_performance(&realized);
-}
\ No newline at end of file
+    compute_global_win_loss_averages(&realized, &interval);
+    analyze_hourly_trade_performance(&realized, min_trades);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "timestamp,symbol,action,price,qty,quote,stop_loss,reason,trend\n";
+
+    /// Fresh empty folder under the system temp dir for one test's log files.
+    fn temp_log_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("stock_pred_reporting_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn day_report_pairs_a_sell_with_the_previous_days_buy() {
+        let dir = temp_log_dir("midnight");
+        fs::write(
+            dir.join("2026-03-01.csv"),
+            format!("{}2026-03-01T23:00:00Z,ABCUSDT,BUY,10,2,20,9,placed_initial,Bull\n2026-03-01T23:30:00Z,ABCUSDT,SET,11,2,22,11,moved,Bull\n", HEADER),
+        )
+        .unwrap();
+        fs::write(dir.join("2026-03-02.csv"), format!("{}2026-03-02T01:00:00Z,ABCUSDT,SELL,11,2,22,11,stop_hit,Bull\n", HEADER)).unwrap();
+
+        let day = realized_trades_for_day(&dir, date("2026-03-02"));
+        let full: Vec<RealizedTrade> = generate_realized_report(&load_trades_from_dir(&dir));
+        assert_eq!(day.len(), 1);
+        assert_eq!(full.len(), 1);
+        assert_eq!(day[0].profit, full[0].profit);
+        assert!(realized_trades_for_day(&dir, date("2026-03-01")).is_empty());
+        assert_eq!(realized_trades_between(&dir, date("2026-03-01"), date("2026-03-02")).len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn earliest_log_date_ignores_other_files() {
+        let dir = temp_log_dir("earliest");
+        fs::write(dir.join("2026-03-05.csv"), HEADER).unwrap();
+        fs::write(dir.join("2026-02-28.csv"), HEADER).unwrap();
+        fs::write(dir.join("notes.csv"), HEADER).unwrap();
+        assert_eq!(earliest_log_date(&dir), Some(date("2026-02-28")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn log_row(time: &str, action: &str, price: f64, qty: f64, stop_loss: f64) -> TradeLogEntry {
+        TradeLogEntry {
+            timestamp: DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc),
+            symbol: "ABCUSDT".to_string(),
+            action: action.to_string(),
+            price,
+            qty,
+            quote: price * qty,
+            stop_loss,
+        }
+    }
+
+    #[test]
+    fn partial_exits_are_realized_and_reduce_the_closing_sell() {
+        let trades = vec![
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 10.0, 9.0),
+            log_row("2026-03-01T10:05:00Z", "TAKE_PROFIT", 10.5, 5.0, 0.0),
+            log_row("2026-03-01T11:00:00Z", "TRIM", 12.0, 2.0, 11.0),
+            log_row("2026-03-01T11:30:00Z", "SET", 11.0, 3.0, 11.0),
+            log_row("2026-03-01T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+        ];
+        let realized = generate_realized_report(&trades);
+        let sold: Vec<(f64, f64)> = realized.iter().map(|t| (t.sell_price, t.qty)).collect();
+        assert_eq!(sold, vec![(10.5, 5.0), (12.0, 2.0), (11.0, 3.0)]);
+        let profit: f64 = realized.iter().map(|t| t.profit).sum();
+        assert!((profit - (2.5 + 4.0 + 3.0)).abs() < 1e-9);
+
+        let open = open_buys(&trades[..3]);
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].qty, 3.0);
+    }
+
+    #[test]
+    fn json_summary_is_one_parseable_document() {
+        let trades = generate_realized_report(&[
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-01T11:00:00Z", "SET", 11.0, 2.0, 11.0),
+            log_row("2026-03-01T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+            log_row("2026-03-02T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-02T10:05:00Z", "SET", 9.0, 2.0, 9.0),
+            log_row("2026-03-02T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+        ]);
+        let json = summary_report_json(&build_summary_report(&trades)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed["daily"].as_array().unwrap().len(), 2);
+        assert!(parsed["weekly"].is_array() && parsed["monthly"].is_array());
+        assert_eq!(parsed["global"]["total_trades"], 2);
+        assert_eq!(parsed["global"]["win_count"], 1);
+        assert!(!json.contains('\u{1b}'), "no ANSI escapes in JSON output");
+    }
+
+    #[test]
+    fn a_late_evening_trade_lands_on_the_local_day() {
+        let trade = RealizedTrade {
+            symbol: "ABCUSDT".to_string(),
+            profit: 4.0,
+            qty: 2.0,
+            timestamp: DateTime::parse_from_rfc3339("2026-03-02T03:00:00Z").unwrap().with_timezone(&Utc),
+            ..Default::default()
+        };
+        let utc = summarize_by_day_in(std::slice::from_ref(&trade), Tz::UTC);
+        let new_york = summarize_by_day_in(&[trade], chrono_tz::America::New_York);
+        assert_eq!(utc.keys().collect::<Vec<_>>(), vec![&date("2026-03-02")]);
+        assert_eq!(new_york.keys().collect::<Vec<_>>(), vec![&date("2026-03-01")]);
+        assert_eq!(new_york[&date("2026-03-01")], (4.0, 2.0));
+    }
+
+    #[test]
+    fn a_zero_price_buy_is_skipped_instead_of_poisoning_the_summary() {
+        let trades = generate_realized_report(&[
+            log_row("2026-03-01T10:00:00Z", "BUY", 0.0, 2.0, 0.0),
+            log_row("2026-03-01T11:00:00Z", "SET", 11.0, 2.0, 11.0),
+            log_row("2026-03-01T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+            log_row("2026-03-02T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-02T10:05:00Z", "SET", 12.0, 2.0, 12.0),
+            log_row("2026-03-02T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+        ]);
+        assert_eq!(trades.len(), 1);
+        assert!(trades.iter().all(|t| t.profit_pct.is_finite()));
+
+        let report = build_summary_report(&trades);
+        assert_eq!(report.global.total_trades, 1);
+        assert!(report.global.win_rate.is_finite());
+    }
+
+    #[test]
+    fn an_open_buy_is_valued_at_the_current_price() {
+        let trades = vec![
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 4.0, 9.0),
+            log_row("2026-03-01T10:05:00Z", "SET", 10.0, 4.0, 9.5),
+        ];
+        let prices: HashMap<String, f64> = [("ABCUSDT".to_string(), 12.5)].into_iter().collect();
+        let positions = unrealized_positions(&open_buys(&trades), &prices);
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].unrealized, 10.0);
+        assert!((positions[0].unrealized_pct - 25.0).abs() < 1e-9);
+
+        // No live price: the position cannot be valued and is left out.
+        assert!(unrealized_positions(&open_buys(&trades), &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn symbol_trades_are_returned_with_the_printed_total() {
+        let other = |mut row: TradeLogEntry| {
+            row.symbol = "XYZUSDT".to_string();
+            row
+        };
+        let trades = vec![
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            other(log_row("2026-03-01T10:01:00Z", "BUY", 5.0, 4.0, 4.5)),
+            log_row("2026-03-01T11:00:00Z", "SET", 12.0, 2.0, 12.0),
+            other(log_row("2026-03-01T11:01:00Z", "SET", 4.0, 4.0, 4.0)),
+            log_row("2026-03-01T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+            other(log_row("2026-03-01T12:01:00Z", "SELL", 0.0, 0.0, 0.0)),
+        ];
+        let abc = realized_trades_for_symbol("ABCUSDT", &trades);
+        assert_eq!(abc.len(), 1);
+        assert!(abc.iter().all(|t| t.symbol == "ABCUSDT"));
+        assert_eq!(abc.iter().map(|t| t.profit).sum::<f64>(), 4.0);
+        assert_eq!(realized_trades_for_symbol("XYZUSDT", &trades)[0].profit, -4.0);
+    }
+
+    #[test]
+    fn a_matched_pair_reports_its_holding_time() {
+        let trades = generate_realized_report(&[
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-01T11:00:00Z", "SET", 11.0, 2.0, 11.0),
+            log_row("2026-03-01T12:30:00Z", "SELL", 0.0, 0.0, 0.0),
+        ]);
+        assert_eq!(trades[0].duration, chrono::Duration::minutes(150));
+        assert_eq!(trades[0].entry_timestamp, log_row("2026-03-01T10:00:00Z", "BUY", 0.0, 0.0, 0.0).timestamp);
+
+        let global = global_metrics(&trades);
+        assert_eq!(global.avg_hold_minutes, 150.0);
+        assert_eq!(format_hold(global.avg_hold_minutes), "2h 30m");
+    }
+
+    #[test]
+    fn comparing_two_days_zero_fills_tokens_traded_on_one_side() {
+        let trade = |symbol: &str, profit: f64| RealizedTrade { symbol: symbol.to_string(), profit, ..Default::default() };
+        let day_a = vec![trade("ABCUSDT", 5.0), trade("ABCUSDT", -1.0), trade("ONLYAUSDT", 2.0)];
+        let day_b = vec![trade("ABCUSDT", 1.0), trade("ONLYBUSDT", -3.0)];
+
+        let (rows, total_a, total_b) = compare_days(&day_a, &day_b);
+        let symbols: Vec<&str> = rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["ABCUSDT", "ONLYAUSDT", "ONLYBUSDT"]);
+
+        let deltas: Vec<f64> = rows.iter().map(|r| r.b.profit - r.a.profit).collect();
+        assert_eq!(deltas, vec![-3.0, -2.0, -3.0]);
+        assert_eq!(rows[1].b, DayStats::default());
+        assert_eq!(rows[2].a, DayStats::default());
+
+        assert_eq!((total_a.profit, total_a.trades, total_a.wins), (6.0, 3, 2));
+        assert_eq!((total_b.profit, total_b.trades, total_b.wins), (-2.0, 2, 1));
+        assert_eq!(total_b.profit - total_a.profit, -8.0);
+    }
+
+    #[test]
+    fn an_hour_with_two_trades_is_flagged_as_low_sample() {
+        let at = |rfc: &str, profit: f64| RealizedTrade { profit, timestamp: rfc.parse().unwrap(), ..Default::default() };
+        let mut trades = vec![at("2026-03-01T03:10:00Z", 4.0), at("2026-03-02T03:40:00Z", 2.0)];
+        trades.extend([1.0, -1.0, 2.0, 3.0, -2.0].iter().enumerate().map(|(i, p)| at(&format!("2026-03-0{}T15:20:00Z", i + 1), *p)));
+        let quiet_hour = to_report_tz(&trades[0].timestamp).hour();
+        let busy_hour = to_report_tz(&trades[2].timestamp).hour();
+
+        let stats = hourly_stats(&trades, DEFAULT_MIN_TRADES_PER_HOUR);
+        assert_eq!(stats.len(), 2);
+        let quiet = stats.iter().find(|s| s.hour == quiet_hour).unwrap();
+        assert_eq!((quiet.trades, quiet.wins), (2, 2));
+        assert_eq!(quiet.win_rate, None);
+        let busy = stats.iter().find(|s| s.hour == busy_hour).unwrap();
+        assert_eq!((busy.trades, busy.wins), (5, 3));
+        assert_eq!(busy.win_rate, Some(60.0));
+    }
+
+    #[test]
+    fn symbol_table_sorts_by_each_key_and_reconciles_with_the_summary() {
+        let trade = |symbol: &str, profit: f64| RealizedTrade { symbol: symbol.to_string(), profit, profit_pct: profit, ..Default::default() };
+        let trades = vec![
+            trade("AAAUSDT", 5.0),
+            trade("BBBUSDT", -2.0),
+            trade("CCCUSDT", 10.0),
+            trade("AAAUSDT", 3.0),
+            trade("BBBUSDT", 1.0),
+            trade("BBBUSDT", -1.0),
+        ];
+        let order = |sort: SymbolSort| symbol_stats(&trades, sort).into_iter().map(|s| s.symbol).collect::<Vec<_>>();
+        assert_eq!(order(SymbolSort::Profit), vec!["CCCUSDT", "AAAUSDT", "BBBUSDT"]);
+        assert_eq!(order(SymbolSort::WinRate), vec!["AAAUSDT", "CCCUSDT", "BBBUSDT"]);
+        assert_eq!(order(SymbolSort::Count), vec!["BBBUSDT", "AAAUSDT", "CCCUSDT"]);
+
+        let stats = symbol_stats(&trades, SymbolSort::Profit);
+        let global = global_metrics(&trades);
+        assert_eq!(stats.iter().map(|s| s.trades).sum::<usize>(), global.total_trades);
+        assert_eq!(stats.iter().map(|s| s.wins).sum::<usize>(), global.win_count);
+        assert_eq!(stats.iter().map(|s| s.total_profit).sum::<f64>(), trades.iter().map(|t| t.profit).sum::<f64>());
+        let bbb = stats.iter().find(|s| s.symbol == "BBBUSDT").unwrap();
+        assert!((bbb.win_rate() - 100.0 / 3.0).abs() < 1e-9);
+        assert!((bbb.avg_profit_pct + 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trades_held_three_hours_on_average_report_a_three_hour_hold() {
+        let trades = generate_realized_report(&[
+            log_row("2026-03-01T08:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-01T09:00:00Z", "SET", 11.0, 2.0, 11.0),
+            log_row("2026-03-01T10:00:00Z", "SELL", 0.0, 0.0, 0.0),
+            log_row("2026-03-01T12:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            log_row("2026-03-01T13:00:00Z", "SET", 9.5, 2.0, 9.5),
+            log_row("2026-03-01T16:00:00Z", "SELL", 0.0, 0.0, 0.0),
+        ]);
+        assert_eq!(trades.len(), 2);
+        let avg_hold = global_metrics(&trades).avg_hold_minutes;
+        assert_eq!(avg_hold, 180.0);
+        assert_eq!(average_hold_line(avg_hold, "1h"), "≈3h average hold (≈3.0 candles of 1h)");
+        assert_eq!(average_hold_line(avg_hold, "bogus"), "≈3h average hold");
+        assert_eq!(humanize_hold(45.0), "≈45m");
+        assert_eq!(humanize_hold(36.0 * 60.0), "≈1.5d");
+    }
+
+    #[test]
+    fn an_unmatched_buy_and_an_unmatched_sell_are_both_listed() {
+        let mut stray_sell = log_row("2026-03-01T10:30:00Z", "SELL", 0.0, 0.0, 0.0);
+        stray_sell.symbol = "XYZUSDT".to_string();
+        let trades = vec![
+            log_row("2026-03-01T10:00:00Z", "BUY", 10.0, 2.0, 9.0),
+            stray_sell,
+            log_row("2026-03-01T11:00:00Z", "BUY", 11.0, 2.0, 10.0),
+            log_row("2026-03-01T11:30:00Z", "SET", 11.5, 2.0, 11.5),
+            log_row("2026-03-01T12:00:00Z", "SELL", 0.0, 0.0, 0.0),
+        ];
+
+        let orphans: Vec<(OrphanKind, String, DateTime<Utc>)> =
+            find_orphans(&trades).into_iter().map(|o| (o.kind, o.entry.symbol, o.entry.timestamp)).collect();
+        assert_eq!(
+            orphans,
+            vec![
+                (OrphanKind::BuyWithoutSell, "ABCUSDT".to_string(), trades[0].timestamp),
+                (OrphanKind::SellWithoutBuy, "XYZUSDT".to_string(), trades[1].timestamp),
+            ]
+        );
+        assert_eq!(generate_realized_report(&trades).len(), 1);
+    }
+
+    /// Day logs read by `realized_trades_between` for `date`, in the order it read them.
+    fn days_read(dir: &Path, date: NaiveDate) -> (Vec<RealizedTrade>, Vec<NaiveDate>) {
+        let mut read = Vec::new();
+        let trades = realized_trades_loading(dir, date, date, |d| {
+            read.push(d);
+            load_trades_for_date(dir, d)
+        });
+        (trades, read)
+    }
+
+    #[test]
+    fn earlier_days_are_read_only_while_a_sell_lacks_its_buy() {
+        let dir = temp_log_dir("lazy_days");
+        let bought = |day: &str, symbol: &str| {
+            format!(
+                "{day}T12:00:00Z,{symbol},BUY,10,2,20,9,placed_initial,Bull\n{day}T12:30:00Z,{symbol},SET,11,2,22,11,moved,Bull\n"
+            )
+        };
+        let sold = |symbol: &str| format!("2026-03-05T12:00:00Z,{symbol},SELL,11,2,22,11,stop_hit,Bull\n");
+        for day in ["2026-03-01", "2026-03-02", "2026-03-04"] {
+            fs::write(dir.join(format!("{}.csv", day)), format!("{}{}", HEADER, bought(day, "XYZUSDT"))).unwrap();
+        }
+        fs::write(dir.join("2026-03-03.csv"), format!("{}{}", HEADER, bought("2026-03-03", "ABCUSDT"))).unwrap();
+        let window = utc_dates_for_report_day(date("2026-03-05"));
+
+        // Matched inside the day: no earlier day is read at all.
+        fs::write(dir.join("2026-03-05.csv"), format!("{}{}{}", HEADER, bought("2026-03-05", "XYZUSDT").replace("T12:", "T10:"), sold("XYZUSDT"))).unwrap();
+        let (trades, read) = days_read(&dir, date("2026-03-05"));
+        assert_eq!(trades.len(), 1);
+        assert_eq!(read, window);
+
+        // A SELL whose BUY is two days back reads back to that day and no further.
+        fs::write(dir.join("2026-03-05.csv"), format!("{}{}", HEADER, sold("ABCUSDT"))).unwrap();
+        let (trades, read) = days_read(&dir, date("2026-03-05"));
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].entry_timestamp.date_naive(), date("2026-03-03"));
+        assert_eq!(read[..window.len()], window[..]);
+        assert!(read.contains(&date("2026-03-03")), "{:?}", read);
+        assert!(!read.contains(&date("2026-03-02")), "{:?}", read);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn three_trades_give_the_hand_computed_equity_and_drawdown() {
+        let at = |rfc: &str, profit: f64| RealizedTrade { profit, timestamp: rfc.parse().unwrap(), ..Default::default() };
+        // Out of order on purpose: the curve follows the timestamps.
+        let trades = [at("2026-03-03T10:00:00Z", 30.0), at("2026-03-01T10:00:00Z", 50.0), at("2026-03-02T10:00:00Z", -80.0)];
+        let curve = compute_equity_curve(&trades, 1000.0);
+
+        let points: Vec<(f64, f64, f64)> = curve.iter().map(|p| (p.profit, p.equity, p.drawdown)).collect();
+        // 1000 + 50 = 1050 (new peak), - 80 = 970 (80 below it), + 30 = 1000 (still 50 below).
+        assert_eq!(points, [(50.0, 1050.0, 0.0), (-80.0, 970.0, 80.0), (30.0, 1000.0, 50.0)]);
+        assert!(compute_equity_curve(&[], 1000.0).is_empty());
+    }
+
+    #[test]
+    fn logged_breadth_rows_append_and_read_back_into_the_regime_summary() {
+        let dir = temp_log_dir("breadth");
+        stock_pred::config::set_trade_log_folder(dir.to_str().unwrap());
+        for (breadth, trend) in [(0.7, "Positive"), (0.3, "Negative"), (0.2, "Negative")] {
+            stock_pred::logging::log_market_breadth(breadth, trend);
+        }
+        stock_pred::logging::flush_trade_log();
+
+        let path = breadth_log_path();
+        assert!(path.starts_with(dir.to_str().unwrap()), "{}", path);
+        let log = fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().filter(|l| l.starts_with("timestamp")).count(), 1, "{}", log);
+        let entries = load_breadth(Path::new(&path));
+        let rows: Vec<(f64, &str)> = entries.iter().map(|e| (e.breadth, e.trend.as_str())).collect();
+        assert_eq!(rows, [(0.7, "Positive"), (0.3, "Negative"), (0.2, "Negative")]);
+
+        // All three land in the current hour unless the test straddles an hour boundary.
+        let periods = regime_summary(&entries, &[]);
+        assert_eq!(periods.iter().map(|p| p.samples).sum::<usize>(), 3);
+        let latest = periods.last().unwrap();
+        assert_eq!(latest.trend, "Negative");
+        if periods.len() == 1 {
+            assert!((latest.avg_breadth - 0.4).abs() < 1e-9, "{}", latest.avg_breadth);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// The subcommand `reporting <args>` parses to.
+    fn parsed(args: &[&str]) -> Option<Command> {
+        parse_cli(std::iter::once("reporting").chain(args.iter().copied())).unwrap().command
+    }
+
+    #[test]
+    fn every_subcommand_parses_from_the_command_line() {
+        assert!(parsed(&[]).is_none());
+        assert!(matches!(parsed(&["day", "2026-03-01"]), Some(Command::Day { date: day }) if day == date("2026-03-01")));
+        assert!(matches!(
+            parsed(&["range", "2026-03-01", "2026-03-07"]),
+            Some(Command::Range { from, to }) if from == date("2026-03-01") && to == date("2026-03-07")
+        ));
+        assert!(matches!(parsed(&["compare", "2026-03-01", "2026-03-02"]), Some(Command::Compare { .. })));
+        assert!(matches!(parsed(&["symbols"]), Some(Command::Symbols { sort: SymbolSort::Profit })));
+        assert!(matches!(parsed(&["symbols", "--sort", "winrate"]), Some(Command::Symbols { sort: SymbolSort::WinRate })));
+        assert!(matches!(parsed(&["negative"]), Some(Command::Negative)));
+        assert!(matches!(
+            parsed(&["underperforming", "1.5", "0.3"]),
+            Some(Command::Underperforming { profit, win_rate }) if profit == 1.5 && win_rate == 0.3
+        ));
+        assert!(matches!(parsed(&["times"]), Some(Command::Times)));
+        assert!(matches!(
+            parsed(&["equity", "1000", "curve.csv"]),
+            Some(Command::Equity { capital, file }) if capital == 1000.0 && file == "curve.csv"
+        ));
+        assert!(matches!(parsed(&["openpositions"]), Some(Command::Openpositions)));
+        assert!(matches!(parsed(&["orphans"]), Some(Command::Orphans)));
+        assert!(matches!(parsed(&["regime"]), Some(Command::Regime)));
+        assert!(matches!(parsed(&["help"]), Some(Command::Help)));
+        assert!(matches!(parsed(&["h"]), Some(Command::Help)));
+        assert!(matches!(parsed(&["apeusdc"]), Some(Command::Symbol(words)) if words == ["APEUSDC"]));
+
+        let cli = parse_cli(["reporting", "--format", "json", "times", "--min-trades", "3", "--interval", "15m"]).unwrap();
+        assert!(matches!(cli.format, OutputFormat::Json));
+        assert_eq!((cli.min_trades, cli.interval.as_str()), (3, "15m"));
+    }
+
+    #[test]
+    fn malformed_invocations_are_usage_errors() {
+        for args in [
+            vec!["reporting", "ape-usdc"],
+            vec!["reporting", "ape", "usdc"],
+            vec!["reporting", "day", "yesterday"],
+            vec!["reporting", "range", "2026-03-01"],
+            vec!["reporting", "--format", "xml"],
+            vec!["reporting", "times", "--interval", "1hr"],
+        ] {
+            let err = parse_cli(&args).unwrap_err();
+            assert_eq!(err.exit_code(), 2, "{:?}", args);
+            assert!(err.use_stderr(), "{:?}: {}", args, err);
+        }
+    }
+}
@@ -1,38 +1,81 @@
-use reqwest::Client;
-use serde::Deserialize;
+use reqwest::{Client, Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize};
 use tokio_tungstenite::connect_async;
-use tokio_stream::StreamExt;
+use futures_util::stream::{self, StreamExt};
 use serde_json::Value;
 use url::Url;
 use std::env;
-use std::time::SystemTime;
-use std::time::UNIX_EPOCH;
+use std::time::Instant;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use chrono::Utc;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
 type HmacSha256 = Hmac<Sha256>;
 use std::collections::HashSet;
 use hex::encode as hex_encode;
-use dotenv::from_filename;
-use tracing::{info,error};
+use tracing::{info,warn,error,Instrument};
 use reqwest::Error;
 use std::collections::HashMap;
 use tokio::time::Duration;
 use tokio::time::sleep;
 use reqwest::Error as ReqwestError;         
 use std::error::Error as StdError;      
-use crate::logging::log_trade_event;
+use crate::logging::{equity_log_path, equity_snapshot_due, log_equity_snapshot, log_order_audit, log_trade_event, new_trade_id, trade_id_for, trade_span, traded_symbols_today};
+use crate::trading::discovery::{expand_holdings_to_pairs, indicator_warmup, kline_fetch_limit, split_symbol};
+use crate::trading::indicators::compute_wilder_rsi;
 use crate::types::*;
 use crate::config::*;
 use crate::config;
+use crate::{status_println, verbose_println};
 
 #[derive(Debug, Clone, Default)]
 pub struct SymbolFilters {
     pub tick_size: f64,
     pub step_size: f64,
     pub min_qty: f64,
+    /// LOT_SIZE maxQty; 0 when the exchange reported none.
+    pub max_qty: f64,
     pub min_price: f64,
     pub min_notional: f64,
+    /// Decimals allowed in quote amounts such as `quoteOrderQty` (`quoteAssetPrecision`).
+    pub quote_precision: u32,
+}
+
+impl SymbolFilters {
+    /// Parses the `filters` of one `exchangeInfo` symbol entry. Missing filters leave their fields at 0.
+    /// Both MIN_NOTIONAL and its replacement NOTIONAL supply `min_notional`; futures report it
+    /// as `notional` instead of `minNotional`.
+    pub fn from_symbol_info(symbol_info: &Value) -> SymbolFilters {
+        let mut filters = SymbolFilters {
+            quote_precision: symbol_info["quoteAssetPrecision"]
+                .as_u64()
+                .or_else(|| symbol_info["quotePrecision"].as_u64())
+                .unwrap_or(8) as u32,
+            ..SymbolFilters::default()
+        };
+        let field = |f: &Value, name: &str| f[name].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
+
+        for f in symbol_info["filters"].as_array().map(Vec::as_slice).unwrap_or(&[]) {
+            match f.get("filterType").and_then(|v| v.as_str()) {
+                Some("PRICE_FILTER") => {
+                    filters.tick_size = field(f, "tickSize");
+                    filters.min_price = field(f, "minPrice");
+                }
+                Some("LOT_SIZE") => {
+                    filters.step_size = field(f, "stepSize");
+                    filters.min_qty = field(f, "minQty");
+                    filters.max_qty = field(f, "maxQty");
+                }
+                Some("MIN_NOTIONAL") | Some("NOTIONAL") => {
+                    filters.min_notional = field(f, "minNotional").max(field(f, "notional"));
+                }
+                _ => {}
+            }
+        }
+        filters
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -52,6 +95,9 @@ pub struct SymbolInfo {
     pub quote_asset: String,
     #[serde(rename = "orderTypes")]
     pub order_types: Vec<String>,
+    /// False for symbols that only trade on margin; missing in some responses.
+    #[serde(rename = "isSpotTradingAllowed")]
+    pub is_spot_trading_allowed: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,6 +108,7 @@ pub struct Balance {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AccountInfo {
     #[serde(default)]
     pub maker_commission: i64,
@@ -97,6 +144,77 @@ pub struct Ticker24hr {
     // Add additional fields if needed.
 }
 
+#[derive(Debug, Deserialize)]
+struct DepthResponse {
+    bids: Vec<[String; 2]>,
+    asks: Vec<[String; 2]>,
+}
+
+/// Latest funding of a USDⓈ-M perpetual, from the futures `/premiumIndex` endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FundingRate {
+    pub symbol: String,
+    /// Last funding rate as a percentage (Binance reports a fraction: 0.0001 = 0.01%).
+    pub rate_pct: f64,
+    pub mark_price: f64,
+    pub next_funding_time: u64,
+}
+
+impl FundingRate {
+    pub fn from_premium_index(value: &Value) -> Option<Self> {
+        let num = |key: &str| value[key].as_str().and_then(|s| s.parse::<f64>().ok());
+        Some(FundingRate {
+            symbol: value["symbol"].as_str()?.to_string(),
+            rate_pct: num("lastFundingRate")? * 100.0,
+            mark_price: num("markPrice").unwrap_or(0.0),
+            next_funding_time: value["nextFundingTime"].as_u64().unwrap_or(0),
+        })
+    }
+}
+
+/// Open interest of a perpetual in base units, from the futures `/openInterest` endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenInterest {
+    pub symbol: String,
+    pub open_interest: f64,
+    pub time: u64,
+}
+
+impl OpenInterest {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        Some(OpenInterest {
+            symbol: value["symbol"].as_str()?.to_string(),
+            open_interest: value["openInterest"].as_str()?.parse().ok()?,
+            time: value["time"].as_u64().unwrap_or(0),
+        })
+    }
+}
+
+/// Order book levels as (price, quantity), best first.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    pub bids: Vec<(f64, f64)>,
+    pub asks: Vec<(f64, f64)>,
+}
+
+impl OrderBook {
+    /// Best bid/ask spread as a percentage of the mid price, if both sides have levels.
+    pub fn spread_pct(&self) -> Option<f64> {
+        let (bid, _) = self.bids.first()?;
+        let (ask, _) = self.asks.first()?;
+        let mid = (bid + ask) / 2.0;
+        if mid <= 0.0 {
+            return None;
+        }
+        Some((ask - bid) / mid * 100.0)
+    }
+
+    /// True when the spread is at most `max_spread_pct`; an empty side never passes.
+    pub fn within_spread(&self, max_spread_pct: f64) -> bool {
+        self.spread_pct().is_some_and(|spread| spread <= max_spread_pct)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct TickerPrice {
     price: String,
@@ -108,9 +226,183 @@ struct OrderResponse {
     executedQty: String,
 }
 
+/// Outcome of an accepted order. Quantities are zero until the order fills.
+#[derive(Debug, Clone)]
+pub struct OrderResult {
+    pub order_id: u64,
+    pub executed_qty: f64,
+    pub quote_qty: f64,
+    /// Per-fill prices and commissions of a FULL response; empty for other response types.
+    pub fills: Vec<OrderFill>,
+}
+
+impl OrderResult {
+    /// Parses a successful order response. Spot reports the spent quote as
+    /// `cummulativeQuoteQty`, futures as `cumQuote`.
+    pub fn from_response(parsed: &Value) -> Self {
+        let number = |name: &str| parsed[name].as_str().and_then(|q| q.parse().ok());
+        OrderResult {
+            order_id: parsed["orderId"].as_u64().unwrap_or(0),
+            executed_qty: number("executedQty").unwrap_or(0.0),
+            quote_qty: number("cummulativeQuoteQty").or_else(|| number("cumQuote")).unwrap_or(0.0),
+            fills: Binance::parse_fills(&parsed["fills"]),
+        }
+    }
+
+    /// Average fill price, or None before anything has executed.
+    pub fn avg_price(&self) -> Option<f64> {
+        (self.executed_qty > 0.0 && self.quote_qty > 0.0).then(|| self.quote_qty / self.executed_qty)
+    }
+}
+
+/// One fill of an order, with the commission and the asset it was charged in.
+#[derive(Debug, Clone)]
+pub struct OrderFill {
+    pub price: f64,
+    pub qty: f64,
+    pub commission: f64,
+    pub commission_asset: String,
+}
+
+/// Parameters of a single order, built up with `OrderRequest::new(..).quantity(..)` etc.
+/// and sent with `Binance::place_order`.
+#[derive(Debug, Clone)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: String,
+    pub order_type: String,
+    pub quantity: Option<f64>,
+    pub quote_order_qty: Option<f64>,
+    pub price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub callback_rate: Option<f64>,
+    pub activation_price: Option<f64>,
+    pub tif: Option<String>,
+    pub good_till_date: Option<u64>,
+    pub reduce_only: bool,
+    /// Send to the futures API instead of spot.
+    pub futures: bool,
+}
+
+impl OrderRequest {
+    pub fn new(symbol: &str, side: &str, order_type: &str) -> Self {
+        OrderRequest {
+            symbol: symbol.to_string(),
+            side: side.to_string(),
+            order_type: order_type.to_string(),
+            quantity: None,
+            quote_order_qty: None,
+            price: None,
+            stop_price: None,
+            callback_rate: None,
+            activation_price: None,
+            tif: None,
+            good_till_date: None,
+            reduce_only: false,
+            futures: false,
+        }
+    }
+
+    pub fn quantity(mut self, quantity: f64) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn quote_order_qty(mut self, quote_amount: f64) -> Self {
+        self.quote_order_qty = Some(quote_amount);
+        self
+    }
+
+    pub fn price(mut self, price: f64) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    pub fn stop_price(mut self, stop_price: f64) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn callback_rate(mut self, callback_rate: f64) -> Self {
+        self.callback_rate = Some(callback_rate);
+        self
+    }
+
+    pub fn activation_price(mut self, activation_price: Option<f64>) -> Self {
+        self.activation_price = activation_price;
+        self
+    }
+
+    /// Sets `timeInForce` (GTC when `tif` is None) and `goodTillDate` for GTD.
+    pub fn tif(mut self, tif: Option<&str>, good_till_date: Option<u64>) -> Self {
+        self.tif = Some(tif.unwrap_or("GTC").to_string());
+        self.good_till_date = good_till_date;
+        self
+    }
+
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    pub fn futures(mut self) -> Self {
+        self.futures = true;
+        self
+    }
+
+    /// Rounds every price field (price, stopPrice, activationPrice) to `decimals` places, for
+    /// symbols whose QUOTE_PRECISION_OVERRIDES entry beats the filter-derived precision.
+    pub fn price_decimals(mut self, decimals: u32) -> Self {
+        let round = |value: Option<f64>| value.map(|v| Binance::round_decimals(v, decimals as i32));
+        self.price = round(self.price);
+        self.stop_price = round(self.stop_price);
+        self.activation_price = round(self.activation_price);
+        self
+    }
+
+    /// Query string without timestamp/signature. Fails on an invalid timeInForce combination.
+    pub fn to_query(&self) -> Result<String, Box<dyn StdError>> {
+        let mut parts = vec![
+            format!("symbol={}", self.symbol),
+            format!("side={}", self.side),
+            format!("type={}", self.order_type),
+        ];
+        if self.tif.is_some() {
+            parts.push(Binance::time_in_force_params(self.tif.as_deref(), self.good_till_date)?);
+        }
+        let values = [
+            ("quantity", self.quantity),
+            ("quoteOrderQty", self.quote_order_qty),
+            ("price", self.price),
+            ("stopPrice", self.stop_price),
+            ("callbackRate", self.callback_rate),
+            ("activationPrice", self.activation_price),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                parts.push(format!("{}={}", name, value));
+            }
+        }
+        if self.reduce_only {
+            parts.push("reduceOnly=true".to_string());
+        }
+        parts.push("recvWindow=5000".to_string());
+        Ok(parts.join("&"))
+    }
+}
+
 pub struct Binance {
     client: Client,
     base_url: String,
+    futures_base_url: String,
+    sapi_base_url: String,
+    stream_base_url: String,
+    api_key: String,
+    secret_key: String,
+    /// Server time minus local time (ms), refreshed by `sync_time`.
+    time_offset_ms: AtomicI64,
+    /// Positions bought through this client; share the client via `Arc` to share them.
+    positions: Arc<Mutex<PositionStore>>,
 }
 
 #[derive(Debug, Clone)]
@@ -121,12 +413,147 @@ pub struct TrackedPosition {
     pub quantity: f64,
 }
 
+/// A STOP_LOSS_LIMIT order followed by `manage_stop_loss_stream`.
+#[derive(Debug, Clone)]
+pub struct StreamedStop {
+    pub order_id: u64,
+    pub quantity: f64,
+    pub purchase_price: f64,
+    pub stop_price: f64,
+    pub tick_size: f64,
+    pub high_water: f64,
+    pub last_update: Option<Instant>,
+}
+
+/// Exchange-wide state reported by `/sapi/v1/system/status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemStatus {
+    Normal,
+    Maintenance,
+}
+
+impl SystemStatus {
+    /// `status` 1 in the body means maintenance; a 503 is treated the same since Binance
+    /// serves it for every endpoint during downtime.
+    pub fn from_response(http_status: u16, body: &Value) -> Self {
+        if http_status == 503 || body["status"].as_u64() == Some(1) {
+            SystemStatus::Maintenance
+        } else {
+            SystemStatus::Normal
+        }
+    }
+}
+
+/// Binance codes answered to a request signed with a stale timestamp: -1021 (outside
+/// recvWindow) and -1022 (signature not valid for the request as received).
+const TIMESTAMP_ERROR_CODES: [i64; 2] = [-1021, -1022];
+
+/// How long the tradable symbol set used by `validate_symbol` is reused.
+const TRADABLE_SYMBOLS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How long symbol filters served by `cached_symbol_filters` are reused.
+const SYMBOL_FILTERS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Minimum time between two streamed re-placements of the same stop.
+const STREAM_UPDATE_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Fraction of a step tolerated as floating-point error when flooring to a step size.
+const STEP_EPSILON: f64 = 1e-9;
+
+/// RSI period (1h candles) for the sell-into-strength check.
+const RSI_EXIT_PERIOD: usize = 14;
+
+impl StreamedStop {
+    /// Returns the new stop price when `price` sets a new high that moves the stop up
+    /// by at least one tick, respecting the per-symbol update cooldown. The high water mark
+    /// only advances when the stop is evaluated, so a peak seen during the cooldown is
+    /// still a new high for the first trade after it.
+    pub fn ratcheted_stop(&mut self, symbol: &str, price: f64) -> Option<f64> {
+        if price <= self.high_water {
+            return None;
+        }
+        if self.last_update.is_some_and(|t| t.elapsed() < STREAM_UPDATE_COOLDOWN) {
+            return None;
+        }
+        self.high_water = price;
+
+        let stop_loss_percent = Binance::trailing_stop_percent(symbol, price, self.purchase_price);
+        let trailing = price * (1.0 - stop_loss_percent / 100.0);
+        let locked = Binance::profit_locked_stop(trailing, self.stop_price, self.purchase_price, price, get_profit_lock_trail_pct());
+        let new_stop = Binance::round_to_step(locked, self.tick_size);
+        if new_stop > Binance::round_to_step(self.stop_price, self.tick_size) {
+            Some(new_stop)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses a combined-stream `@trade` message into (symbol, price).
+fn parse_trade_price(text: &str) -> Option<(String, f64)> {
+    let json: Value = serde_json::from_str(text).ok()?;
+    let data = &json["data"];
+    let symbol = data["s"].as_str()?.to_string();
+    let price = data["p"].as_str()?.parse::<f64>().ok()?;
+    Some((symbol, price))
+}
+
+impl Default for Binance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Binance {
+    /// Client using BINANCE_API_KEY / BINANCE_SECRET_KEY from the process environment (after
+    /// loading vars.env if present). Public endpoints still work when the keys are unset.
     pub fn new() -> Self {
+        load_env_file();
+        Self::with_credentials(
+            &env::var("BINANCE_API_KEY").unwrap_or_default(),
+            &env::var("BINANCE_SECRET_KEY").unwrap_or_default(),
+        )
+    }
+
+    /// Client with explicit credentials, for embedding the crate without env vars or vars.env.
+    pub fn with_credentials(api_key: &str, secret_key: &str) -> Self {
         Self {
-            client: Client::new(),
+            client: Binance::http_client(get_http_connect_timeout_ms(), get_http_request_timeout_ms()),
             base_url: "https://api.binance.com/api/v3".to_string(),
+            futures_base_url: "https://fapi.binance.com/fapi/v1".to_string(),
+            sapi_base_url: "https://api.binance.com/sapi/v1".to_string(),
+            stream_base_url: "wss://stream.binance.com:9443".to_string(),
+            api_key: api_key.to_string(),
+            secret_key: secret_key.to_string(),
+            time_offset_ms: AtomicI64::new(0),
+            positions: Arc::new(Mutex::new(PositionStore::default())),
+        }
+    }
+
+    /// Store of the positions bought through this client.
+    pub fn positions(&self) -> Arc<Mutex<PositionStore>> {
+        Arc::clone(&self.positions)
+    }
+
+    /// Builds the HTTP client with the given connect and total request timeouts (0 = none).
+    pub fn http_client(connect_timeout_ms: u64, request_timeout_ms: u64) -> Client {
+        let mut builder = Client::builder();
+        if connect_timeout_ms > 0 {
+            builder = builder.connect_timeout(Duration::from_millis(connect_timeout_ms));
         }
+        if request_timeout_ms > 0 {
+            builder = builder.timeout(Duration::from_millis(request_timeout_ms));
+        }
+        builder.build().unwrap_or_else(|e| {
+            error!("Failed to build HTTP client with timeouts: {}", e);
+            Client::new()
+        })
+    }
+
+    /// Endpoint for TRAILING_STOP_MARKET orders. The order type (with `callbackRate`/`activationPrice`)
+    /// only exists on USDⓈ-M futures, so there is none unless FUTURES_TRAILING_STOPS is enabled.
+    pub fn trailing_stop_base_url(&self, futures_enabled: bool) -> Option<&str> {
+        futures_enabled.then_some(self.futures_base_url.as_str())
     }
 
      /// Fetches the exchange information from Binance.
@@ -137,6 +564,126 @@ impl Binance {
         Ok(info)
     }
 
+    /// Local time corrected by the last measured server offset, in ms.
+    fn timestamp_ms(&self) -> i64 {
+        Utc::now().timestamp_millis() + self.time_offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// Measures the offset between the server clock and ours and applies it to later signed requests.
+    pub async fn sync_time(&self) -> Result<i64, reqwest::Error> {
+        let server_time = self.get_server_time().await? as i64;
+        let offset = server_time - Utc::now().timestamp_millis();
+        self.time_offset_ms.store(offset, Ordering::Relaxed);
+        Ok(offset)
+    }
+
+    /// Appends `timestamp` to `params` and signs the result with HMAC-SHA256.
+    pub fn signed_query(&self, params: &str, timestamp: i64) -> String {
+        let query = if params.is_empty() {
+            format!("timestamp={}", timestamp)
+        } else {
+            format!("{}&timestamp={}", params, timestamp)
+        };
+        let mut mac = HmacSha256::new_from_slice(self.secret_key.as_bytes())
+            .expect("HMAC can take key of any size");
+        mac.update(query.as_bytes());
+        format!("{}&signature={}", query, hex_encode(mac.finalize().into_bytes()))
+    }
+
+    /// True when an error body carries -1021 or -1022, the rejections a clock drifting past
+    /// recvWindow produces; other errors are not retried.
+    pub fn is_timestamp_error(body: &str) -> bool {
+        serde_json::from_str::<Value>(body)
+            .ok()
+            .and_then(|v| v["code"].as_i64())
+            .is_some_and(|code| TIMESTAMP_ERROR_CODES.contains(&code))
+    }
+
+    async fn send_signed_once(&self, method: Method, url: &str, params: &str) -> Result<(StatusCode, String), reqwest::Error> {
+        let query = self.signed_query(params, self.timestamp_ms());
+        let response = self.client
+            .request(method, format!("{}?{}", url, query))
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .await?;
+        let status = response.status();
+        Ok((status, response.text().await?))
+    }
+
+    /// Sends a signed request to `url` with `params` (without timestamp/signature) and returns
+    /// the status and body. When Binance rejects the timestamp, resyncs the clock offset and
+    /// retries once with a fresh timestamp.
+    async fn send_signed(&self, method: Method, url: &str, params: &str) -> Result<(StatusCode, String), reqwest::Error> {
+        let (status, body) = self.send_signed_once(method.clone(), url, params).await?;
+        let rejected = status == StatusCode::BAD_REQUEST || status == StatusCode::UNAUTHORIZED;
+        if !rejected || !Binance::is_timestamp_error(&body) {
+            return Ok((status, body));
+        }
+
+        let offset = self.sync_time().await?;
+        warn!("⏱ Timestamp rejected ({}); resynced clock offset to {} ms and retrying", body, offset);
+        status_println!("⏱ Timestamp rejected; resynced clock offset to {} ms and retrying", offset);
+        self.send_signed_once(method, url, params).await
+    }
+
+    /// Sends a signed request and deserializes a successful body; an error status becomes an
+    /// error carrying Binance's response.
+    async fn signed_json<T: DeserializeOwned>(&self, method: Method, url: &str, params: &str) -> Result<T, Box<dyn StdError>> {
+        let (status, body) = self.send_signed(method, url, params).await?;
+        if !status.is_success() {
+            return Err(format!("Binance returned {}: {}", status, body).into());
+        }
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Symbols in TRADING status that allow spot trading, from `/exchangeInfo` cached for an hour.
+    pub async fn tradable_symbols(&self) -> Result<Arc<HashSet<String>>, reqwest::Error> {
+        let fresh = |cache: &Option<(Instant, Arc<HashSet<String>>)>| {
+            cache
+                .as_ref()
+                .filter(|(fetched, _)| fetched.elapsed() <= TRADABLE_SYMBOLS_TTL)
+                .map(|(_, symbols)| Arc::clone(symbols))
+        };
+        if let Some(symbols) = fresh(&*TRADABLE_SYMBOLS.read().await) {
+            return Ok(symbols);
+        }
+
+        // Stale or empty: refresh under the write lock, unless another caller just did.
+        let mut cache = TRADABLE_SYMBOLS.write().await;
+        if let Some(symbols) = fresh(&cache) {
+            return Ok(symbols);
+        }
+        let info = self.get_exchange_info().await?;
+        let symbols: Arc<HashSet<String>> = Arc::new(
+            info.symbols
+                .into_iter()
+                .filter(|s| s.status == "TRADING" && s.is_spot_trading_allowed != Some(false))
+                .map(|s| s.symbol)
+                .collect(),
+        );
+        *cache = Some((Instant::now(), Arc::clone(&symbols)));
+        Ok(symbols)
+    }
+
+    /// Uppercases `symbol` and checks it against the TRADING symbols of the exchange (cached for
+    /// an hour), so a typo fails here instead of as a cryptic order rejection.
+    pub async fn validate_symbol(&self, symbol: &str) -> Result<String, Box<dyn StdError>> {
+        let symbols = self.tradable_symbols().await?;
+
+        Binance::normalize_symbol(symbol, &symbols).ok_or_else(|| {
+            Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown or non-trading symbol: {}", symbol),
+            )) as Box<dyn StdError>
+        })
+    }
+
+    /// Canonical (trimmed, uppercase) form of `symbol` if it is in `tradable`.
+    pub fn normalize_symbol(symbol: &str, tradable: &HashSet<String>) -> Option<String> {
+        let canonical = symbol.trim().to_uppercase();
+        tradable.contains(&canonical).then_some(canonical)
+    }
+
     /// Returns a list of all trading pairs where the quote asset is USDT and status is TRADING.
     pub async fn get_pairs(&self,quote_asset: &str) -> Result<Vec<SymbolInfo>, reqwest::Error> {
         let exchange_info = self.get_exchange_info().await?;
@@ -163,6 +710,82 @@ impl Binance {
         Ok(klines)
     }
 
+    /// Same as `get_klines`, with each candle parsed into a `Kline`; malformed rows are dropped.
+    pub async fn get_klines_typed(&self, symbol: &str, interval: &str, limit: u16) -> Result<Vec<Kline>, reqwest::Error> {
+        let raw = self.get_klines(symbol, interval, limit).await?;
+        Ok(raw.iter().filter_map(|k| Kline::from_raw(k)).collect())
+    }
+
+    /// Open time (ms) of the first daily kline of `symbol`, which approximates its listing date.
+    pub async fn get_first_kline_time(&self, symbol: &str) -> Result<Option<i64>, reqwest::Error> {
+        let url = format!("{}/klines?symbol={}&interval=1d&startTime=0&limit=1", self.base_url, symbol);
+        let resp = self.client.get(&url).send().await?;
+        let klines = resp.json::<Vec<Vec<Value>>>().await?;
+        Ok(klines.first().and_then(|k| Kline::from_raw(k)).map(|k| k.open_time))
+    }
+
+    /// Fetch the top `depth` levels of the order book for a symbol.
+    pub async fn get_order_book(&self, symbol: &str, depth: u16) -> Result<OrderBook, reqwest::Error> {
+        let url = format!("{}/depth?symbol={}&limit={}", self.base_url, symbol, depth);
+        let resp = self.client.get(&url).send().await?;
+        let raw = resp.json::<DepthResponse>().await?;
+        let parse_levels = |levels: Vec<[String; 2]>| {
+            levels
+                .into_iter()
+                .filter_map(|[price, qty]| Some((price.parse::<f64>().ok()?, qty.parse::<f64>().ok()?)))
+                .collect()
+        };
+        Ok(OrderBook {
+            bids: parse_levels(raw.bids),
+            asks: parse_levels(raw.asks),
+        })
+    }
+
+    /// Read-only funding rate of `symbol`'s perpetual. Ok(None) when there is no perpetual
+    /// (Binance answers 400) or the response is unexpected; no futures account is needed.
+    pub async fn get_funding_rate(&self, symbol: &str) -> Result<Option<FundingRate>, reqwest::Error> {
+        let url = format!("{}/premiumIndex?symbol={}", self.futures_base_url, symbol);
+        let resp = self.client.get(&url).send().await?;
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+        Ok(FundingRate::from_premium_index(&resp.json::<Value>().await?))
+    }
+
+    /// Read-only open interest of `symbol`'s perpetual; Ok(None) when there is no perpetual.
+    pub async fn get_open_interest(&self, symbol: &str) -> Result<Option<OpenInterest>, reqwest::Error> {
+        let url = format!("{}/openInterest?symbol={}", self.futures_base_url, symbol);
+        let resp = self.client.get(&url).send().await?;
+        if !resp.status().is_success() {
+            return Ok(None);
+        }
+        Ok(OpenInterest::from_value(&resp.json::<Value>().await?))
+    }
+
+    /// Fetch the exchange server time in milliseconds since the epoch.
+    pub async fn get_server_time(&self) -> Result<u64, reqwest::Error> {
+        let url = format!("{}/time", self.base_url);
+        let resp = self.client.get(&url).send().await?;
+        let json = resp.json::<Value>().await?;
+        Ok(json["serverTime"].as_u64().unwrap_or(0))
+    }
+
+    /// Cheap connectivity check against `/ping`; any network or HTTP error is returned.
+    pub async fn ping(&self) -> Result<(), reqwest::Error> {
+        let url = format!("{}/ping", self.base_url);
+        self.client.get(&url).send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Checks whether Binance is in a maintenance window.
+    pub async fn system_status(&self) -> Result<SystemStatus, reqwest::Error> {
+        let url = format!("{}/system/status", self.sapi_base_url);
+        let resp = self.client.get(&url).send().await?;
+        let http_status = resp.status().as_u16();
+        let body = resp.json::<Value>().await.unwrap_or_default();
+        Ok(SystemStatus::from_response(http_status, &body))
+    }
+
     pub async fn subscribe_websocket(symbol: &str) {
         let url = format!("wss://stream.binance.com:9443/ws/{}@ticker", symbol.to_lowercase());
         let (ws_stream, _) = connect_async(Url::parse(&url).unwrap()).await.expect("WebSocket connection failed");
@@ -188,54 +811,18 @@ impl Binance {
 
       /// Fetches account information from Binance using a signed request.
     /// The API key and secret are loaded from environment variables.
-    pub async fn get_account_info(&self) -> Result<AccountInfo, reqwest::Error> {
-        // Load API credentials from environment variables.
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY")
-            .expect("BINANCE_API_KEY must be set in vars.env");
-        let secret_key = env::var("BINANCE_SECRET_KEY")
-            .expect("BINANCE_SECRET_KEY must be set in vars.env");
-
+    pub async fn get_account_info(&self) -> Result<AccountInfo, Box<dyn StdError>> {
         let endpoint = "/account";
         // Optional: set a recvWindow (default 5000 ms) to specify the allowed time difference.
         let recv_window = 5000;
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis();
-
-        // Build the query string with both timestamp and recvWindow.
-        let query = format!("timestamp={}&recvWindow={}", timestamp, recv_window);
-
-        // Sign the query string using HMAC-SHA256 with the secret key.
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())
-            .expect("HMAC can take key of any size");
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-
-        // Build the full URL including the signature.
-        let url = format!("{}{}?{}&signature={}", self.base_url, endpoint, query, signature);
-        //print!("{}", url);
-
-        // Send the GET request with the API key in the header.
-        let response = self.client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
 
-        // Read the response body as bytes.
-        let bytes = response.bytes().await?;
-        // Convert bytes to a string for debugging.
-        //let raw_body = String::from_utf8_lossy(&bytes);
-        //println!("Raw response body:\n{}", raw_body);
-        // Deserialize the JSON from the bytes.
-        let account_info: AccountInfo = serde_json::from_slice(&bytes)
-            .expect("Failed to deserialize account info");
-        Ok(account_info)
+        // send_signed appends the timestamp and signature.
+        let query = format!("recvWindow={}", recv_window);
+        let url = format!("{}{}", self.base_url, endpoint);
+        self.signed_json(Method::GET, &url, &query).await
     }
 
-    pub async fn get_account_balance(&self, asset: &str) -> Result<f64, reqwest::Error> {
+    pub async fn get_account_balance(&self, asset: &str) -> Result<f64, Box<dyn StdError>> {
         let account_info = self.get_account_info().await?;
         if let Some(balance) = account_info.balances.into_iter().find(|b| b.asset == asset) {
             if let Ok(free) = balance.free.parse::<f64>() {
@@ -245,34 +832,94 @@ impl Binance {
         Ok(0.0)
     }
 
-    pub async fn get_open_order_symbols(&self) -> Result<Vec<String>, reqwest::Error> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("BINANCE_API_KEY not set");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("BINANCE_SECRET_KEY not set");
-    
+    /// Total value of the account in USDT: free plus locked balance of every asset at current
+    /// prices. See `value_balances` for how assets without a USDT pair are handled.
+    pub async fn account_equity(&self) -> Result<f64, Box<dyn StdError>> {
+        let query = "recvWindow=5000".to_string();
+        let url = format!("{}{}", self.base_url, "/account");
+        let account: AccountInfo = self.signed_json(Method::GET, &url, &query).await?;
+        let prices = self.get_all_prices().await?;
+        Ok(Binance::value_balances(&account.balances, &prices, "USDT"))
+    }
+
+    /// Sums free + locked of each balance valued in `valuation_asset`: directly, through the
+    /// asset's `<asset><valuation_asset>` price, or via its BTC pair. Assets with none of those
+    /// prices (delisted, LD* earn tokens) count as 0.
+    pub fn value_balances(balances: &[Balance], prices: &HashMap<String, f64>, valuation_asset: &str) -> f64 {
+        let btc_price = prices.get(&format!("BTC{}", valuation_asset)).copied();
+        balances
+            .iter()
+            .map(|b| {
+                let amount = b.free.parse::<f64>().unwrap_or(0.0) + b.locked.parse::<f64>().unwrap_or(0.0);
+                if amount <= 0.0 {
+                    return 0.0;
+                }
+                let price = if b.asset == valuation_asset {
+                    Some(1.0)
+                } else {
+                    prices
+                        .get(&format!("{}{}", b.asset, valuation_asset))
+                        .copied()
+                        .or_else(|| Some(prices.get(&format!("{}BTC", b.asset))? * btc_price?))
+                };
+                amount * price.unwrap_or(0.0)
+            })
+            .sum()
+    }
+
+    /// Appends a daily account equity snapshot to `equity_log_path()`. Checks hourly, so a
+    /// restart on the same day does not write a second row.
+    pub async fn equity_snapshot_loop(&self) {
+        loop {
+            let today = Utc::now().format("%Y-%m-%d").to_string();
+            if equity_snapshot_due(&equity_log_path(), &today) {
+                match self.account_equity().await {
+                    Ok(equity) => {
+                        log_equity_snapshot(equity);
+                        println!("📸 Account equity snapshot: {:.2} USDT", equity);
+                        info!("📸 Account equity snapshot: {:.2} USDT", equity);
+                    }
+                    Err(e) => eprintln!("❌ Could not compute account equity: {}", e),
+                }
+            }
+            sleep(Duration::from_secs(60 * 60)).await;
+        }
+    }
+
+    /// Sums the free balance of every quote asset and reports whether it exceeds
+    /// `MAX_ACCOUNT_VALUE`, which usually means the bot is pointed at the wrong account.
+    pub async fn exceeds_max_account_value(&self, quote_assets: &[String]) -> Result<bool, Box<dyn StdError>> {
+        let max_account_value = get_max_account_value();
+        if max_account_value <= 0.0 {
+            return Ok(false);
+        }
+
+        let mut total = 0.0;
+        for quote in quote_assets {
+            total += self.get_account_balance(quote).await?;
+        }
+
+        if Binance::above_max_account_value(total, max_account_value) {
+            warn!("⚠️ Quote balance {:.2} exceeds MAX_ACCOUNT_VALUE {:.2}", total, max_account_value);
+            println!("⚠️ Quote balance {:.2} exceeds MAX_ACCOUNT_VALUE {:.2}", total, max_account_value);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// True when `total` quote balance is above `max_account_value`; a max of 0 disables the check.
+    pub fn above_max_account_value(total: f64, max_account_value: f64) -> bool {
+        max_account_value > 0.0 && total > max_account_value
+    }
+
+    pub async fn get_open_order_symbols(&self) -> Result<Vec<String>, Box<dyn StdError>> {
         let endpoint = "/openOrders";
         let recv_window = 5000;
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-    
-        let query = format!("timestamp={}&recvWindow={}", timestamp, recv_window);
-    
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-    
-        let url = format!("{}{}?{}&signature={}", self.base_url, endpoint, query, signature);
-
     
-        let response = self.client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+        let query = format!("recvWindow={}", recv_window);
     
-        let orders: Vec<OpenOrder> = response.json().await?;
+        let url = format!("{}{}", self.base_url, endpoint);
+        let orders: Vec<OpenOrder> = self.signed_json(Method::GET, &url, &query).await?;
 
         for order in &orders {
             info!("📘 Open Order: {} | Side: {} | Qty: {} | Price: {} | Type: {}",
@@ -282,7 +929,7 @@ impl Binance {
                 order.price,
                 order.type_field,
             );
-            println!("📘 Open Order: {} | Side: {} | Qty: {} | Price: {} | Type: {}",
+            status_println!("📘 Open Order: {} | Side: {} | Qty: {} | Price: {} | Type: {}",
                 order.symbol,
                 order.side,
                 order.orig_qty,
@@ -296,162 +943,205 @@ impl Binance {
         Ok(symbols)
     }
     
-    pub async fn place_market_buy_order(&self,symbol: &str,quantity: f64,) -> Result<u64, Box<dyn StdError>> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
+    /// Market buy of `quantity` units, which must already be floored to the symbol's step size;
+    /// it is sent as is so 8-decimal steps are not cut to 5.
+    pub async fn place_market_buy_order(&self, symbol: &str, quantity: f64) -> Result<OrderResult, Box<dyn StdError>> {
+        let req = OrderRequest::new(symbol, "BUY", "MARKET").quantity(quantity);
+        self.place_order(req).await
+    }
 
-        let query = format!(
-            "symbol={}&side=BUY&type=MARKET&quantity={:.5}&recvWindow=5000&timestamp={}",
-            symbol,
-            quantity,
-            timestamp
-        );
+    /// Parses the `fills` array of a FULL order response; malformed entries are skipped.
+    pub fn parse_fills(fills: &Value) -> Vec<OrderFill> {
+        let num = |v: &Value| v.as_str().and_then(|s| s.parse::<f64>().ok());
+        fills
+            .as_array()
+            .map(|fills| {
+                fills
+                    .iter()
+                    .filter_map(|f| {
+                        Some(OrderFill {
+                            price: num(&f["price"])?,
+                            qty: num(&f["qty"])?,
+                            commission: num(&f["commission"]).unwrap_or(0.0),
+                            commission_asset: f["commissionAsset"].as_str().unwrap_or_default().to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/order",
-            query,
-            signature
-        );
+    /// Base units actually received for `executed_qty`: commissions only reduce it when they were
+    /// charged in `base_asset`; fees paid in BNB or the quote asset leave it unchanged.
+    pub fn net_base_quantity(executed_qty: f64, fills: &[OrderFill], base_asset: &str) -> f64 {
+        let base_commission: f64 = fills
+            .iter()
+            .filter(|f| f.commission_asset == base_asset)
+            .map(|f| f.commission)
+            .sum();
+        (executed_qty - base_commission).max(0.0)
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+    /// Validates the symbol, signs and sends `req`, and parses the order id and fill quantities.
+    /// Spot orders go to `/api/v3/order`, futures orders (`req.futures`) to `/fapi/v1/order`.
+    pub async fn place_order(&self, mut req: OrderRequest) -> Result<OrderResult, Box<dyn StdError>> {
+        // Reject a bad timeInForce before touching the network, then build with the normalized symbol.
+        req.to_query()?;
+        req.symbol = self.validate_symbol(&req.symbol).await?;
+        if let Some(decimals) = get_quote_precision(&req.symbol) {
+            req = req.price_decimals(decimals);
+        }
+        let query = req.to_query()?;
 
-        let status = response.status();
-        let body = response.text().await?;
+        let base_url = if req.futures { &self.futures_base_url } else { &self.base_url };
+        let url = format!("{}{}", base_url, "/order");
+        let (status, body) = self.send_signed(Method::POST, &url, &query).await?;
 
         if status.is_success() {
             let parsed: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
-            let order_id = parsed["orderId"].as_u64().unwrap_or(0);
-            println!("✅ Market buy order placed successfully. Order ID: {}", order_id);
-            info!("✅ Market buy order placed: {:?}", parsed);
-            Ok(order_id)
+            log_order_audit(&parsed);
+            let result = OrderResult::from_response(&parsed);
+            println!("✅ {} {} order placed for {}. Order ID: {}", req.order_type, req.side, req.symbol, result.order_id);
+            info!("✅ {} {} order placed: {:?}", req.order_type, req.side, parsed);
+            Ok(result)
         } else {
-            eprintln!("❌ Failed to place market buy order: {}", body);
-            info!("❌ Failed to place market buy order: {}", body);
-            return Err(Box::<dyn StdError + Send + Sync>::from("Buy order failed"));
+            eprintln!("❌ Failed to place {} {} order for {}: {}", req.order_type, req.side, req.symbol, body);
+            info!("❌ Failed to place {} {} order for {}: {}", req.order_type, req.side, req.symbol, body);
+            Err(Box::new(std::io::Error::other(format!("{} {} order failed for {}", req.order_type, req.side, req.symbol))))
         }
     }
 
-    pub async fn place_trailing_stop_sell_order(&self, symbol: &str, quantity: f64, callback_rate: f64,  activation_price: Option<f64>,) -> Result<u64, Box<dyn std::error::Error>> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-
-        let mut query_parts = vec![
-            format!("symbol={}", symbol),
-            "side=SELL".to_string(),
-            "type=TRAILING_STOP_MARKET".to_string(),
-            format!("quantity={:.5}", quantity),
-            format!("callbackRate={:.1}", callback_rate),
-            "recvWindow=5000".to_string(),
-            format!("timestamp={}", timestamp),
-        ];
-
-        if let Some(price) = activation_price {
-            query_parts.push(format!("activationPrice={}", price));
-        }
+    /// Places a market sell of `quantity` units (already rounded to `step_size`).
+    pub async fn place_market_sell_order(&self, symbol: &str, quantity: f64) -> Result<u64, Box<dyn StdError>> {
+        let req = OrderRequest::new(symbol, "SELL", "MARKET").quantity(quantity);
+        Ok(self.place_order(req).await?.order_id)
+    }
 
-        let query = query_parts.join("&");
+    /// Places a GTC limit sell of `quantity` units at `price`.
+    pub async fn place_limit_sell_order(&self, symbol: &str, quantity: f64, price: f64) -> Result<u64, Box<dyn StdError>> {
+        let req = OrderRequest::new(symbol, "SELL", "LIMIT").tif(None, None).quantity(quantity).price(price);
+        Ok(self.place_order(req).await?.order_id)
+    }
 
-        
+    /// Splits `quantity` into take-profit limit sells (price, qty) and the remainder left for the stop.
+    /// Levels too small for minQty/minNotional are folded back into the remainder, and trailing levels
+    /// are dropped if the remainder itself would be too small to protect.
+    pub fn plan_take_profits(quantity: f64, entry_price: f64, levels: &[(f64, f64)], filters: &SymbolFilters) -> (Vec<(f64, f64)>, f64) {
+        let mut orders: Vec<(f64, f64)> = levels
+            .iter()
+            .filter_map(|(pct, fraction)| {
+                let price = Binance::round_to_step(entry_price * (1.0 + pct / 100.0), filters.tick_size);
+                let qty = Binance::round_to_step(quantity * fraction, filters.step_size);
+                (qty > 0.0 && qty >= filters.min_qty && qty * price >= filters.min_notional).then_some((price, qty))
+            })
+            .collect();
 
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/order",
-            query,
-            signature
-        );
+        loop {
+            let sold: f64 = orders.iter().map(|(_, qty)| qty).sum();
+            let remainder = Binance::round_to_step(quantity - sold, filters.step_size);
+            let protectable = remainder >= filters.min_qty && remainder * entry_price >= filters.min_notional;
+            if protectable || orders.is_empty() {
+                return (orders, remainder);
+            }
+            orders.pop();
+        }
+    }
 
-        let response = self
-            .client
-            .post(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+    /// Sells the part of a holding above MAX_POSITION_QUOTE and returns the remaining quantity.
+    async fn trim_position(&self, symbol: &str, quantity: f64, price: f64, filters: &SymbolFilters) -> f64 {
+        let Some(excess) = Binance::excess_position_quantity(quantity, price, get_max_position_quote(), filters) else {
+            return quantity;
+        };
+
+        println!("✂️ {} exceeds MAX_POSITION_QUOTE: selling {} of {} units", symbol, excess, quantity);
+        info!("✂️ {} exceeds MAX_POSITION_QUOTE: selling {} of {} units", symbol, excess, quantity);
+        if let Err(e) = self.place_market_sell_order(symbol, excess).await {
+            println!("❌ Failed to trim {}: {}", symbol, e);
+            return quantity;
+        }
 
-        let status = response.status();
-        let body = response.text().await?;
+        let trend = MARKET_TREND.read().await.clone();
+        log_trade_event(symbol, "TRIM", price, excess, price * excess, 0.0, "max_position_trim", &trend).await;
+        Binance::round_to_step(quantity - excess, filters.step_size)
+    }
 
-        if status.is_success() {
-            let parsed: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
-            let order_id = parsed["orderId"].as_u64().unwrap_or(0);
-            println!("✅ Trailing stop order placed successfully. Order ID: {}", order_id);
-            info!("✅ Trailing stop order placed successfully: {:?}", parsed);
-            Ok(order_id)
-        } else {
-            eprintln!("❌ Failed to place trailing stop order: {}", body);
-            info!("❌ Failed to place trailing stop order: {}", body);
+    /// Places a market buy that spends exactly `quote_amount` via `quoteOrderQty`,
+    /// letting Binance work out the units instead of `calculate_quantity_for_quote`.
+    pub async fn place_market_buy_quote(&self, symbol: &str, quote_amount: f64) -> Result<OrderResult, Box<dyn StdError>> {
+        let filters = self.cached_symbol_filters(symbol).await?;
+        let req = Binance::market_buy_quote_request(symbol, quote_amount, filters.quote_precision);
+        let fill = self.place_order(req).await?;
+        println!("✅ Market buy (quote) filled for {}: {:.6} units for {:.2} quote. Order ID: {}", symbol, fill.executed_qty, fill.quote_qty, fill.order_id);
+        info!("✅ Market buy (quote) filled for {}: {:.6} units for {:.2} quote. Order ID: {}", symbol, fill.executed_qty, fill.quote_qty, fill.order_id);
+        Ok(fill)
+    }
+
+    /// MARKET buy spending `quote_amount`, floored to the quote asset's `quote_precision` so
+    /// BTC/ETH-quoted pairs keep their decimals and the spend never exceeds the amount asked.
+    pub fn market_buy_quote_request(symbol: &str, quote_amount: f64, quote_precision: u32) -> OrderRequest {
+        let scale = 10_f64.powi(quote_precision as i32);
+        // The epsilon keeps e.g. 0.00123 from flooring to 0.00122999 through float error.
+        OrderRequest::new(symbol, "BUY", "MARKET").quote_order_qty((quote_amount * scale + 1e-6).floor() / scale)
+    }
+
+    /// Places a reduce-only TRAILING_STOP_MARKET sell on the futures API. Spot has no such order
+    /// type, so this fails with InvalidInput unless FUTURES_TRAILING_STOPS is enabled.
+    pub async fn place_trailing_stop_sell_order(&self, symbol: &str, quantity: f64, callback_rate: f64,  activation_price: Option<f64>, time_in_force: Option<&str>, good_till_date: Option<u64>,) -> Result<u64, Box<dyn std::error::Error>> {
+        // Validate before touching credentials or the network.
+        if self.trailing_stop_base_url(get_futures_trailing_stops()).is_none() {
             return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Trailing stop order failed",
+                std::io::ErrorKind::InvalidInput,
+                "TRAILING_STOP_MARKET is futures-only; set FUTURES_TRAILING_STOPS=true or use STOP_LOSS_LIMIT",
             )));
         }
+
+        let step_size = self.cached_symbol_filters(symbol).await?.step_size;
+        let req = OrderRequest::new(symbol, "SELL", "TRAILING_STOP_MARKET")
+            .futures()
+            .reduce_only()
+            .tif(time_in_force, good_till_date)
+            .quantity(Binance::round_to_step(quantity, step_size))
+            .callback_rate(Binance::round_decimals(callback_rate, 1))
+            .activation_price(activation_price);
+        Ok(self.place_order(req).await?.order_id)
     }
     
-    pub async fn get_executed_quantity(&self, symbol: &str, order_id: u64) -> Result<f64, Error> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-
-        let query = format!("symbol={}&orderId={}&timestamp={}&recvWindow=5000", symbol, order_id, timestamp);
+    pub async fn get_executed_quantity(&self, symbol: &str, order_id: u64) -> Result<f64, Box<dyn StdError>> {
 
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-
-        let url = format!("{}{}?{}&signature={}", self.base_url, "/order", query, signature);
-
-        let response = self
-            .client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+        let query = format!("symbol={}&orderId={}&recvWindow=5000", symbol, order_id);
 
-        let order: OrderResponse = response.json().await?;
+        let url = format!("{}{}", self.base_url, "/order");
+        let order: OrderResponse = self.signed_json(Method::GET, &url, &query).await?;
         let qty = order.executedQty.parse::<f64>().unwrap_or(0.0);
         Ok((qty * 100000.0).floor() / 100000.0)
     }
 
-    pub async fn execute_trade_with_fallback_stop(&self,symbol: &str, activation_price: Option<f64>,) -> Result<(), Box<dyn StdError>> {
+    /// Buys `symbol` and protects it, inside a `trade` span whose fresh `trade_id` the stop-loss
+    /// loop reuses for the position's later stop updates and sells. Returns the quote amount the
+    /// buy actually spent.
+    pub async fn execute_trade_with_fallback_stop(&self,symbol: &str, activation_price: Option<f64>,) -> Result<f64, Box<dyn StdError>> {
+        let trade_id = new_trade_id(symbol, Utc::now().timestamp_millis());
+        TRADE_IDS.lock().await.insert(symbol.to_string(), trade_id.clone());
+        self.open_trade(symbol, activation_price)
+            .instrument(trade_span(symbol, &trade_id))
+            .await
+    }
+
+    async fn open_trade(&self, symbol: &str, activation_price: Option<f64>) -> Result<f64, Box<dyn StdError>> {
         let quote_asset = &symbol[symbol.len() - 4..];
         let (quote_amount, stop_loss_percent) = get_quote_amount_and_stop_loss(quote_asset);
+        let stop_loss_percent = Binance::floored_stop_percent(stop_loss_percent, get_min_stop_distance_pct());
         // Get filters
         let filters = Binance::get_symbol_filters(self, symbol).await?;
-        let raw_qty = self.calculate_quantity_for_quote(symbol, quote_amount).await?;
-        let quantity = Binance::round_to_step(raw_qty, filters.step_size);
+        let quantity = self.calculate_quantity_for_quote(symbol, quote_amount, filters.step_size).await?;
         let trend = MARKET_TREND.read().await.clone();
+
+        let clamped = Binance::clamp_to_max_qty(quantity, &filters);
+        if clamped < quantity {
+            println!("✂️ {}: quantity {} exceeds maxQty {}; capping the order at {}", symbol, quantity, filters.max_qty, clamped);
+            warn!("✂️ {}: quantity {} exceeds maxQty {}; capping the order at {}", symbol, quantity, filters.max_qty, clamped);
+        }
+        let quantity = clamped;
     
         if quantity < filters.min_qty {
             println!("❌ {}: Adjusted quantity {:.5} below minQty {:.5}. Skipping.", symbol, quantity, filters.min_qty);
@@ -466,121 +1156,288 @@ impl Binance {
     
         // Wait briefly to ensure balance is updated on Binance's end
         // 1. Place market buy
-        let _buy_order_id = self.place_market_buy_order(symbol, quantity).await?;
+        let buy = self.place_market_buy_order(symbol, quantity).await?;
 
         // 2. Wait briefly for wallet to update
         tokio::time::sleep(Duration::from_secs(10)).await;
     
         let base_asset = &symbol[..symbol.len() - 4];
         let confirmed_balance = self.get_account_balance(base_asset).await?;
-        let adjusted_balance = Binance::round_to_step(confirmed_balance, filters.step_size);
-    
         let current_price = self.get_price(symbol).await?;
-        log_trade_event(symbol,"BUY",current_price,adjusted_balance,current_price * adjusted_balance,current_price * (1.0 - stop_loss_percent / 100.0),"placed_initial",&trend).await;
-    
-        let supports_trailing = self
-            .symbol_supports_order_type(symbol, "TRAILING_STOP_MARKET")
-            .await
-            .unwrap_or(false);
+
+        // Size the stop off what was received after fees: a commission paid in the base asset
+        // leaves less than was bought (one paid in BNB does not), and flooring that to stepSize
+        // can fall below min notional. Without fills, fall back to the free balance.
+        let received = if buy.fills.is_empty() {
+            confirmed_balance
+        } else {
+            Binance::net_base_quantity(buy.executed_qty, &buy.fills, base_asset).min(confirmed_balance)
+        };
+        let initial_stop = Binance::round_to_step(current_price * (1.0 - stop_loss_percent / 100.0), filters.tick_size);
+        let adjusted_balance = Binance::stop_quantity(received, confirmed_balance, initial_stop, &filters);
+        if adjusted_balance < quantity {
+            let fee_assets: HashSet<&str> = buy.fills.iter().map(|f| f.commission_asset.as_str()).collect();
+            info!("🧾 {}: bought {} but received {} after fees (paid in {:?}); sizing the stop off {}", symbol, quantity, received, fee_assets, adjusted_balance);
+        }
+        log_trade_event(symbol,"BUY",current_price,adjusted_balance,current_price * adjusted_balance,initial_stop,"placed_initial",&trend).await;
+        self.positions.lock().await.record_buy(symbol, current_price, adjusted_balance, Utc::now());
+        if Binance::is_dust(adjusted_balance, initial_stop, &filters) {
+            println!("⚠️ {}: post-fee balance {} cannot support a stop at {} (below min notional). Leaving it unprotected.", symbol, received, initial_stop);
+            warn!("⚠️ {}: post-fee balance {} cannot support a stop at {} (below min notional). Leaving it unprotected.", symbol, received, initial_stop);
+            return Ok(buy.quote_qty);
+        }
+
+        // Scale out: each take-profit limit sell locks its units, so only the remainder gets the stop.
+        let (take_profits, mut adjusted_balance) = Binance::plan_take_profits(adjusted_balance, current_price, &get_take_profit_levels(), &filters);
+        for (tp_price, tp_qty) in take_profits {
+            println!("🎯 Placing take-profit for {}: {} units @ {}", symbol, tp_qty, tp_price);
+            info!("🎯 Placing take-profit for {}: {} units @ {}", symbol, tp_qty, tp_price);
+            if let Err(e) = self.place_limit_sell_order(symbol, tp_qty, tp_price).await {
+                // Keep those units under the stop instead of leaving them unprotected.
+                println!("❌ Take-profit for {} failed, adding {} units back to the stop: {}", symbol, tp_qty, e);
+                adjusted_balance = Binance::round_to_step(adjusted_balance + tp_qty, filters.step_size);
+                continue;
+            }
+            log_trade_event(symbol,"TAKE_PROFIT",tp_price,tp_qty,tp_price * tp_qty,0.0,&format!("placed take-profit @ {}",tp_price),&trend).await;
+        }
     
-        if supports_trailing {
-            println!("📉 Using TRAILING_STOP_MARKET for {}", symbol);
-            info!("📉 Using TRAILING_STOP_MARKET for {}", symbol);
-            self.place_trailing_stop_sell_order(symbol, adjusted_balance, stop_loss_percent, activation_price).await?;
+        // Spot symbols never list TRAILING_STOP_MARKET; only futures mode can use it.
+        let supports_trailing = get_futures_trailing_stops()
+            && self.futures_supports_order_type(symbol, "TRAILING_STOP_MARKET").await.unwrap_or(false);
+    
+        let trailing_placed = if supports_trailing {
+            let entry = buy.avg_price().unwrap_or(current_price);
+            let (callback_rate, activation_price) = Binance::trailing_stop_params(entry, activation_price, filters.tick_size);
+            println!("📉 Using TRAILING_STOP_MARKET for {} (callback: {}%, activation: {:?})", symbol, callback_rate, activation_price);
+            info!("📉 Using TRAILING_STOP_MARKET for {} (callback: {}%, activation: {:?})", symbol, callback_rate, activation_price);
+            match self.place_trailing_stop_sell_order(symbol, adjusted_balance, callback_rate, activation_price, None, None).await {
+                Ok(_) => true,
+                Err(e) => {
+                    // The spot holding was just bought; never leave it without a stop.
+                    println!("⚠️ TRAILING_STOP_MARKET failed for {}: {}. Falling back to STOP_LOSS_LIMIT.", symbol, e);
+                    warn!("⚠️ TRAILING_STOP_MARKET failed for {}: {}. Falling back to STOP_LOSS_LIMIT.", symbol, e);
+                    false
+                }
+            }
         } else {
+            false
+        };
+
+        if !trailing_placed {
             println!("📉 Using STOP_LOSS_LIMIT for {}", symbol);
             info!("📉 Using STOP_LOSS_LIMIT for {}", symbol);
-            let stop_price = current_price * (1.0 - stop_loss_percent / 100.0);
-            let stop_price = Binance::round_to_step(stop_price, filters.tick_size);
-            let limit_price = stop_price;
+            let stop_price = initial_stop;
+            let limit_price = Binance::stop_limit_price(stop_price, get_stop_limit_buffer_pct(), filters.tick_size);
     
-            self.place_stop_loss_limit_order(symbol, adjusted_balance, stop_price, limit_price).await?;
+            self.place_stop_loss_limit_order(symbol, adjusted_balance, stop_price, limit_price, None, None).await?;
         }
     
         println!("✅ Trade + stop setup complete for {}", symbol);
         info!("✅ Trade + stop setup complete for {}", symbol);
-        Ok(())
+        Ok(buy.quote_qty)
     }
     
-    pub async fn count_today_losses(&self) -> Result<u32, Error> {
-        let _ = dotenv::from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
+    /// Opens a futures short on `symbol` with a reduce-only STOP_MARKET buy above entry, inside
+    /// a `trade` span like `execute_trade_with_fallback_stop`. Spot cannot short, so this fails
+    /// with InvalidInput unless ENABLE_SHORTS is set.
+    pub async fn execute_short_with_stop(&self, symbol: &str) -> Result<(), Box<dyn StdError>> {
+        if !get_enable_shorts() {
+            let reason = "shorting is unsupported on spot; set ENABLE_SHORTS=true with a futures-enabled account";
+            println!("⚠️ Not shorting {}: {}", symbol, reason);
+            warn!("⚠️ Not shorting {}: {}", symbol, reason);
+            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::InvalidInput, reason)));
+        }
+        let trade_id = new_trade_id(symbol, Utc::now().timestamp_millis());
+        TRADE_IDS.lock().await.insert(symbol.to_string(), trade_id.clone());
+        self.open_short(symbol)
+            .instrument(trade_span(symbol, &trade_id))
+            .await
+    }
+
+    async fn open_short(&self, symbol: &str) -> Result<(), Box<dyn StdError>> {
+        let quote_asset = &symbol[symbol.len() - 4..];
+        let (quote_amount, stop_loss_percent) = get_quote_amount_and_stop_loss(quote_asset);
+        let stop_loss_percent = Binance::floored_stop_percent(stop_loss_percent, get_min_stop_distance_pct());
+        // Size and price off the perpetual, whose lot and tick sizes differ from the spot pair's.
+        let filters = self.get_futures_symbol_filters(symbol).await?;
+        let current_price = self
+            .get_funding_rate(symbol)
+            .await?
+            .map(|funding| funding.mark_price)
+            .ok_or_else(|| format!("{} has no USDⓈ-M perpetual", symbol))?;
+        let quantity = Binance::clamp_to_max_qty(Binance::quantity_for_quote(quote_amount, current_price, filters.step_size), &filters);
+        let trend = MARKET_TREND.read().await.clone();
+
+        if quantity < filters.min_qty {
+            println!("❌ {}: Short quantity {:.5} below minQty {:.5}. Skipping.", symbol, quantity, filters.min_qty);
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Quantity too low: {} < {}", quantity, filters.min_qty),
+            )));
+        }
+
+        println!("📉 Executing market short for {} with {:.6} units ({} quote)", symbol, quantity, quote_amount);
+        info!("📉 Executing market short for {} with {:.6} units ({} quote)", symbol, quantity, quote_amount);
+        let entry = self.place_order(OrderRequest::new(symbol, "SELL", "MARKET").futures().quantity(quantity)).await?;
+        let entry_price = entry.avg_price().unwrap_or(current_price);
+
+        let stop_price = Binance::short_stop_price(entry_price, stop_loss_percent, filters.tick_size);
+        log_trade_event(symbol, "SHORT", entry_price, quantity, entry_price * quantity, stop_price, "placed_short_stop", &trend).await;
+        let stop = OrderRequest::new(symbol, "BUY", "STOP_MARKET")
+            .futures()
+            .reduce_only()
+            .quantity(quantity)
+            .stop_price(stop_price);
+        let stop_error = self.place_order(stop).await.err().map(|e| e.to_string());
+        if let Some(stop_error) = stop_error {
+            // Never leave the short open without its stop: buy it back right away.
+            println!("❌ Stop for the {} short failed: {}. Closing the short.", symbol, stop_error);
+            error!("❌ Stop for the {} short failed: {}. Closing the short.", symbol, stop_error);
+            let close = OrderRequest::new(symbol, "BUY", "MARKET").futures().reduce_only().quantity(quantity);
+            match self.place_order(close).await.map_err(|e| e.to_string()) {
+                Ok(cover) => {
+                    let cover_price = cover.avg_price().unwrap_or(entry_price);
+                    log_trade_event(symbol, "COVER", cover_price, quantity, cover_price * quantity, 0.0, "short_stop_failed", &trend).await;
+                }
+                Err(close_error) => {
+                    println!("🚨 UNPROTECTED SHORT: {} {} could not be closed: {}", quantity, symbol, close_error);
+                    error!("🚨 UNPROTECTED SHORT: {} {} could not be closed: {}", quantity, symbol, close_error);
+                }
+            }
+            return Err(format!("stop for the {} short failed: {}", symbol, stop_error).into());
+        }
+
+        println!("✅ Short + stop setup complete for {} (stop {} above entry {})", symbol, stop_price, entry_price);
+        info!("✅ Short + stop setup complete for {} (stop {} above entry {})", symbol, stop_price, entry_price);
+        Ok(())
+    }
+
+    /// Filters of `symbol`'s USDⓈ-M perpetual from the futures `/exchangeInfo`.
+    pub async fn get_futures_symbol_filters(&self, symbol: &str) -> Result<SymbolFilters, Box<dyn StdError>> {
+        let url = format!("{}/exchangeInfo", self.futures_base_url);
+        let info: Value = self.client.get(&url).send().await?.json().await?;
+        info["symbols"]
+            .as_array()
+            .and_then(|symbols| symbols.iter().find(|s| s["symbol"] == symbol))
+            .map(SymbolFilters::from_symbol_info)
+            .ok_or_else(|| format!("{} has no USDⓈ-M perpetual", symbol).into())
+    }
+
+    /// Symbols with an open USDⓈ-M futures short.
+    pub async fn get_futures_short_symbols(&self) -> Result<HashSet<String>, Box<dyn StdError>> {
+        // positionRisk is only served from v2 on.
+        let url = format!("{}/positionRisk", self.futures_base_url.replace("/fapi/v1", "/fapi/v2"));
+        let positions: Vec<Value> = self.signed_json(Method::GET, &url, "recvWindow=5000").await?;
+        Ok(Binance::short_symbols(&positions))
+    }
 
+    /// Symbols of the `/positionRisk` entries with a negative `positionAmt`.
+    pub fn short_symbols(positions: &[Value]) -> HashSet<String> {
+        positions
+            .iter()
+            .filter(|p| p["positionAmt"].as_str().and_then(|a| a.parse::<f64>().ok()).is_some_and(|a| a < 0.0))
+            .filter_map(|p| p["symbol"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Buy-back stop for a short: `stop_loss_percent` above `entry_price`, rounded to the tick.
+    pub fn short_stop_price(entry_price: f64, stop_loss_percent: f64, tick_size: f64) -> f64 {
+        Binance::round_to_step(entry_price * (1.0 + stop_loss_percent / 100.0), tick_size)
+    }
+
+    /// Counts today's losing round-trips. `/allOrders` only works per symbol, so this queries every
+    /// symbol in today's trade log plus every pair of a currently held asset.
+    pub async fn count_today_losses(&self) -> Result<u32, Box<dyn StdError>> {
         let now = Utc::now();
         let start_of_day = now.date_naive().and_hms_opt(0, 0, 0).unwrap();
         let start_time = start_of_day.and_utc().timestamp_millis();
         let end_time = now.timestamp_millis();
 
-        let query = format!(
-            "startTime={}&endTime={}&timestamp={}&recvWindow=5000",
-            start_time,
-            end_time,
-            end_time
-        );
+        let holdings = self.get_spot_balances().await?;
+        let mut symbols = traded_symbols_today();
+        symbols.extend(expand_holdings_to_pairs(&holdings, &get_quote_assets()));
+        symbols.sort();
+        symbols.dedup();
 
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/allOrders",
-            query,
-            signature
-        );
+        let quote_assets = get_quote_assets();
+        let mut losses = 0u32;
+        for symbol in symbols {
+            let Some((base, quote)) = split_symbol(&symbol, &quote_assets) else {
+                continue;
+            };
+            let trades = self.get_my_trades_typed(&symbol, Some(start_time as u64), Some(end_time as u64)).await?;
+            losses += Binance::count_losing_round_trips(&trades, base, quote);
+        }
 
-        let response = self.client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+        Ok(losses)
+    }
 
-            let text = response.text().await?;
-            let orders: Vec<Order> = match serde_json::from_str(&text) {
-                Ok(data) => data,
-                Err(_) => {
-                    info!("❌ Unexpected response: {}", text);
-                    eprintln!("❌ Unexpected response: {}", text);
-                    return Ok(0); // Gracefully assume no losses if response is malformed
-                }
-            };
-        let mut losses = 0u32;
-        let mut last_buy_price: Option<f64> = None;
+    /// Fetches the fills of `symbol` from `/myTrades`, optionally between `start_time` and `end_time` (ms).
+    /// Unknown symbols and other error bodies are logged and yield no trades.
+    pub async fn get_my_trades_typed(&self, symbol: &str, start_time: Option<u64>, end_time: Option<u64>) -> Result<Vec<MyTrade>, ReqwestError> {
+        let mut query = format!("symbol={}", symbol);
+        if let Some(start) = start_time {
+            query.push_str(&format!("&startTime={}", start));
+        }
+        if let Some(end) = end_time {
+            query.push_str(&format!("&endTime={}", end));
+        }
+        query.push_str("&recvWindow=5000");
 
-        for order in orders.into_iter().filter(|o| o.status == "FILLED") {
-            let qty = order.executed_qty.parse::<f64>().unwrap_or(0.0);
-            let quote = order.cummulative_quote_qty.parse::<f64>().unwrap_or(0.0);
+        let url = format!("{}{}", self.base_url, "/myTrades");
+        let (_, text) = self.send_signed(Method::GET, &url, &query).await?;
 
-            if qty == 0.0 {
-                continue;
+        match serde_json::from_str(&text) {
+            Ok(trades) => Ok(trades),
+            Err(_) => {
+                info!("❌ Unexpected /myTrades response for {}: {}", symbol, text);
+                verbose_println!("❌ Unexpected /myTrades response for {}: {}", symbol, text);
+                Ok(vec![])
             }
+        }
+    }
 
-            let avg_price = quote / qty;
+    /// Groups one symbol's fills (oldest first) into round trips, a run of buys followed by a run
+    /// of sells, and counts those whose proceeds fell short of the cost of the units sold.
+    /// Commissions paid in `quote` count as cost, those paid in `base` reduce the units bought;
+    /// commissions in other assets (e.g. BNB) are ignored. Sells without a prior buy are skipped.
+    pub fn count_losing_round_trips(trades: &[MyTrade], base: &str, quote: &str) -> u32 {
+        let mut losses = 0u32;
+        let (mut bought, mut cost, mut sold, mut proceeds) = (0.0, 0.0, 0.0, 0.0);
 
-            match order.side.as_str() {
-                "BUY" => {
-                    last_buy_price = Some(avg_price);
-                }
-                "SELL" => {
-                    if let Some(entry_price) = last_buy_price {
-                        if avg_price < entry_price {
-                            losses += 1;
-                            println!("🔻 Loss detected: bought at {:.2}, sold at {:.2}", entry_price, avg_price);
-                            info!("🔻 Loss detected: bought at {:.2}, sold at {:.2}", entry_price, avg_price);
-                        } else {
-                            info!("✅ Profit: bought at {:.2}, sold at {:.2}", entry_price, avg_price);
-                            println!("✅ Profit: bought at {:.2}, sold at {:.2}", entry_price, avg_price);
-                        }
-                        last_buy_price = None; // clear after one SELL
-                    }
+        let mut close = |bought: f64, cost: f64, sold: f64, proceeds: f64| {
+            if bought <= 0.0 || sold <= 0.0 {
+                return;
+            }
+            let cost_of_sold = cost * (sold / bought).min(1.0);
+            if proceeds < cost_of_sold {
+                losses += 1;
+                verbose_println!("🔻 Loss detected on {}: cost {:.4}, proceeds {:.4} {}", base, cost_of_sold, proceeds, quote);
+                info!("🔻 Loss detected on {}: cost {:.4}, proceeds {:.4} {}", base, cost_of_sold, proceeds, quote);
+            } else {
+                info!("✅ Profit on {}: cost {:.4}, proceeds {:.4} {}", base, cost_of_sold, proceeds, quote);
+                verbose_println!("✅ Profit on {}: cost {:.4}, proceeds {:.4} {}", base, cost_of_sold, proceeds, quote);
+            }
+        };
+
+        for trade in trades {
+            let fee_in_quote = if trade.commission_asset == quote { trade.commission } else { 0.0 };
+            let fee_in_base = if trade.commission_asset == base { trade.commission } else { 0.0 };
+            if trade.is_buyer {
+                if sold > 0.0 {
+                    // A buy after sells starts the next round trip.
+                    close(bought, cost, sold, proceeds);
+                    (bought, cost, sold, proceeds) = (0.0, 0.0, 0.0, 0.0);
                 }
-                _ => {}
+                bought += trade.qty - fee_in_base;
+                cost += trade.quote_qty + fee_in_quote;
+            } else if bought > 0.0 {
+                sold += trade.qty;
+                proceeds += trade.quote_qty - fee_in_quote;
             }
         }
-
-        Ok(losses)
+        close(bought, cost, sold, proceeds);
+        losses
     }
 
     pub async fn should_pause_for_losses(&self) -> Result<bool, Error> {
@@ -588,7 +1445,7 @@ impl Binance {
 
         match self.count_today_losses().await {
             Ok(losses) => {
-                println!("Today's confirmed losses: {} (max allowed: {})", losses, max_losses);
+                status_println!("Today's confirmed losses: {} (max allowed: {})", losses, max_losses);
                 info!(" Today's confirmed losses: {} (max allowed: {})", losses, max_losses);
                 Ok(losses >= max_losses)
             }
@@ -599,7 +1456,8 @@ impl Binance {
         }
     }
 
-    pub async fn calculate_quantity_for_quote(&self,symbol: &str,quote_amount: f64,) -> Result<f64, Box<dyn std::error::Error>> {
+    /// Units of `symbol` that `quote_amount` buys at the current price, floored to `step_size`.
+    pub async fn calculate_quantity_for_quote(&self,symbol: &str,quote_amount: f64, step_size: f64,) -> Result<f64, Box<dyn std::error::Error>> {
         let url = format!("{}/ticker/price?symbol={}", self.base_url, symbol);
         let response = self.client.get(&url).send().await?;
         let ticker: TickerPrice = response.json().await?;
@@ -613,11 +1471,10 @@ impl Binance {
         )));
     }
 
-        let quantity = quote_amount / price;
-        let rounded = (quantity * 100000.0).floor() / 100000.0; // round down to 5 decimal places
+        let rounded = Binance::quantity_for_quote(quote_amount, price, step_size);
 
-        info!("Calculated quantity for {} at {:.6} price: {:.6} units for {:.2} {}", symbol, price, rounded, quote_amount, &symbol[symbol.len()-4..]);
-        println!("Calculated quantity for {} at {:.6} price: {:.6} units for {:.2} {}", symbol, price, rounded, quote_amount, &symbol[symbol.len()-4..]);
+        info!("Calculated quantity for {} at {:.6} price: {} units for {:.2} {}", symbol, price, rounded, quote_amount, &symbol[symbol.len()-4..]);
+        status_println!("Calculated quantity for {} at {:.6} price: {} units for {:.2} {}", symbol, price, rounded, quote_amount, &symbol[symbol.len()-4..]);
 
         Ok(rounded)
     }
@@ -633,32 +1490,12 @@ impl Binance {
             .unwrap_or(false))
     }
 
-    pub async fn get_spot_balances(&self) -> Result<Vec<(String, f64)>, Error> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
-    
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-    
-        let query = format!("timestamp={}&recvWindow=5000", timestamp);
-    
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
+    pub async fn get_spot_balances(&self) -> Result<Vec<(String, f64)>, Box<dyn StdError>> {
     
-        let url = format!("{}{}?{}&signature={}", self.base_url, "/account", query, signature);
+        let query = "recvWindow=5000".to_string();
     
-        let response = self
-            .client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
-    
-        let account: AccountInfo = response.json().await?;
+        let url = format!("{}{}", self.base_url, "/account");
+        let account: AccountInfo = self.signed_json(Method::GET, &url, &query).await?;
     
         let excluded_assets = get_excluded_assets_spot();
 
@@ -692,6 +1529,18 @@ impl Binance {
         }
     }
 
+    /// Same as `symbol_supports_order_type`, but against the futures exchange info.
+    pub async fn futures_supports_order_type(&self, symbol: &str, order_type: &str) -> Result<bool, Error> {
+        let url = format!("{}/exchangeInfo", self.futures_base_url);
+        let response = self.client.get(&url).send().await?;
+        let info: ExchangeInfo = response.json().await?;
+
+        Ok(info
+            .symbols
+            .iter()
+            .any(|s| s.symbol == symbol && s.order_types.iter().any(|t| t == order_type)))
+    }
+
     /// Calculates a stop price given a current price and loss percentage
     fn calculate_stop_price(current_price: f64, stop_percent: f64) -> f64 {
     let stop_price = current_price * (1.0 - stop_percent / 100.0);
@@ -725,7 +1574,7 @@ impl Binance {
 
         let interval = get_stop_loss_loop_seconds();
 
-        println!("⏱ Sleeping {} seconds before next stop-loss check", interval);
+        status_println!("⏱ Sleeping {} seconds before next stop-loss check", interval);
         sleep(Duration::from_secs(interval)).await;
     }
 }
@@ -738,317 +1587,792 @@ impl Binance {
         Ok(price)
     }
 
-    pub async fn place_stop_loss_limit_order(&self,symbol: &str,quantity: f64,stop_price: f64,limit_price: f64,) -> Result<u64, Box<dyn StdError>> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY")?;
-        let secret_key = env::var("BINANCE_SECRET_KEY")?;
-    
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_millis();
-    
-        let query = format!(
-            "symbol={}&side=SELL&type=STOP_LOSS_LIMIT&quantity={:.5}&stopPrice={:.4}&price={:.4}&timeInForce=GTC&recvWindow=5000&timestamp={}",
-            symbol, quantity, stop_price, limit_price, timestamp
-        );
-    
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())?;
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-    
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/order",
-            query,
-            signature
-        );
-    
-        let response = self
-            .client
-            .post(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
-    
-        let status = response.status();
-        let body = response.text().await?;
-    
-        if status.is_success() {
-            let parsed: serde_json::Value = serde_json::from_str(&body)?;
-            let order_id = parsed["orderId"].as_u64().unwrap_or(0);
-            let trend = MARKET_TREND.read().await.clone();
-            log_trade_event(symbol,"SET",stop_price,quantity,stop_price * quantity,stop_price,&format!("placed stop-loss @ {:.4}",stop_price), &trend).await;
-            println!("✅ STOP_LOSS_LIMIT order placed for {}. Order ID: {}", symbol, order_id);
-            info!("✅ STOP_LOSS_LIMIT order placed: {:?}", parsed);
-            Ok(order_id)
-        } else {
-            eprintln!("❌ Failed to place STOP_LOSS_LIMIT for symbol {} order: {}", symbol, body);
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Failed to place stop loss limit order",
-            )))
+    /// Last price of every symbol in one request, keyed by symbol.
+    pub async fn get_all_prices(&self) -> Result<HashMap<String, f64>, ReqwestError> {
+        let url = format!("{}/ticker/price", self.base_url);
+        let response = self.client.get(&url).send().await?;
+        let tickers: Vec<Value> = response.json().await?;
+        Ok(tickers
+            .iter()
+            .filter_map(|t| {
+                let symbol = t["symbol"].as_str()?;
+                let price = t["price"].as_str()?.parse::<f64>().ok()?;
+                Some((symbol.to_string(), price))
+            })
+            .collect())
+    }
+
+    /// Places a STOP_LOSS_LIMIT sell. `time_in_force` defaults to GTC; GTD requires `good_till_date` (ms).
+    /// Prices are sent at the symbol's tick size precision (a QUOTE_PRECISION_OVERRIDES entry
+    /// still wins in `place_order`); `quantity` must already be floored to the step size.
+    pub async fn place_stop_loss_limit_order(&self,symbol: &str,quantity: f64,stop_price: f64,limit_price: f64, time_in_force: Option<&str>, good_till_date: Option<u64>,) -> Result<u64, Box<dyn StdError>> {
+        // Reject a bad timeInForce before any request, including the filter lookup.
+        Binance::time_in_force_params(time_in_force, good_till_date)?;
+        let tick_size = self.cached_symbol_filters(symbol).await?.tick_size;
+        let req = OrderRequest::new(symbol, "SELL", "STOP_LOSS_LIMIT")
+            .tif(time_in_force, good_till_date)
+            .quantity(quantity)
+            .stop_price(Binance::round_to_tick_precision(stop_price, tick_size))
+            .price(Binance::round_to_tick_precision(limit_price, tick_size));
+        let order_id = self.place_order(req).await?.order_id;
+
+        let trend = MARKET_TREND.read().await.clone();
+        log_trade_event(symbol,"SET",stop_price,quantity,stop_price * quantity,stop_price,&format!("placed stop-loss @ {:.4}",stop_price), &trend).await;
+        Ok(order_id)
+    }
+
+    /// Price at which a trailing stop arms: `entry` plus `activation_pct` percent, rounded to
+    /// `tick_size`. None when the threshold is disabled (0), so the trail arms immediately.
+    pub fn trailing_activation_price(entry: f64, activation_pct: f64, tick_size: f64) -> Option<f64> {
+        (activation_pct > 0.0 && entry > 0.0)
+            .then(|| Binance::round_to_step(entry * (1.0 + activation_pct / 100.0), tick_size))
+    }
+
+    /// Callback rate and activation price of a trailing stop on a position entered at `entry`.
+    /// Trail tightness and arming threshold are tuned separately: the callback rate comes from
+    /// TRAILING_CALLBACK_PCT, the activation from TRAILING_ACTIVATION_PCT above entry unless
+    /// `activation_price` is given.
+    pub fn trailing_stop_params(entry: f64, activation_price: Option<f64>, tick_size: f64) -> (f64, Option<f64>) {
+        let activation_price = activation_price
+            .or_else(|| Binance::trailing_activation_price(entry, get_trailing_activation_pct(), tick_size));
+        (get_trailing_callback_pct(), activation_price)
+    }
+
+    /// Limit price of a STOP_LOSS_LIMIT order: `buffer_pct` below the trigger, rounded down to
+    /// `tick_size`, so a gap through the trigger still leaves room for the sell to fill.
+    /// A buffer of 0 keeps the limit at the trigger.
+    pub fn stop_limit_price(stop_price: f64, buffer_pct: f64, tick_size: f64) -> f64 {
+        if buffer_pct <= 0.0 {
+            return stop_price;
         }
+        let limit = stop_price * (1.0 - buffer_pct / 100.0);
+        if tick_size <= 0.0 {
+            return limit;
+        }
+        Binance::round_to_step(limit, tick_size).max(tick_size)
     }
 
-    /// Periodically check held spot tokens and ensure a stop-loss is in place or updated.
-    pub async fn manage_stop_loss_limit_loop(&self) {
-        loop {
-            let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S");
-            let trend = MARKET_TREND.read().await.clone();
-            println!("🔁 [{}] Starting stop-loss management loop", timestamp);
-            info!("🔁 [{}] Starting stop-loss management loop", timestamp);
-    
-            let balances = match self.get_spot_balances().await {
-                Ok(b) => b,
-                Err(e) => {
-                    error!("Failed to fetch balances: {}", e);
-                    println!("❌ Failed to fetch balances: {}", e);
-                    sleep(Duration::from_secs(60)).await;
-                    continue;
-                }
-            };
-    
-            let quote_assets = get_quote_assets();
-    
-            let open_orders = match self.get_open_orders().await {
-                Ok(orders) => orders,
-                Err(e) => {
-                    error!("Failed to fetch full open orders: {}", e);
-                    println!("❌ Failed to fetch full open orders: {}", e);
-                    vec![]
-                }
+    /// Loss-side stop distance (%): the configured `stop_loss_percent`, widened to `min_distance_pct`
+    /// so a tight stop on a volatile token is not inside normal noise. 0 disables the floor.
+    pub fn floored_stop_percent(stop_loss_percent: f64, min_distance_pct: f64) -> f64 {
+        stop_loss_percent.max(min_distance_pct)
+    }
+
+    /// Price the initial stop is measured from: the entry price when known, unless the market
+    /// has already fallen through that stop (an exchange would reject it), else the current price.
+    pub fn initial_stop_anchor(entry_price: Option<f64>, current_price: f64, stop_loss_percent: f64) -> f64 {
+        match entry_price {
+            Some(entry) if entry > 0.0 && entry * (1.0 - stop_loss_percent / 100.0) < current_price => entry,
+            _ => current_price,
+        }
+    }
+
+    /// Entry price for `symbol` from the position store or `PURCHASE_PRICES`, falling back to
+    /// trade history. Not cached here: the stop loop treats any tracked symbol without a stop as sold.
+    async fn entry_price(&self, symbol: &str) -> Option<f64> {
+        if let Some(price) = self.positions.lock().await.entry_price(symbol) {
+            return Some(price);
+        }
+        if let Some(price) = PURCHASE_PRICES.lock().await.get(symbol) {
+            return Some(*price);
+        }
+        self.get_last_buy_price(symbol).await.ok().flatten()
+    }
+
+    /// Profit-locking mode (`lock_pct` > 0): once `price` is above `entry`, the stop becomes
+    /// max(trailing, existing, entry, price - lock_pct%), so a pullback keeps the gains already locked.
+    /// Otherwise returns `trailing` unchanged.
+    pub fn profit_locked_stop(trailing: f64, existing: f64, entry: f64, price: f64, lock_pct: f64) -> f64 {
+        if lock_pct <= 0.0 || price <= entry {
+            return trailing;
+        }
+        trailing
+            .max(existing)
+            .max(entry)
+            .max(price * (1.0 - lock_pct / 100.0))
+    }
+
+    /// Trailing distance (%) that shrinks smoothly with the unrealized gain (%):
+    /// `base_pct / (1 + k * gain_pct)`. Strictly decreasing in the gain for any `k > 0`.
+    pub fn curved_stop_percent(base_pct: f64, k: f64, gain_pct: f64) -> f64 {
+        base_pct / (1.0 + k * gain_pct.max(0.0))
+    }
+
+    /// Stop distance (%) below `current_price`: the base stop until a 2% gain, then a trailing
+    /// stop (tighter above 10% gain, or following `curved_stop_percent` when STOP_CURVE_K is
+    /// set) that never locks in less than 1% profit.
+    pub fn trailing_stop_percent(symbol: &str, current_price: f64, purchase_price: f64) -> f64 {
+        let base_trailing_sl_percent = get_stop_loss_percent_profit() / 100.0;  // e.g. 0.05
+        let min_profit_lock = 0.01;  // 1% minimum profit lock
+        let tighter_trailing_sl_percent = get_stop_loss_percent_profit_10() / 100.0;   // e.g 2.5
+        let tighten_threshold = 0.10; // 10% gain to tighten
+        let trailing_activation_threshold = 0.02; // activate after 2% gain
+    
+        let gain = (current_price - purchase_price) / purchase_price;   
+    
+        if gain >= trailing_activation_threshold {
+            // Calculate stop that either trails by X% or locks 1%, whichever is higher
+            let curve_k = get_stop_curve_k();
+            let trailing_sl_percent = if curve_k > 0.0 {
+                Binance::curved_stop_percent(get_stop_curve_base_pct(), curve_k, gain * 100.0) / 100.0
+            } else if gain >= tighten_threshold {
+                verbose_println!("[{}] 🎯 Gain {:.2} ≥ 10%, tightening SL to [{}]%", symbol, gain * 100.0,tighter_trailing_sl_percent*100.0);
+                tighter_trailing_sl_percent
+            } else {
+                base_trailing_sl_percent
             };
-    
-            let trailing_stop_symbols: HashSet<String> = open_orders
-                .iter()
-                .filter(|o| o.type_field == "TRAILING_STOP_MARKET")
-                .map(|o| o.symbol.clone())
-                .collect();
-    
-            let stop_limit_symbols: HashSet<String> = open_orders
-                .iter()
-                .filter(|o| o.type_field == "STOP_LOSS_LIMIT")
-                .map(|o| o.symbol.clone())
-                .collect();
-    
-            let active_symbols: HashSet<String> = stop_limit_symbols.union(&trailing_stop_symbols).cloned().collect();
-    
-            // Clean up purchase prices for tokens no longer in open orders
-            {
-                let mut purchase_prices = PURCHASE_PRICES.lock().await;
-                let prev_symbols: HashSet<String> = purchase_prices.keys().cloned().collect();
-                for symbol in prev_symbols.difference(&active_symbols) {
-                    log_trade_event(symbol,"SELL",0.0,0.0,0.0,0.0,"stop_hit",&trend).await;
-                    println!("📉 Logged SELL for {} — stop order no longer active", symbol);
-                    info!("📉 Logged SELL for {} — stop order no longer active", symbol);
-                }
-                purchase_prices.retain(|symbol, _| active_symbols.contains(symbol));
+            let trailing_stop_price = current_price * (1.0 - trailing_sl_percent);
+            let locked_profit_price = purchase_price * (1.0 + min_profit_lock);
+            let stop_price_1 = trailing_stop_price.max(locked_profit_price);
+    
+            verbose_println!(
+                "[{}] SL tighten triggered: {:.2}% gain → stop_price = max({:.4} [trailing], {:.4} [lock 1%]) = {:.4}",
+                symbol,
+                gain * 100.0,
+                trailing_stop_price,
+                locked_profit_price,
+                stop_price_1
+            );
+            // Return stop price as % below current price, capped at locking at least 1% gain
+            (1.0 - (stop_price_1 / current_price)) * 100.0
+        } else {
+            Binance::floored_stop_percent(get_stop_loss_percent(), get_min_stop_distance_pct())
+        }
+    }
 
+    /// Places the initial stop for a holding without one. Returns the holding's quote value when
+    /// it is dust that no stop can protect, so the caller can tally it.
+    async fn place_initial_stop(&self, symbol: &str, balance: f64, price: f64) -> Option<f64> {
+        let filters = match self.cached_symbol_filters(symbol).await {
+            Ok(f) => f,
+            Err(e) => {
+                println!("❌ Failed to fetch filters for {}: {}", symbol, e);
+                Binance::note_stop_attempt(symbol, false, balance * price).await;
+                return None;
             }
-    
-            // PLACE INITIAL STOP-LOSS IF NONE EXISTS
-            for (asset, balance) in balances {
-                if quote_assets.contains(&asset) {
-                    continue;
-                }
-    
-                for quote in &quote_assets {
-                    let symbol = format!("{}{}", asset, quote);
-    
-                    if trailing_stop_symbols.contains(&symbol) || stop_limit_symbols.contains(&symbol) {
-                        continue;
-                    }
-    
-                    let price = match self.get_price(&symbol).await {
-                        Ok(p) => p,
-                        Err(_) => continue,
-                    };
-    
-                    let filters = match Binance::get_symbol_filters(self, &symbol).await {
-                        Ok(f) => f,
-                        Err(_) => continue,
-                    };
-    
-                    let stop_loss_percent = get_stop_loss_percent();
-                    let stop_price = Binance::round_to_step(price * (1.0 - stop_loss_percent / 100.0), filters.tick_size);
-                    let quantity = Binance::round_to_step(balance, filters.step_size);
-                    let notional = stop_price * quantity;
-    
-                    if quantity < 1.0 || quantity < filters.min_qty || stop_price <= 0.0 || stop_price < filters.min_price || notional < filters.min_notional {
-                        continue;
+        };
+
+        let entry_price = if get_initial_stop_from_entry() {
+            self.entry_price(symbol).await
+        } else {
+            None
+        };
+        let stop_loss_percent = Binance::floored_stop_percent(get_stop_loss_percent(), get_min_stop_distance_pct());
+        let anchor = Binance::initial_stop_anchor(entry_price, price, stop_loss_percent);
+        let stop_price = Binance::round_to_step(anchor * (1.0 - stop_loss_percent / 100.0), filters.tick_size);
+        let quantity = Binance::stop_quantity(balance, balance, stop_price, &filters);
+
+        if Binance::is_dust(quantity, stop_price, &filters) {
+            // Leftovers from fees and partial fills: no stop can be placed, so just tally them.
+            return Some(balance * price);
+        }
+
+        let quantity = self.trim_position(symbol, quantity, price, &filters).await;
+
+        println!("🔒 Placing initial stop-loss for {} at {:.4} (anchored at {:.4})", symbol, stop_price, anchor);
+        let placed = match self.place_stop_loss_limit_order(symbol, quantity, stop_price, Binance::stop_limit_price(stop_price, get_stop_limit_buffer_pct(), filters.tick_size), None, None).await {
+            Ok(_) => true,
+            Err(e) => {
+                println!("❌ Failed to place stop-loss for {}: {}", symbol, e);
+                false
+            }
+        };
+        Binance::note_stop_attempt(symbol, placed, balance * price).await;
+        None
+    }
+
+    /// Tracks the failure streak of `symbol` and raises the unprotected-holding alert when it
+    /// reaches `UNPROTECTED_ALERT_AFTER`.
+    async fn note_stop_attempt(symbol: &str, placed: bool, value: f64) {
+        let threshold = get_unprotected_alert_after();
+        let mut failures = STOP_FAILURES.lock().await;
+        if Binance::record_stop_attempt(&mut failures, symbol, placed, threshold) {
+            error!("🚨 UNPROTECTED HOLDING: {} worth {:.4} quote has failed stop placement {} times in a row", symbol, value, threshold);
+            println!("🚨 UNPROTECTED HOLDING: {} worth {:.4} quote has failed stop placement {} times in a row", symbol, value, threshold);
+        }
+    }
+
+    /// Trims, RSI-exits or ratchets one existing STOP_LOSS_LIMIT order at `current_price`.
+    async fn update_stop(&self, order: &OpenOrder, current_price: f64, trend: &str, timestamp: &str) {
+        let symbol = &order.symbol;
+
+        let filters = match self.cached_symbol_filters(symbol).await {
+            Ok(f) => f,
+            Err(e) => {
+                println!("❌ Failed to fetch filters for {}: {}", symbol, e);
+                return;
+            }
+        };
+
+        // A stop exists now, so track the entry like a backfilled one; the cleanup then logs its SELL.
+        let bought_here = self.positions.lock().await.entry_price(symbol);
+        if let Some(price) = bought_here {
+            PURCHASE_PRICES.lock().await.entry(symbol.clone()).or_insert(price);
+        }
+        let known_price = PURCHASE_PRICES.lock().await.get(symbol).copied();
+        let purchase_price = match known_price {
+            Some(p) => p,
+            None => {
+                let last_buy = self.get_last_buy_price(symbol).await.ok().flatten();
+                match last_buy {
+                    Some(price) => {
+                        println!("💾 [{}] Backfilled purchase price for {}: {:.4}", timestamp, symbol, price);
+                        PURCHASE_PRICES.lock().await.insert(symbol.clone(), price);
+                        price
                     }
-    
-                    println!("🔒 Placing initial stop-loss for {} at {:.4}", symbol, stop_price);
-                    if let Err(e) = self.place_stop_loss_limit_order(&symbol, quantity, stop_price, stop_price).await {
-                        println!("❌ Failed to place stop-loss for {}: {}", symbol, e);
+                    None => {
+                        status_println!("⚠️ No purchase price found for {}. Skipping...", symbol);
+                        return;
                     }
                 }
             }
-    
-            // UPDATE STOP-LOSS IF ABOVE BREAK-EVEN
-            for order in open_orders.iter().filter(|o| o.type_field == "STOP_LOSS_LIMIT") {
-                let symbol = &order.symbol;
-    
-                if trailing_stop_symbols.contains(symbol) {
-                    continue;
+        };
+
+        // Trim oversized positions: the stop locks the units, so cancel, sell the excess and re-protect the rest.
+        let order_qty = Binance::round_to_step(order.orig_qty.parse::<f64>().unwrap_or(0.0), filters.step_size);
+        if Binance::excess_position_quantity(order_qty, current_price, get_max_position_quote(), &filters).is_some() {
+            if let Err(e) = self.cancel_order(symbol, order.order_id).await {
+                println!("❌ Failed to cancel stop-loss for {} before trimming: {}", symbol, e);
+                return;
+            }
+            let remaining = self.trim_position(symbol, order_qty, current_price, &filters).await;
+            let existing_stop = order.stop_price.parse::<f64>().unwrap_or(0.0);
+            if let Err(e) = self.place_stop_loss_limit_order(symbol, remaining, existing_stop, Binance::stop_limit_price(existing_stop, get_stop_limit_buffer_pct(), filters.tick_size), None, None).await {
+                println!("❌ Failed to re-place stop-loss for {} after trimming: {}", symbol, e);
+            }
+            return;
+        }
+
+        // Sell into strength: trim once per overbought run, then re-protect the rest.
+        let rsi_threshold = get_rsi_exit_threshold();
+        if rsi_threshold > 0.0 {
+            let rsi = self.latest_rsi(symbol).await;
+            let already_exited = {
+                let mut exited = RSI_EXITED.lock().await;
+                if !rsi.is_some_and(|r| r > rsi_threshold) {
+                    exited.remove(symbol);
+                    true
+                } else {
+                    exited.contains(symbol)
                 }
-    
-                let filters = match Binance::get_symbol_filters(self, symbol).await {
-                    Ok(f) => f,
-                    Err(e) => {
-                        println!("❌ Failed to fetch filters for {}: {}", symbol, e);
-                        continue;
-                    }
-                };
-    
-                let current_price = match self.get_price(symbol).await {
-                    Ok(p) => p,
-                    Err(e) => {
-                        println!("❌ Failed to fetch price for {}: {}", symbol, e);
-                        continue;
-                    }
-                };
-    
-                let mut purchase_prices = PURCHASE_PRICES.lock().await;
-                let purchase_price = match purchase_prices.get(symbol) {
-                    Some(p) => *p,
-                    None => {
-                        match self.get_last_buy_price(symbol).await {
-                            Ok(Some(price)) => {
-                                println!("💾 [{}] Backfilled purchase price for {}: {:.4}", timestamp, symbol, price);
-                                purchase_prices.insert(symbol.clone(), price);
-                                price
-                            }
-                            _ => {
-                                println!("⚠️ No purchase price found for {}. Skipping...", symbol);
-                                continue;
-                            }
-                        }
+            };
+            if !already_exited {
+                if let Some(sell_qty) = Binance::rsi_exit_quantity(order_qty, get_rsi_exit_fraction(), current_price, &filters) {
+                    println!("🔥 {} RSI {:.1} above {}: selling {} of {} units", symbol, rsi.unwrap_or(0.0), rsi_threshold, sell_qty, order_qty);
+                    info!("🔥 {} RSI {:.1} above {}: selling {} of {} units", symbol, rsi.unwrap_or(0.0), rsi_threshold, sell_qty, order_qty);
+                    if let Err(e) = self.cancel_order(symbol, order.order_id).await {
+                        println!("❌ Failed to cancel stop-loss for {} before the RSI exit: {}", symbol, e);
+                        return;
                     }
-                };
-    
-                let stop_loss_percent = {
-                    let base_trailing_sl_percent = get_stop_loss_percent_profit() / 100.0;  // e.g. 0.05
-                    let min_profit_lock = 0.01;  // 1% minimum profit lock
-                    let tighter_trailing_sl_percent = get_stop_loss_percent_profit_10() / 100.0;   // e.g 2.5
-                    let tighten_threshold = 0.10; // 10% gain to tighten
-                    let trailing_activation_threshold = 0.02; // activate after 2% gain
-                
-                    let gain = (current_price - purchase_price) / purchase_price;   
-                
-                    if gain >= trailing_activation_threshold {
-                        // Calculate stop that either trails by X% or locks 1%, whichever is higher
-                        let trailing_sl_percent = if gain >= tighten_threshold {
-                            println!("[{}] 🎯 Gain {:.2} ≥ 10%, tightening SL to [{}]%", symbol, gain * 100.0,tighter_trailing_sl_percent*100.0);
-                            tighter_trailing_sl_percent
-                        } else {
-                            base_trailing_sl_percent
-                        };
-                        let trailing_stop_price = current_price * (1.0 - trailing_sl_percent);
-                        let locked_profit_price = purchase_price * (1.0 + min_profit_lock);
-                        let stop_price_1 = trailing_stop_price.max(locked_profit_price);
-                
-                        println!(
-                            "[{}] SL tighten triggered: {:.2}% gain → stop_price = max({:.4} [trailing], {:.4} [lock 1%]) = {:.4}",
-                            symbol,
-                            gain * 100.0,
-                            trailing_stop_price,
-                            locked_profit_price,
-                            stop_price_1
-                        );
-                        // Return stop price as % below current price, capped at locking at least 1% gain
-                        (1.0 - (stop_price_1 / current_price)) * 100.0
+                    let existing_stop = order.stop_price.parse::<f64>().unwrap_or(0.0);
+                    let remaining = if self.place_market_sell_order(symbol, sell_qty).await.is_ok() {
+                        RSI_EXITED.lock().await.insert(symbol.clone());
+                        log_trade_event(symbol, "TRIM", current_price, sell_qty, current_price * sell_qty, existing_stop, "rsi_overbought", trend).await;
+                        Binance::round_to_step(order_qty - sell_qty, filters.step_size)
                     } else {
-                        get_stop_loss_percent()  // already returns a percentage
+                        order_qty
+                    };
+                    if let Err(e) = self.place_stop_loss_limit_order(symbol, remaining, existing_stop, Binance::stop_limit_price(existing_stop, get_stop_limit_buffer_pct(), filters.tick_size), None, None).await {
+                        println!("❌ Failed to re-place stop-loss for {} after the RSI exit: {}", symbol, e);
                     }
-                };
-                //trailing behaviour
-                let stop_price = Binance::round_to_step(current_price * (1.0 - stop_loss_percent / 100.0), filters.tick_size);
-                let existing_stop = order.stop_price.parse::<f64>().unwrap_or(0.0);
-                let rounded_existing = Binance::round_to_step(existing_stop, filters.tick_size);
-                let rounded_new = Binance::round_to_step(stop_price, filters.tick_size);
-    
-                if rounded_new > rounded_existing {
-                    let quantity = Binance::round_to_step(order.orig_qty.parse::<f64>().unwrap_or(0.0), filters.step_size);
-    
-                    println!("📊 {} current: {:.4}, purchase: {:.4}, existing stop: {:.4}, new stop: {:.4}", symbol, current_price, purchase_price, existing_stop, stop_price);
-    
-                    if let Err(e) = self.cancel_order(symbol, order.order_id).await {
-                        println!("❌ Failed to cancel old stop-loss for {}: {}", symbol, e);
-                        continue;
-                    }
-    
-                    if let Err(e) = self.place_stop_loss_limit_order(symbol, quantity, stop_price, stop_price).await {
-                        println!("❌ Failed to update stop-loss for {}: {}", symbol, e);
-                    } else {
-                        println!("✅ Updated stop-loss for {} to {:.4}% ({} → {})", symbol, stop_loss_percent, existing_stop, stop_price);
-                    }
-                } else {
-                    println!("✅ No update needed for {} — stop {:.4} is still valid", symbol, existing_stop);
+                    return;
                 }
             }
-    
+        }
+
+        let stop_loss_percent = Binance::trailing_stop_percent(symbol, current_price, purchase_price);
+        //trailing behaviour
+        let existing_stop = order.stop_price.parse::<f64>().unwrap_or(0.0);
+        let trailing = current_price * (1.0 - stop_loss_percent / 100.0);
+        let locked = Binance::profit_locked_stop(trailing, existing_stop, purchase_price, current_price, get_profit_lock_trail_pct());
+        let stop_price = Binance::round_to_step(locked, filters.tick_size);
+        let stop_loss_percent = (1.0 - stop_price / current_price) * 100.0;
+        let rounded_existing = Binance::round_to_step(existing_stop, filters.tick_size);
+        let rounded_new = Binance::round_to_step(stop_price, filters.tick_size);
+
+        if rounded_new > rounded_existing {
+            let quantity = Binance::round_to_step(order.orig_qty.parse::<f64>().unwrap_or(0.0), filters.step_size);
+
+            status_println!("📊 {} current: {:.4}, purchase: {:.4}, existing stop: {:.4}, new stop: {:.4}", symbol, current_price, purchase_price, existing_stop, stop_price);
+
+            if let Err(e) = self.cancel_order(symbol, order.order_id).await {
+                println!("❌ Failed to cancel old stop-loss for {}: {}", symbol, e);
+                return;
+            }
+
+            if let Err(e) = self.place_stop_loss_limit_order(symbol, quantity, stop_price, Binance::stop_limit_price(stop_price, get_stop_limit_buffer_pct(), filters.tick_size), None, None).await {
+                println!("❌ Failed to update stop-loss for {}: {}", symbol, e);
+            } else {
+                println!("✅ Updated stop-loss for {} to {:.4}% ({} → {})", symbol, stop_loss_percent, existing_stop, stop_price);
+            }
+        } else {
+            status_println!("✅ No update needed for {} — stop {:.4} is still valid", symbol, existing_stop);
+        }
+    }
+
+    /// Places initial stops for `(asset, symbol, balance, price)` holdings, up to `concurrency`
+    /// at a time. Returns the quote value of each asset too small to protect, counted once even
+    /// when it is held against several quote assets.
+    async fn place_missing_stops(&self, unprotected: Vec<(String, String, f64, f64)>, concurrency: usize) -> HashMap<String, f64> {
+        let dust_values: Vec<Option<(String, f64)>> = stream::iter(unprotected)
+            .map(|(asset, symbol, balance, price)| async move {
+                let span = trade_span(&symbol, &trade_id_for(&symbol).await);
+                self.place_initial_stop(&symbol, balance, price)
+                    .instrument(span)
+                    .await
+                    .map(|value| (asset, value))
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+
+        let mut dust: HashMap<String, f64> = HashMap::new();
+        for (asset, value) in dust_values.into_iter().flatten() {
+            dust.entry(asset).or_insert(value);
+        }
+        dust
+    }
+
+    /// One pass of the stop-loss manager: places missing stops and ratchets existing ones.
+    /// Returns false when balances could not be fetched.
+    pub async fn run_stop_loss_cycle(&self) -> bool {
+        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let trend = MARKET_TREND.read().await.clone();
+        status_println!("🔁 [{}] Starting stop-loss management loop", timestamp);
+        info!("🔁 [{}] Starting stop-loss management loop", timestamp);
+
+        let balances = match self.get_spot_balances().await {
+            Ok(b) => b,
+            Err(e) => {
+                error!("Failed to fetch balances: {}", e);
+                println!("❌ Failed to fetch balances: {}", e);
+                return false;
+            }
+        };
+
+        let quote_assets = get_quote_assets();
+
+        let open_orders = match self.get_open_orders().await {
+            Ok(orders) => orders,
+            Err(e) => {
+                error!("Failed to fetch full open orders: {}", e);
+                println!("❌ Failed to fetch full open orders: {}", e);
+                vec![]
+            }
+        };
+
+        let trailing_stop_symbols: HashSet<String> = open_orders
+            .iter()
+            .filter(|o| o.type_field == "TRAILING_STOP_MARKET")
+            .map(|o| o.symbol.clone())
+            .collect();
+
+        let stop_limit_symbols: HashSet<String> = open_orders
+            .iter()
+            .filter(|o| o.type_field == "STOP_LOSS_LIMIT")
+            .map(|o| o.symbol.clone())
+            .collect();
+
+        let active_symbols: HashSet<String> = stop_limit_symbols.union(&trailing_stop_symbols).cloned().collect();
+
+        // Clean up purchase prices for tokens no longer in open orders
+        {
+            let mut purchase_prices = PURCHASE_PRICES.lock().await;
+            let prev_symbols: HashSet<String> = purchase_prices.keys().cloned().collect();
+            for symbol in prev_symbols.difference(&active_symbols) {
+                let span = trade_span(symbol, &trade_id_for(symbol).await);
+                async {
+                    log_trade_event(symbol,"SELL",0.0,0.0,0.0,0.0,"stop_hit",&trend).await;
+                    println!("📉 Logged SELL for {} — stop order no longer active", symbol);
+                    info!("📉 Logged SELL for {} — stop order no longer active", symbol);
+                }
+                .instrument(span)
+                .await;
+                TRADE_IDS.lock().await.remove(symbol);
+                self.positions.lock().await.remove(symbol);
+            }
+            purchase_prices.retain(|symbol, _| active_symbols.contains(symbol));
+
+        }
+
+        // One ticker request for the whole cycle instead of one per symbol.
+        let prices = match self.get_all_prices().await {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Failed to fetch prices: {}", e);
+                println!("❌ Failed to fetch prices: {}", e);
+                return false;
+            }
+        };
+        let concurrency = get_stop_loss_concurrency();
+
+        // PLACE INITIAL STOP-LOSS IF NONE EXISTS
+        let mut unprotected: Vec<(String, String, f64, f64)> = Vec::new();
+        for (asset, balance) in balances {
+            if quote_assets.contains(&asset) {
+                continue;
+            }
+
+            for quote in &quote_assets {
+                let symbol = format!("{}{}", asset, quote);
+
+                if trailing_stop_symbols.contains(&symbol) || stop_limit_symbols.contains(&symbol) {
+                    continue;
+                }
+
+                if let Some(&price) = prices.get(&symbol) {
+                    unprotected.push((asset.clone(), symbol, balance, price));
+                }
+            }
+        }
+
+        let dust = self.place_missing_stops(unprotected, concurrency).await;
+        if !dust.is_empty() {
+            let total: f64 = dust.values().sum();
+            let mut assets: Vec<&String> = dust.keys().collect();
+            assets.sort();
+            status_println!("🧹 {} dust holdings below min notional, worth {:.4} quote in total: {:?}", dust.len(), total, assets);
+            info!("🧹 {} dust holdings below min notional, worth {:.4} quote in total: {:?}", dust.len(), total, assets);
+        }
+
+        // UPDATE STOP-LOSS IF ABOVE BREAK-EVEN
+        let stops: Vec<(&OpenOrder, f64)> = open_orders
+            .iter()
+            .filter(|o| o.type_field == "STOP_LOSS_LIMIT" && !trailing_stop_symbols.contains(&o.symbol))
+            .filter_map(|o| match prices.get(&o.symbol) {
+                Some(&price) => Some((o, price)),
+                None => {
+                    println!("❌ No price for {} in the ticker snapshot", o.symbol);
+                    None
+                }
+            })
+            .collect();
+
+        stream::iter(stops)
+            .for_each_concurrent(concurrency, |(order, current_price)| {
+                let (trend, timestamp) = (&trend, &timestamp);
+                async move {
+                    let span = trade_span(&order.symbol, &trade_id_for(&order.symbol).await);
+                    self.update_stop(order, current_price, trend, timestamp).instrument(span).await
+                }
+            })
+            .await;
+
+        true
+    }
+
+    /// Periodically check held spot tokens and ensure a stop-loss is in place or updated.
+    pub async fn manage_stop_loss_limit_loop(&self) {
+        loop {
+            if !self.run_stop_loss_cycle().await {
+                sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+
             let interval = config::get_stop_loss_loop_seconds();
-            println!("⏱ Sleeping {} seconds before next stop-loss check", interval);
-            println!("-------------------------------------------------------------------------");
+            status_println!("⏱ Sleeping {} seconds before next stop-loss check", interval);
+            status_println!("-------------------------------------------------------------------------");
             sleep(Duration::from_secs(interval)).await;
         }
     }
     
+    /// WebSocket-driven variant of `manage_stop_loss_limit_loop`. Each cycle runs one polling pass
+    /// (placing missing stops), then follows the trade stream of every STOP_LOSS_LIMIT symbol and
+    /// ratchets the stop on each new high instead of waiting for the next poll.
+    pub async fn manage_stop_loss_stream(&self) {
+        loop {
+            if !self.run_stop_loss_cycle().await {
+                sleep(Duration::from_secs(60)).await;
+                continue;
+            }
+
+            let interval = config::get_stop_loss_loop_seconds();
+            let deadline = Instant::now() + Duration::from_secs(interval);
+            let mut stops = self.load_streamed_stops().await;
+
+            if stops.is_empty() {
+                status_println!("⏱ No stop-loss orders to stream. Sleeping {} seconds", interval);
+                sleep(Duration::from_secs(interval)).await;
+                continue;
+            }
+
+            if let Err(e) = self.follow_stop_stream(&mut stops, deadline).await {
+                error!("Stop-loss stream connection failed: {}", e);
+                println!("❌ Stop-loss stream connection failed: {}", e);
+                sleep(Duration::from_secs(interval)).await;
+            }
+        }
+    }
+
+    /// Follows the trade stream of every symbol in `stops` until `deadline` or until the stream
+    /// ends, re-placing a stop as soon as a trade raises it. Fails only when it cannot connect.
+    async fn follow_stop_stream(&self, stops: &mut HashMap<String, StreamedStop>, deadline: Instant) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+        let streams = stops
+            .keys()
+            .map(|s| format!("{}@trade", s.to_lowercase()))
+            .collect::<Vec<_>>()
+            .join("/");
+        let url = format!("{}/stream?streams={}", self.stream_base_url, streams);
+        let (mut ws_stream, _) = connect_async(Url::parse(&url).unwrap()).await?;
+        let seconds = deadline.saturating_duration_since(Instant::now()).as_secs();
+        status_println!("📡 Streaming prices for {} stop-loss orders for {} seconds", stops.len(), seconds);
+        info!("📡 Streaming prices for {} stop-loss orders for {} seconds", stops.len(), seconds);
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let msg = match tokio::time::timeout(remaining, ws_stream.next()).await {
+                Ok(Some(Ok(msg))) => msg,
+                Ok(Some(Err(e))) => {
+                    error!("Stop-loss stream error: {}", e);
+                    break;
+                }
+                // Stream closed or refresh interval reached: re-run the polling pass.
+                Ok(None) | Err(_) => break,
+            };
+
+            let Some((symbol, price)) = msg.into_text().ok().and_then(|text| parse_trade_price(&text)) else {
+                continue;
+            };
+            let Some(stop) = stops.get_mut(&symbol) else {
+                continue;
+            };
+            let Some(new_stop) = stop.ratcheted_stop(&symbol, price) else {
+                continue;
+            };
+
+            if let Err(e) = self.cancel_order(&symbol, stop.order_id).await {
+                // Most likely the stop already filled; the next polling pass logs the SELL.
+                println!("❌ Failed to cancel old stop-loss for {}: {}", symbol, e);
+                stops.remove(&symbol);
+                continue;
+            }
+
+            match self.place_stop_loss_limit_order(&symbol, stop.quantity, new_stop, Binance::stop_limit_price(new_stop, get_stop_limit_buffer_pct(), stop.tick_size), None, None).await {
+                Ok(order_id) => {
+                    println!("⚡ Streamed stop-loss update for {}: {:.4} → {:.4} (price {:.4})", symbol, stop.stop_price, new_stop, price);
+                    info!("⚡ Streamed stop-loss update for {}: {:.4} → {:.4} (price {:.4})", symbol, stop.stop_price, new_stop, price);
+                    stop.order_id = order_id;
+                    stop.stop_price = new_stop;
+                    stop.last_update = Some(Instant::now());
+                }
+                Err(e) => {
+                    println!("❌ Failed to update stop-loss for {}: {}", symbol, e);
+                    stops.remove(&symbol);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the STOP_LOSS_LIMIT orders the stream variant should follow.
+    async fn load_streamed_stops(&self) -> HashMap<String, StreamedStop> {
+        let mut stops = HashMap::new();
+        let open_orders = match self.get_open_orders().await {
+            Ok(orders) => orders,
+            Err(e) => {
+                error!("Failed to fetch full open orders: {}", e);
+                return stops;
+            }
+        };
+
+        for order in open_orders.iter().filter(|o| o.type_field == "STOP_LOSS_LIMIT") {
+            let bought_here = self.positions.lock().await.entry_price(&order.symbol);
+            let purchase_price = match bought_here.or(PURCHASE_PRICES.lock().await.get(&order.symbol).copied()) {
+                Some(p) => p,
+                None => continue,
+            };
+            let filters = match self.cached_symbol_filters(&order.symbol).await {
+                Ok(f) => f,
+                Err(_) => continue,
+            };
+            stops.insert(order.symbol.clone(), StreamedStop {
+                order_id: order.order_id,
+                quantity: Binance::round_to_step(order.orig_qty.parse::<f64>().unwrap_or(0.0), filters.step_size),
+                purchase_price,
+                stop_price: order.stop_price.parse::<f64>().unwrap_or(0.0),
+                tick_size: filters.tick_size,
+                high_water: 0.0,
+                last_update: None,
+            });
+        }
+        stops
+    }
+
     pub async fn get_symbol_filters(binance: &Binance, symbol: &str) -> Result<SymbolFilters, Error> {
         let url = format!("{}/exchangeInfo?symbol={}", binance.base_url, symbol);
         let response = binance.client.get(&url).send().await?;
         let json: serde_json::Value = response.json().await?;
-    
-        let filters = &json["symbols"][0]["filters"];
-    
-        let mut tick_size = 0.0;
-        let mut step_size = 0.0;
-        let mut min_qty = 0.0;
-        let mut min_price = 0.0;
-        let mut min_notional = 0.0;
-    
-        for f in filters.as_array().unwrap_or(&vec![]) {
-            if let Some(filter_type) = f.get("filterType").and_then(|v| v.as_str()) {
-                match filter_type {
-                    "PRICE_FILTER" => {
-                        tick_size = f["tickSize"].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
-                        min_price = f["minPrice"].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
-                    },
-                    "LOT_SIZE" => {
-                        step_size = f["stepSize"].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
-                        min_qty = f["minQty"].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
-                    },
-                    "MIN_NOTIONAL" => {
-                        min_notional = f["minNotional"].as_str().unwrap_or("0.0").parse().unwrap_or(0.0);
-                    },
-                    _ => {}
-                }
+
+        Ok(SymbolFilters::from_symbol_info(&json["symbols"][0]))
+    }
+
+    /// `get_symbol_filters` behind an hour-long cache, so stop-loss cycles only hit
+    /// `/exchangeInfo` for symbols they have not seen recently.
+    pub async fn cached_symbol_filters(&self, symbol: &str) -> Result<SymbolFilters, Error> {
+        if let Some((fetched, filters)) = SYMBOL_FILTERS.read().await.get(symbol) {
+            if fetched.elapsed() <= SYMBOL_FILTERS_TTL {
+                return Ok(filters.clone());
             }
         }
-    
-        Ok(SymbolFilters {
-            tick_size,
-            step_size,
-            min_qty,
-            min_price,
-            min_notional,
-        })
+        let filters = Binance::get_symbol_filters(self, symbol).await?;
+        SYMBOL_FILTERS.write().await.insert(symbol.to_string(), (Instant::now(), filters.clone()));
+        Ok(filters)
     }
     
+    /// Wilder RSI of the latest 1h closes, fetched with `RSI_EXIT_PERIOD` warmup candles in
+    /// front of the window it reads; None when klines are unavailable.
+    async fn latest_rsi(&self, symbol: &str) -> Option<f64> {
+        let period = RSI_EXIT_PERIOD as u16;
+        let limit = kline_fetch_limit(period + 1, indicator_warmup(period), false);
+        let klines = self.get_klines_typed(symbol, "1h", limit).await.ok()?;
+        let closes: Vec<f64> = klines.iter().map(|k| k.close).collect();
+        compute_wilder_rsi(&closes, RSI_EXIT_PERIOD)
+    }
+
+    /// Units to sell on an overbought RSI: `fraction` of `quantity`, rounded down to `step_size`.
+    /// None when either the sold part or what stays under the stop would be below minQty/minNotional.
+    pub fn rsi_exit_quantity(quantity: f64, fraction: f64, price: f64, filters: &SymbolFilters) -> Option<f64> {
+        let sell = Binance::round_to_step(quantity * fraction, filters.step_size);
+        let remaining = Binance::round_to_step(quantity - sell, filters.step_size);
+        let tradable = |qty: f64| qty > 0.0 && qty >= filters.min_qty && qty * price >= filters.min_notional;
+        (tradable(sell) && tradable(remaining)).then_some(sell)
+    }
+
+    /// Caps `quantity` at LOT_SIZE maxQty (rounded down to `step_size`); unchanged when no max is known.
+    pub fn clamp_to_max_qty(quantity: f64, filters: &SymbolFilters) -> f64 {
+        if filters.max_qty > 0.0 && quantity > filters.max_qty {
+            Binance::round_to_step(filters.max_qty, filters.step_size)
+        } else {
+            quantity
+        }
+    }
+
+    /// Quantity to sell so the position's notional drops to `max_position_quote`, rounded down to
+    /// `step_size`. `None` when the cap is disabled (0) or the excess is below minQty/minNotional.
+    pub fn excess_position_quantity(quantity: f64, price: f64, max_position_quote: f64, filters: &SymbolFilters) -> Option<f64> {
+        if max_position_quote <= 0.0 || price <= 0.0 || quantity * price <= max_position_quote {
+            return None;
+        }
+        let excess = Binance::round_to_step(quantity - max_position_quote / price, filters.step_size);
+        if excess <= 0.0 || excess < filters.min_qty || excess * price < filters.min_notional {
+            return None;
+        }
+        Some(excess)
+    }
+
+    /// Records one initial stop attempt for `symbol`: success resets its failure streak, failure
+    /// extends it. True exactly once per streak, when it reaches `threshold` (0 = never).
+    pub fn record_stop_attempt(failures: &mut HashMap<String, u32>, symbol: &str, placed: bool, threshold: u32) -> bool {
+        if placed {
+            failures.remove(symbol);
+            return false;
+        }
+        let streak = failures.entry(symbol.to_string()).or_insert(0);
+        *streak += 1;
+        threshold > 0 && *streak == threshold
+    }
+
+    /// True when `quantity` (already rounded to `step_size`) is too small for a stop at `stop_price`:
+    /// below minQty, minPrice or minNotional, so any stop order would be rejected.
+    pub fn is_dust(quantity: f64, stop_price: f64, filters: &SymbolFilters) -> bool {
+        quantity <= 0.0
+            || quantity < filters.min_qty
+            || stop_price <= 0.0
+            || stop_price < filters.min_price
+            || quantity * stop_price < filters.min_notional
+    }
+
+    /// Quantity for a stop protecting `position`: floored to `step_size`. When that is too small
+    /// for the stop's minimums but the free `balance` (e.g. with fee residue of earlier trades)
+    /// floored to `step_size` clears them, the stop covers that balance instead, so the position
+    /// is not left unprotected. Never more than `balance`.
+    pub fn stop_quantity(position: f64, balance: f64, stop_price: f64, filters: &SymbolFilters) -> f64 {
+        let floored = Binance::round_to_step(position.min(balance), filters.step_size);
+        if !Binance::is_dust(floored, stop_price, filters) {
+            return floored;
+        }
+        let topped_up = Binance::round_to_step(balance, filters.step_size);
+        if Binance::is_dust(topped_up, stop_price, filters) {
+            floored
+        } else {
+            topped_up
+        }
+    }
+
+    /// Builds the `timeInForce` query fragment (plus `goodTillDate` for GTD).
+    /// Defaults to GTC and rejects unknown values before any request is sent.
+    pub fn time_in_force_params(time_in_force: Option<&str>, good_till_date: Option<u64>) -> Result<String, Box<dyn StdError>> {
+        let tif = time_in_force.unwrap_or("GTC").trim().to_uppercase();
+        match (tif.as_str(), good_till_date) {
+            ("GTC" | "IOC" | "FOK", None) => Ok(format!("timeInForce={}", tif)),
+            ("GTD", Some(expiry)) => Ok(format!("timeInForce=GTD&goodTillDate={}", expiry)),
+            ("GTD", None) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "timeInForce GTD requires a goodTillDate",
+            ))),
+            ("GTC" | "IOC" | "FOK", Some(_)) => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("goodTillDate is only valid with GTD, not {}", tif),
+            ))),
+            _ => Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid timeInForce: {}", tif),
+            ))),
+        }
+    }
+
+    /// Rounds to `decimals` places, for order fields that used fixed `{:.N}` formatting.
+    pub fn round_decimals(value: f64, decimals: i32) -> f64 {
+        (value * 10_f64.powi(decimals)).round() / 10_f64.powi(decimals)
+    }
+
+    /// Rounds a price to as many decimals as `tick_size` has, so 0.00000001 ticks keep 8 places
+    /// and 0.01 ticks 2. An unknown (non-positive) tick size leaves the price as is.
+    pub fn round_to_tick_precision(price: f64, tick_size: f64) -> f64 {
+        if tick_size <= 0.0 {
+            return price;
+        }
+        Binance::round_decimals(price, Self::num_decimal_places(tick_size) as i32)
+    }
+
     pub fn round_to_step_old(value: f64, step: f64) -> f64 {
         (value / step).floor() * step
     }
 
     pub fn round_to_step(value: f64, step: f64) -> f64 {
         let precision = Self::num_decimal_places(step);
-        let scaled = (value / step).floor() * step;
+        // The epsilon keeps exact multiples such as 0.3 / 0.1 = 2.9999999999999996 from losing a step.
+        let scaled = (value / step + STEP_EPSILON).floor() * step;
         (scaled * 10_f64.powi(precision as i32)).round() / 10_f64.powi(precision as i32)
+    }
+
+    /// Units `quote_amount` buys at `price`, floored to the symbol's `step_size` rather than a
+    /// fixed number of decimals, so BTC keeps its 8 decimals and cheap tokens buy whole units.
+    /// A non-positive `step_size` (filters unavailable) returns the raw quantity.
+    pub fn quantity_for_quote(quote_amount: f64, price: f64, step_size: f64) -> f64 {
+        if price <= 0.0 {
+            return 0.0;
+        }
+        let quantity = quote_amount / price;
+        if step_size <= 0.0 {
+            return quantity;
+        }
+        Binance::round_to_step(quantity, step_size)
     }   
 
+    /// Sell quantity for a `balance` and the remainder it leaves. A normal sell floors to `step`
+    /// and leaves the sub-step remainder as dust (see `is_dust`). On a `full_exit`, when what
+    /// flooring would leave is within one step, the whole balance is sold instead so the exit
+    /// leaves nothing behind for the stop loop.
+    pub fn round_to_step_for_sell(balance: f64, step: f64, full_exit: bool) -> (f64, f64) {
+        let floored = Self::round_to_step(balance, step);
+        let quantity = if full_exit && balance - floored < step { balance } else { floored };
+        (quantity, (balance - quantity).max(0.0))
+    }
+
     fn num_decimal_places(step: f64) -> u32 {
-        let step_str = format!("{:.20}", step); // avoid scientific notation
+        // Display prints the shortest exact form without an exponent (0.1 → "0.1", 1e-8 → "0.00000001").
+        let step_str = format!("{}", step);
         step_str.trim_end_matches('0')
             .split('.')
             .nth(1)
@@ -1056,98 +2380,74 @@ impl Binance {
             .unwrap_or(0) as u32
     }
 
-    pub async fn get_open_orders(&self) -> Result<Vec<OpenOrder>, Error> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY").expect("Missing BINANCE_API_KEY");
-        let secret_key = env::var("BINANCE_SECRET_KEY").expect("Missing BINANCE_SECRET_KEY");
-    
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-    
-        let query = format!("timestamp={}&recvWindow=5000", timestamp);
-    
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).unwrap();
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
+    pub async fn get_open_orders(&self) -> Result<Vec<OpenOrder>, Box<dyn StdError>> {
     
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/openOrders",
-            query,
-            signature
-        );
-    
-        let response = self.client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
+        let query = "recvWindow=5000".to_string();
     
-        let orders: Vec<OpenOrder> = response.json().await?;
+        let url = format!("{}{}", self.base_url, "/openOrders");
+        let orders: Vec<OpenOrder> = self.signed_json(Method::GET, &url, &query).await?;
         Ok(orders)
     }
     
     pub async fn cancel_order(&self, symbol: &str, order_id: u64) -> Result<(), Box<dyn StdError>> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY")?;
-        let secret_key = env::var("BINANCE_SECRET_KEY")?;
-    
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_millis();
     
         let query = format!(
-            "symbol={}&orderId={}&recvWindow=5000&timestamp={}",
-            symbol, order_id, timestamp
-        );
-    
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())?;
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-    
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/order",
-            query,
-            signature
+            "symbol={}&orderId={}&recvWindow=5000",
+            symbol, order_id
         );
     
-        let response = self
-            .client
-            .delete(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
-    
-        let status = response.status();
-        let body = response.text().await?;
+        let url = format!("{}{}", self.base_url, "/order");
+        let (status, body) = self.send_signed(Method::DELETE, &url, &query).await?;
     
         if status.is_success() {
             println!("🗑️ Cancelled order {} on {}", order_id, symbol);
             Ok(())
         } else {
             eprintln!("❌ Failed to cancel order {} on {}: {}", order_id, symbol, body);
-            Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("Failed to cancel order: {}", body),
-            )))
+            Err(Box::new(std::io::Error::other(format!("Failed to cancel order: {}", body))))
+        }
+    }
+
+    /// Cancels every open order on `symbol` and market-sells its whole free base balance,
+    /// forgetting the position so the stop loop does not log it as a stop hit. Returns the
+    /// quantity sold.
+    pub async fn liquidate(&self, symbol: &str) -> Result<f64, Box<dyn StdError>> {
+        let symbol = self.validate_symbol(symbol).await?;
+        let quote = get_quote_assets()
+            .into_iter()
+            .find(|q| symbol.ends_with(q.as_str()))
+            .ok_or_else(|| format!("{} is not quoted in any of QUOTE_ASSETS", symbol))?;
+        let base_asset = &symbol[..symbol.len() - quote.len()];
+
+        let open_orders = self.get_open_orders().await?;
+        for order in open_orders.iter().filter(|o| o.symbol == symbol) {
+            self.cancel_order(&symbol, order.order_id).await?;
+        }
+
+        let filters = self.cached_symbol_filters(&symbol).await?;
+        let balance = self.get_account_balance(base_asset).await?;
+        let (quantity, _) = Binance::round_to_step_for_sell(balance, filters.step_size, true);
+        let quantity = Binance::clamp_to_max_qty(quantity, &filters);
+        let dust = balance - quantity;
+        let price = self.get_price(&symbol).await?;
+        if Binance::is_dust(quantity, price, &filters) {
+            return Err(format!("{} balance {} is below the minimum order size", symbol, balance).into());
         }
+        self.place_market_sell_order(&symbol, quantity).await?;
+
+        let trend = MARKET_TREND.read().await.clone();
+        log_trade_event(&symbol, "SELL", price, quantity, price * quantity, 0.0, "liquidated", &trend).await;
+        PURCHASE_PRICES.lock().await.remove(&symbol);
+        TRADE_IDS.lock().await.remove(&symbol);
+        self.positions.lock().await.remove(&symbol);
+        println!("💸 Liquidated {} {} at ~{}", quantity, symbol, price);
+        info!("💸 Liquidated {} {} at ~{} ({} {} left as dust)", quantity, symbol, price, dust, base_asset);
+        Ok(quantity)
     }
 
     pub async fn get_spot_trade_history(&self, symbol: &str, start_time: Option<u64>, end_time: Option<u64>) -> Result<Vec<serde_json::Value>, Box<dyn StdError>> {
-        let _ = from_filename("vars.env");
-        let api_key = env::var("BINANCE_API_KEY")?;
-        let secret_key = env::var("BINANCE_SECRET_KEY")?;
     
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)?
-            .as_millis();
-    
-        let mut query = format!("symbol={}&timestamp={}", symbol, timestamp);
+        let mut query = format!("symbol={}", symbol);
         if let Some(start) = start_time {
             query.push_str(&format!("&startTime={}", start));
         }
@@ -1155,51 +2455,931 @@ impl Binance {
             query.push_str(&format!("&endTime={}", end));
         }
     
-        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())?;
-        mac.update(query.as_bytes());
-        let signature = hex_encode(mac.finalize().into_bytes());
-    
-        let url = format!(
-            "{}{}?{}&signature={}",
-            self.base_url,
-            "/myTrades",
-            query,
-            signature
-        );
-    
-        let response = self.client
-            .get(&url)
-            .header("X-MBX-APIKEY", api_key)
-            .send()
-            .await?;
-    
-        let trades: Vec<serde_json::Value> = response.json().await?;
+        let url = format!("{}{}", self.base_url, "/myTrades");
+        let trades: Vec<serde_json::Value> = self.signed_json(Method::GET, &url, &query).await?;
         Ok(trades)
     }
 
     pub async fn get_last_buy_price(&self, symbol: &str) -> Result<Option<f64>, Box<dyn StdError>> {
-        let trades = self.get_spot_trade_history(symbol, None, None).await?;
-    
-        let last_buy = trades
-            .iter()
-            .rev()
-            .find(|t| t["isBuyer"].as_bool() == Some(true));
-    
-        if let Some(trade) = last_buy {
-            if let Some(price_str) = trade["price"].as_str() {
-                if let Ok(price) = price_str.parse::<f64>() {
-                    return Ok(Some(price));
-                }
+        let trades = self.get_my_trades_typed(symbol, None, None).await?;
+        Ok(trades.iter().rev().find(|t| t.is_buyer).map(|t| t.price))
+    }
+}
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::mock::{local_binance, local_binance_recording, mark_tradable};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn max_account_value_blocks_only_above_the_limit() {
+        assert!(Binance::above_max_account_value(1500.0, 1000.0));
+        assert!(!Binance::above_max_account_value(1000.0, 1000.0));
+        assert!(!Binance::above_max_account_value(1e9, 0.0));
+    }
+
+    #[test]
+    fn quote_buy_sends_quote_order_qty_at_quote_precision() {
+        let query = Binance::market_buy_quote_request("ETHBTC", 0.00123456789, 8).to_query().unwrap();
+        assert!(query.contains("quoteOrderQty=0.00123456&"), "{}", query);
+        assert!(!query.contains("quantity="), "{}", query);
+
+        let query = Binance::market_buy_quote_request("BTCUSDT", 20.129, 2).to_query().unwrap();
+        assert!(query.contains("quoteOrderQty=20.12&"), "{}", query);
+    }
+
+    #[test]
+    fn order_result_reports_units_bought() {
+        let response = json!({
+            "orderId": 42,
+            "executedQty": "0.01230000",
+            "cummulativeQuoteQty": "19.99",
+            "fills": [{"price": "1625.2", "qty": "0.0123", "commission": "0.0000123", "commissionAsset": "ETH"}]
+        });
+        let result = OrderResult::from_response(&response);
+        assert_eq!(result.order_id, 42);
+        assert_eq!(result.executed_qty, 0.0123);
+        assert_eq!(result.fills.len(), 1);
+        assert!((result.avg_price().unwrap() - 19.99 / 0.0123).abs() < 1e-9);
+
+        let futures = OrderResult::from_response(&json!({"orderId": 7, "executedQty": "2", "cumQuote": "10"}));
+        assert_eq!(futures.avg_price(), Some(5.0));
+    }
+
+    fn streamed_stop(last_update: Option<Instant>) -> StreamedStop {
+        StreamedStop {
+            order_id: 1,
+            quantity: 1.0,
+            purchase_price: 100.0,
+            stop_price: 95.0,
+            tick_size: 0.01,
+            high_water: 100.0,
+            last_update,
+        }
+    }
+
+    #[test]
+    fn ratcheted_stop_keeps_the_high_water_mark_during_the_cooldown() {
+        let mut stop = streamed_stop(Some(Instant::now()));
+        assert_eq!(stop.ratcheted_stop("ABCUSDT", 120.0), None);
+        assert_eq!(stop.high_water, 100.0);
+
+        stop.last_update = None;
+        let raised = stop.ratcheted_stop("ABCUSDT", 120.0).expect("a 20% gain raises the stop");
+        assert!(raised > 95.0 && raised < 120.0);
+        assert_eq!(stop.high_water, 120.0);
+        assert_eq!(stop.ratcheted_stop("ABCUSDT", 119.0), None);
+    }
+
+    #[tokio::test]
+    async fn validate_symbol_normalizes_and_rejects_from_the_cache() {
+        mark_tradable(&["BTCUSDT", "ETHBTC"]).await;
+
+        let binance = Binance::with_credentials("", "");
+        assert_eq!(binance.validate_symbol(" btcusdt ").await.unwrap(), "BTCUSDT");
+        assert!(binance.validate_symbol("NOPEUSDT").await.is_err());
+    }
+
+    #[test]
+    fn only_timestamp_rejections_are_retried() {
+        assert!(Binance::is_timestamp_error(r#"{"code":-1021,"msg":"Timestamp for this request is outside of the recvWindow."}"#));
+        assert!(Binance::is_timestamp_error(r#"{"code":-1022,"msg":"Signature for this request is not valid."}"#));
+        assert!(!Binance::is_timestamp_error(r#"{"code":-2010,"msg":"Account has insufficient balance for requested action."}"#));
+        assert!(!Binance::is_timestamp_error("not json"));
+    }
+
+    fn btc_filters() -> SymbolFilters {
+        SymbolFilters {
+            tick_size: 0.01,
+            step_size: 0.00001,
+            min_qty: 0.00001,
+            max_qty: 9000.0,
+            min_price: 0.01,
+            min_notional: 5.0,
+            quote_precision: 8,
+        }
+    }
+
+    #[test]
+    fn fractional_holdings_above_the_minimums_are_not_dust() {
+        let filters = btc_filters();
+        assert!(!Binance::is_dust(0.5, 60000.0, &filters));
+        assert!(Binance::is_dust(0.00005, 60000.0, &filters));
+        assert!(Binance::is_dust(0.0, 60000.0, &filters));
+    }
+
+    #[test]
+    fn normal_sells_floor_to_the_step_and_full_exits_take_the_whole_balance() {
+        let (quantity, dust) = Binance::round_to_step_for_sell(1.0000007, 0.001, false);
+        assert_eq!(quantity, 1.0);
+        assert!((dust - 0.0000007).abs() < 1e-12);
+
+        let (quantity, dust) = Binance::round_to_step_for_sell(1.0000007, 0.001, true);
+        assert_eq!(quantity, 1.0000007);
+        assert_eq!(dust, 0.0);
+
+        for full_exit in [false, true] {
+            assert_eq!(Binance::round_to_step_for_sell(2.5, 0.1, full_exit), (2.5, 0.0));
+        }
+    }
+
+    #[test]
+    fn borderline_stop_is_topped_up_from_the_free_balance_within_it() {
+        let filters = btc_filters();
+        // 0.000109 floors to 0.0001, worth 4.5 at the stop: under the 5.0 minimum notional.
+        assert_eq!(Binance::stop_quantity(0.000109, 0.000109, 45000.0, &filters), 0.0001);
+
+        let quantity = Binance::stop_quantity(0.000109, 0.000125, 45000.0, &filters);
+        assert_eq!(quantity, 0.00012);
+        assert!(quantity <= 0.000125);
+        assert!(!Binance::is_dust(quantity, 45000.0, &filters));
+
+        // Never the raw, unfloored balance.
+        assert_eq!(Binance::stop_quantity(0.5000037, 0.5000037, 45000.0, &filters), 0.5);
+    }
+
+    #[test]
+    fn symbol_filters_parse_from_an_exchange_info_entry() {
+        let info = json!({
+            "symbol": "ETHBTC",
+            "quoteAssetPrecision": 8,
+            "filters": [
+                {"filterType": "PRICE_FILTER", "minPrice": "0.00001000", "maxPrice": "922327.00000000", "tickSize": "0.00001000"},
+                {"filterType": "LOT_SIZE", "minQty": "0.00010000", "maxQty": "100000.00000000", "stepSize": "0.00010000"},
+                {"filterType": "NOTIONAL", "minNotional": "0.00010000", "applyMinToMarket": true}
+            ]
+        });
+        let filters = SymbolFilters::from_symbol_info(&info);
+        assert_eq!(filters.tick_size, 0.00001);
+        assert_eq!(filters.min_price, 0.00001);
+        assert_eq!(filters.step_size, 0.0001);
+        assert_eq!(filters.min_qty, 0.0001);
+        assert_eq!(filters.max_qty, 100000.0);
+        assert_eq!(filters.min_notional, 0.0001);
+        assert_eq!(filters.quote_precision, 8);
+
+        let bare = SymbolFilters::from_symbol_info(&json!({"symbol": "ABCUSDT", "quotePrecision": 2}));
+        assert_eq!((bare.tick_size, bare.step_size, bare.min_notional), (0.0, 0.0, 0.0));
+        assert_eq!(bare.quote_precision, 2);
+    }
+
+    #[test]
+    fn open_shorts_are_the_negative_positions() {
+        let positions = vec![
+            json!({"symbol": "ABCUSDT", "positionAmt": "-12.5"}),
+            json!({"symbol": "XYZUSDT", "positionAmt": "0.000"}),
+            json!({"symbol": "LNGUSDT", "positionAmt": "3"}),
+        ];
+        let shorts = Binance::short_symbols(&positions);
+        assert_eq!(shorts.len(), 1);
+        assert!(shorts.contains("ABCUSDT"));
+    }
+
+    #[test]
+    fn futures_filters_read_the_notional_field() {
+        let info = json!({
+            "symbol": "ABCUSDT",
+            "filters": [
+                {"filterType": "PRICE_FILTER", "minPrice": "0.0010", "tickSize": "0.0010"},
+                {"filterType": "LOT_SIZE", "minQty": "1", "maxQty": "1000000", "stepSize": "1"},
+                {"filterType": "MIN_NOTIONAL", "notional": "5"}
+            ]
+        });
+        let filters = SymbolFilters::from_symbol_info(&info);
+        assert_eq!(filters.min_notional, 5.0);
+        assert_eq!(filters.step_size, 1.0);
+        assert_eq!(Binance::short_stop_price(2.0, 5.0, filters.tick_size), 2.1);
+    }
+
+    /// Collects formatted tracing output for assertions.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Serializes the tests that flip `enable_shorts`.
+    static SHORTS_FLAG_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    #[tokio::test]
+    async fn spot_mode_logs_that_shorting_is_unsupported_and_places_nothing() {
+        let _guard = SHORTS_FLAG_LOCK.lock().await;
+        let previous = std::mem::replace(&mut SHARED_CONFIG.write().unwrap().enable_shorts, false);
+        let (binance, requests) = local_binance_recording(vec![("POST /fapi/v1/order", r#"{"orderId":1}"#.to_string())]).await;
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_ansi(false).with_writer(move || writer.clone()).finish();
+
+        let result = {
+            let _default = tracing::subscriber::set_default(subscriber);
+            binance.execute_short_with_stop("ABCUSDT").await
+        };
+        SHARED_CONFIG.write().unwrap().enable_shorts = previous;
+
+        assert!(result.unwrap_err().to_string().contains("unsupported on spot"));
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Not shorting ABCUSDT: shorting is unsupported on spot"), "{}", output);
+        assert!(requests.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn an_enabled_short_places_the_entry_and_an_upside_stop() {
+        let _guard = SHORTS_FLAG_LOCK.lock().await;
+        let previous = std::mem::replace(&mut SHARED_CONFIG.write().unwrap().enable_shorts, true);
+        mark_tradable(&["ABCUSDT"]).await;
+        let (binance, requests) = local_binance_recording(vec![
+            (
+                "/fapi/v1/exchangeInfo",
+                r#"{"symbols":[{"symbol":"ABCUSDT","filters":[
+                    {"filterType":"PRICE_FILTER","minPrice":"0.0010","tickSize":"0.0010"},
+                    {"filterType":"LOT_SIZE","minQty":"1","maxQty":"1000000","stepSize":"1"},
+                    {"filterType":"MIN_NOTIONAL","notional":"5"}]}]}"#
+                    .to_string(),
+            ),
+            (
+                "/premiumIndex?symbol=ABCUSDT",
+                r#"{"symbol":"ABCUSDT","markPrice":"2.0","lastFundingRate":"0.0001","nextFundingTime":1760000000000}"#.to_string(),
+            ),
+            ("POST /fapi/v1/order", r#"{"orderId":1,"executedQty":"0"}"#.to_string()),
+        ])
+        .await;
+
+        let result = binance.execute_short_with_stop("ABCUSDT").await;
+        SHARED_CONFIG.write().unwrap().enable_shorts = previous;
+        result.unwrap();
+
+        let orders: Vec<String> = requests.lock().unwrap().iter().filter(|r| r.starts_with("POST /fapi/v1/order")).cloned().collect();
+        assert_eq!(orders.len(), 2, "{:?}", orders);
+        assert!(orders[0].contains("side=SELL") && orders[0].contains("type=MARKET"), "{}", orders[0]);
+        assert!(orders[1].contains("side=BUY") && orders[1].contains("type=STOP_MARKET"), "{}", orders[1]);
+        assert!(orders[1].contains("reduceOnly=true"), "{}", orders[1]);
+        let stop_price: f64 = orders[1]
+            .split(['?', '&', ' '])
+            .find_map(|field| field.strip_prefix("stopPrice="))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(stop_price > 2.0, "stop {} is not above the 2.0 entry", stop_price);
+    }
+
+    #[test]
+    fn stop_prices_keep_the_tick_precision_and_overrides_apply_on_top() {
+        let stop = Binance::round_to_tick_precision(0.000012345678, 0.00000001);
+        let limit = Binance::round_to_tick_precision(0.000012, 0.00000001);
+        assert_eq!(stop, 0.00001235);
+
+        let req = OrderRequest::new("ABCBTC", "SELL", "STOP_LOSS_LIMIT").stop_price(stop).price(limit);
+        let query = req.clone().price_decimals(8).to_query().unwrap();
+        assert!(query.contains("stopPrice=0.00001235"), "{}", query);
+        assert!(query.contains("price=0.000012&"), "{}", query);
+
+        let req = OrderRequest::new("ABCUSDT", "SELL", "STOP_LOSS_LIMIT")
+            .stop_price(Binance::round_to_tick_precision(123.4567, 0.0001))
+            .price(123.4);
+        let query = req.price_decimals(2).to_query().unwrap();
+        assert!(query.contains("stopPrice=123.46&"), "{}", query);
+        assert_eq!(Binance::round_to_tick_precision(1.23456, 0.0), 1.23456);
+    }
+
+    #[test]
+    fn stop_limit_query_carries_the_requested_time_in_force() {
+        let order = |tif: Option<&str>, gtd: Option<u64>| {
+            OrderRequest::new("BTCUSDT", "SELL", "STOP_LOSS_LIMIT").quantity(0.5).tif(tif, gtd).to_query()
+        };
+        assert!(order(None, None).unwrap().contains("timeInForce=GTC"));
+        assert!(order(Some("ioc"), None).unwrap().contains("timeInForce=IOC"));
+        assert!(order(Some("FOK"), None).unwrap().contains("timeInForce=FOK"));
+        assert!(order(Some("GTD"), Some(1_900_000_000_000)).unwrap().contains("timeInForce=GTD&goodTillDate=1900000000000"));
+
+        assert!(order(Some("XYZ"), None).is_err());
+        assert!(order(Some("GTD"), None).is_err());
+        assert!(order(Some("GTC"), Some(1_900_000_000_000)).is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_time_in_force_fails_before_any_request() {
+        // Nothing listens here, so any request would fail with a connection error instead.
+        let mut binance = Binance::with_credentials("key", "secret");
+        binance.base_url = "http://127.0.0.1:9/api/v3".to_string();
+        let err = binance
+            .place_stop_loss_limit_order("BTCUSDT", 0.5, 100.0, 99.5, Some("XYZ"), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid timeInForce"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn wide_spreads_fail_the_gate_and_tight_ones_pass() {
+        let binance = local_binance(vec![
+            ("symbol=THINUSDT", r#"{"bids":[["0.0950","100"]],"asks":[["0.1050","80"]]}"#.to_string()),
+            ("symbol=DEEPUSDT", r#"{"bids":[["99.95","3"],["99.90","5"]],"asks":[["100.05","2"]]}"#.to_string()),
+        ])
+        .await;
+
+        let thin = binance.get_order_book("THINUSDT", 5).await.unwrap();
+        assert!((thin.spread_pct().unwrap() - 10.0).abs() < 1e-9);
+        assert!(!thin.within_spread(0.5));
+
+        let deep = binance.get_order_book("DEEPUSDT", 5).await.unwrap();
+        assert_eq!(deep.bids, vec![(99.95, 3.0), (99.90, 5.0)]);
+        assert!(deep.within_spread(0.5));
+
+        assert!(!OrderBook::default().within_spread(0.5));
+    }
+
+    #[test]
+    fn initial_stop_is_anchored_on_the_entry_price_when_known() {
+        // Bought at 100, now 120: a 5% stop belongs at 95, not 114.
+        let anchor = Binance::initial_stop_anchor(Some(100.0), 120.0, 5.0);
+        assert_eq!(anchor, 100.0);
+        assert!((anchor * 0.95 - 95.0).abs() < 1e-9);
+
+        assert_eq!(Binance::initial_stop_anchor(None, 120.0, 5.0), 120.0);
+        // Already below the entry stop: anchoring on entry would place a stop above the market.
+        assert_eq!(Binance::initial_stop_anchor(Some(100.0), 90.0, 5.0), 90.0);
+    }
+
+    #[test]
+    fn two_take_profit_levels_leave_the_rest_on_the_stop() {
+        let (orders, remainder) = Binance::plan_take_profits(0.01, 50_000.0, &[(5.0, 0.5), (10.0, 0.25)], &btc_filters());
+        assert_eq!(orders, vec![(52_500.0, 0.005), (55_000.0, 0.0025)]);
+        assert_eq!(remainder, 0.0025);
+
+        // A level below minNotional is folded back into the stopped remainder.
+        let (orders, remainder) = Binance::plan_take_profits(0.0002, 50_000.0, &[(5.0, 0.1)], &btc_filters());
+        assert!(orders.is_empty());
+        assert_eq!(remainder, 0.0002);
+    }
+
+    #[tokio::test]
+    async fn trailing_stops_go_to_futures_only_in_futures_mode() {
+        mark_tradable(&["TRAILUSDT"]).await;
+        let filters = SymbolFilters { step_size: 0.001, ..btc_filters() };
+        SYMBOL_FILTERS.write().await.insert("TRAILUSDT".to_string(), (Instant::now(), filters));
+        let (binance, requests) = local_binance_recording(vec![("/fapi/v1/order", r#"{"orderId":77,"executedQty":"0"}"#.to_string())]).await;
+        assert_eq!(binance.trailing_stop_base_url(false), None);
+        assert!(binance.trailing_stop_base_url(true).unwrap().ends_with("/fapi/v1"));
+
+        crate::config::SHARED_CONFIG.write().unwrap().futures_trailing_stops = false;
+        let spot = binance.place_trailing_stop_sell_order("TRAILUSDT", 2.0, 1.5, None, None, None).await;
+        assert!(spot.unwrap_err().to_string().contains("STOP_LOSS_LIMIT"));
+        assert!(requests.lock().unwrap().is_empty());
+
+        crate::config::SHARED_CONFIG.write().unwrap().futures_trailing_stops = true;
+        let order_id = binance.place_trailing_stop_sell_order("TRAILUSDT", 2.1234567, 1.5, None, None, None).await.unwrap();
+        crate::config::SHARED_CONFIG.write().unwrap().futures_trailing_stops = false;
+        assert_eq!(order_id, 77);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("POST /fapi/v1/order"), "{}", requests[0]);
+        // Floored to the symbol's 0.001 step, not to a fixed five decimals.
+        assert!(requests[0].contains("quantity=2.123&"), "{}", requests[0]);
+    }
+
+    #[tokio::test]
+    async fn a_losing_round_trip_today_counts_as_one_loss() {
+        let quote = crate::config::get_quote_assets()[0].clone();
+        let trade = |id: u64, price: &str, quote_qty: &str, is_buyer: bool| {
+            json!({
+                "symbol": format!("LOSS{}", quote), "id": id, "orderId": id, "price": price, "qty": "10",
+                "quoteQty": quote_qty, "commission": "0.01", "commissionAsset": quote.as_str(),
+                "time": 1_700_000_000_000u64 + id, "isBuyer": is_buyer, "isMaker": false
+            })
+        };
+        let trades = json!([trade(1, "1.0", "10.0", true), trade(2, "0.9", "9.0", false)]).to_string();
+        let binance = local_binance(vec![
+            ("/account", r#"{"balances":[{"asset":"LOSS","free":"3","locked":"0"}]}"#.to_string()),
+            ("symbol=LOSS", trades),
+        ])
+        .await;
+        assert_eq!(binance.count_today_losses().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn maintenance_is_read_from_the_system_status_endpoint() {
+        let binance = local_binance(vec![("/sapi/v1/system/status", r#"{"status":1,"msg":"system maintenance"}"#.to_string())]).await;
+        assert_eq!(binance.system_status().await.unwrap(), SystemStatus::Maintenance);
+
+        let binance = local_binance(vec![("/sapi/v1/system/status", r#"{"status":0,"msg":"normal"}"#.to_string())]).await;
+        assert_eq!(binance.system_status().await.unwrap(), SystemStatus::Normal);
+
+        assert_eq!(SystemStatus::from_response(503, &Value::Null), SystemStatus::Maintenance);
+    }
+
+    #[test]
+    fn the_builder_produces_each_order_type() {
+        let market = OrderRequest::new("ABCUSDT", "SELL", "MARKET").quantity(12.5).to_query().unwrap();
+        assert_eq!(market, "symbol=ABCUSDT&side=SELL&type=MARKET&quantity=12.5&recvWindow=5000");
+
+        let limit = OrderRequest::new("ABCUSDT", "SELL", "LIMIT").tif(None, None).quantity(3.0).price(1.25).to_query().unwrap();
+        assert_eq!(limit, "symbol=ABCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=3&price=1.25&recvWindow=5000");
+
+        let trailing = OrderRequest::new("ABCUSDT", "SELL", "TRAILING_STOP_MARKET")
+            .futures()
+            .reduce_only()
+            .quantity(3.0)
+            .callback_rate(1.5)
+            .activation_price(Some(1.3));
+        assert!(trailing.futures);
+        assert_eq!(
+            trailing.to_query().unwrap(),
+            "symbol=ABCUSDT&side=SELL&type=TRAILING_STOP_MARKET&quantity=3&callbackRate=1.5&activationPrice=1.3&reduceOnly=true&recvWindow=5000"
+        );
+
+        let stop_market = OrderRequest::new("ABCUSDT", "BUY", "STOP_MARKET").futures().reduce_only().quantity(3.0).stop_price(1.1);
+        assert!(stop_market.to_query().unwrap().contains("stopPrice=1.1&reduceOnly=true"));
+    }
+
+    #[test]
+    fn a_two_percent_activation_threshold_sets_the_activation_price() {
+        let activation = Binance::trailing_activation_price(50.0, 2.0, 0.01);
+        assert_eq!(activation, Some(51.0));
+        let query = OrderRequest::new("ABCUSDT", "SELL", "TRAILING_STOP_MARKET")
+            .futures()
+            .reduce_only()
+            .quantity(3.0)
+            .callback_rate(1.0)
+            .activation_price(activation)
+            .to_query()
+            .unwrap();
+        assert!(query.contains("activationPrice=51&"), "{}", query);
+        assert_eq!(Binance::trailing_activation_price(50.0, 0.0, 0.01), None);
+    }
+
+    #[test]
+    fn a_tiny_max_qty_caps_the_order_quantity() {
+        let filters = SymbolFilters { max_qty: 0.0123456, ..btc_filters() };
+        assert_eq!(Binance::clamp_to_max_qty(5.0, &filters), 0.01234);
+        assert_eq!(Binance::clamp_to_max_qty(0.01, &filters), 0.01);
+        // An unknown maxQty leaves the quantity alone.
+        assert_eq!(Binance::clamp_to_max_qty(5.0, &SymbolFilters { max_qty: 0.0, ..btc_filters() }), 5.0);
+    }
+
+    #[tokio::test]
+    async fn a_hung_endpoint_times_out_within_the_configured_bound() {
+        // Accepts connections but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/v3/ping", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
             }
+        });
+
+        let client = Binance::http_client(1_000, 300);
+        let started = Instant::now();
+        let err = client.get(&url).send().await.unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(2), "{:?}", started.elapsed());
+    }
+
+    #[test]
+    fn an_overbought_rsi_sells_the_configured_fraction() {
+        let threshold = 70.0;
+        let rising: Vec<f64> = (0..=RSI_EXIT_PERIOD).map(|i| 100.0 + i as f64 * (1.0 + (i % 3) as f64 * 0.1)).collect();
+        let neutral: Vec<f64> = (0..=RSI_EXIT_PERIOD).map(|i| if i % 2 == 0 { 100.0 } else { 101.0 }).collect();
+        assert!(compute_wilder_rsi(&rising, RSI_EXIT_PERIOD).unwrap() > threshold);
+        assert!(compute_wilder_rsi(&neutral, RSI_EXIT_PERIOD).unwrap() <= threshold);
+
+        assert_eq!(Binance::rsi_exit_quantity(0.01, 0.5, 50_000.0, &btc_filters()), Some(0.005));
+        assert_eq!(Binance::rsi_exit_quantity(0.01, 0.25, 50_000.0, &btc_filters()), Some(0.0025));
+        // Trimming a position this small would leave unsellable dust on one side.
+        assert_eq!(Binance::rsi_exit_quantity(0.00015, 0.5, 50_000.0, &btc_filters()), None);
+    }
+
+    #[tokio::test]
+    async fn a_cycle_over_twenty_holdings_fetches_prices_once_and_bounds_order_concurrency() {
+        let quote = crate::config::get_quote_assets()[0].clone();
+        let assets: Vec<String> = (0..20).map(|i| format!("CONC{}", i)).collect();
+        let symbols: Vec<String> = assets.iter().map(|a| format!("{}{}", a, quote)).collect();
+        mark_tradable(&symbols.iter().map(String::as_str).collect::<Vec<_>>()).await;
+        for symbol in &symbols {
+            SYMBOL_FILTERS.write().await.insert(symbol.clone(), (Instant::now(), btc_filters()));
         }
-    
-        Ok(None)
+        let balances: Vec<Value> = assets.iter().map(|a| json!({"asset": a, "free": "2", "locked": "0"})).collect();
+        let prices: Vec<Value> = symbols.iter().map(|s| json!({"symbol": s, "price": "10.00"})).collect();
+        let routes: Vec<(&'static str, String)> = vec![
+            ("/account", json!({"balances": balances}).to_string()),
+            ("/openOrders", "[]".to_string()),
+            ("/ticker/price", Value::from(prices).to_string()),
+            ("/myTrades", "[]".to_string()),
+            ("/order", r#"{"orderId":1,"executedQty":"0"}"#.to_string()),
+        ];
+
+        // Like `local_binance_recording`, but answers connections concurrently and holds each
+        // order call open briefly so overlapping ones can be counted.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let in_flight = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let (recorded, in_flight_orders, peak_orders) = (Arc::clone(&requests), Arc::clone(&in_flight), Arc::clone(&peak));
+        tokio::spawn(async move {
+            let routes = Arc::new(routes);
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (routes, recorded, in_flight, peak) = (Arc::clone(&routes), Arc::clone(&recorded), Arc::clone(&in_flight_orders), Arc::clone(&peak_orders));
+                tokio::spawn(async move {
+                    let mut request = vec![0u8; 8192];
+                    let read = socket.read(&mut request).await.unwrap_or(0);
+                    let request_line = String::from_utf8_lossy(&request[..read]).lines().next().unwrap_or_default().to_string();
+                    recorded.lock().unwrap().push(request_line.clone());
+                    let is_order = request_line.starts_with("POST ") && request_line.contains("/order");
+                    if is_order {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        sleep(Duration::from_millis(30)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    let body = routes.iter().find(|(path, _)| request_line.contains(path)).map(|(_, body)| body.clone()).unwrap_or_default();
+                    let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        let mut binance = Binance::with_credentials("key", "secret");
+        binance.base_url = format!("http://{}/api/v3", address);
+
+        let previous = std::mem::replace(&mut crate::config::SHARED_CONFIG.write().unwrap().stop_loss_concurrency, 4);
+        let completed = binance.run_stop_loss_cycle().await;
+        crate::config::SHARED_CONFIG.write().unwrap().stop_loss_concurrency = previous;
+        assert!(completed);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.iter().filter(|r| r.contains("/ticker/price")).count(), 1);
+        assert_eq!(requests.iter().filter(|r| r.starts_with("POST ") && r.contains("/order")).count(), 20);
+        let peak = peak.load(Ordering::SeqCst);
+        assert!((1..=4).contains(&peak), "{} simultaneous order calls", peak);
     }
-}
 
+    #[test]
+    fn three_failed_stop_placements_escalate_exactly_once() {
+        let mut failures = HashMap::new();
+        let alerts: Vec<bool> = (0..5).map(|_| Binance::record_stop_attempt(&mut failures, "ABCUSDT", false, 3)).collect();
+        assert_eq!(alerts, vec![false, false, true, false, false]);
+
+        // A placed stop ends the streak, so a new run of failures alerts again.
+        assert!(!Binance::record_stop_attempt(&mut failures, "ABCUSDT", true, 3));
+        assert!(!failures.contains_key("ABCUSDT"));
+        let alerts = (0..3).filter(|_| Binance::record_stop_attempt(&mut failures, "ABCUSDT", false, 3)).count();
+        assert_eq!(alerts, 1);
+        assert!(!Binance::record_stop_attempt(&mut HashMap::new(), "ABCUSDT", false, 0));
+    }
+
+    #[tokio::test]
+    async fn a_failed_ping_is_one_request_and_an_error() {
+        let (down, requests) = local_binance_recording(vec![]).await;
+        assert!(down.ping().await.is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        assert!(requests.lock().unwrap()[0].starts_with("GET /api/v3/ping"));
+
+        let up = local_binance(vec![("/ping", "{}".to_string())]).await;
+        assert!(up.ping().await.is_ok());
 
+        let mut unreachable = Binance::with_credentials("key", "secret");
+        unreachable.base_url = "http://127.0.0.1:9/api/v3".to_string();
+        assert!(unreachable.ping().await.is_err());
+    }
+
+    #[test]
+    fn a_pullback_after_eight_percent_keeps_the_profit_locked_stop() {
+        let (entry, trail_pct, lock_pct) = (100.0, 10.0, 3.0);
+        let trailing = |price: f64| price * (1.0 - trail_pct / 100.0);
 
+        let at_peak = Binance::profit_locked_stop(trailing(108.0), 90.0, entry, 108.0, lock_pct);
+        assert!((at_peak - 104.76).abs() < 1e-9);
+        let after_pullback = Binance::profit_locked_stop(trailing(105.0), at_peak, entry, 105.0, lock_pct);
+        assert_eq!(after_pullback, at_peak);
 
+        // Without the mode the stop just trails the price, and below entry nothing is locked.
+        assert_eq!(Binance::profit_locked_stop(trailing(105.0), at_peak, entry, 105.0, 0.0), trailing(105.0));
+        assert_eq!(Binance::profit_locked_stop(trailing(99.0), 90.0, entry, 99.0, lock_pct), trailing(99.0));
+    }
+
+    #[test]
+    fn a_fee_reduced_balance_sizes_the_stop_off_what_was_received() {
+        let filters = SymbolFilters { tick_size: 0.01, step_size: 0.001, min_qty: 0.001, min_price: 0.01, min_notional: 5.0, ..btc_filters() };
+        let fill = |qty: f64, commission: f64| OrderFill { price: 10.0, qty, commission, commission_asset: "XYZ".to_string() };
+
+        let received = Binance::net_base_quantity(1.0, &[fill(0.6, 0.0006), fill(0.4, 0.0004)], "XYZ");
+        assert!((received - 0.999).abs() < 1e-12);
+        assert_eq!(Binance::stop_quantity(received, 0.999, 9.5, &filters), 0.999);
+
+        // Bought just above min notional at the stop; the fee pushes it below.
+        let received = Binance::net_base_quantity(0.53, &[fill(0.53, 0.005)], "XYZ");
+        let quantity = Binance::stop_quantity(received, received, 9.5, &filters);
+        assert_eq!(quantity, 0.525);
+        assert!(!Binance::is_dust(0.53, 9.5, &filters));
+        assert!(Binance::is_dust(quantity, 9.5, &filters));
+    }
+
+    #[test]
+    fn btc_keeps_eight_decimals_and_a_cheap_token_buys_whole_units() {
+        let btc = Binance::quantity_for_quote(100.0, 67_321.5, 0.000_000_01);
+        assert!((btc - 0.001_485_40).abs() < 1e-12, "{}", btc);
+        // The old five-decimal floor would have dropped the last three digits.
+        assert!(btc - 0.001_48 > 0.000_005);
+
+        assert_eq!(Binance::quantity_for_quote(10.0, 0.000_003, 1.0), 3_333_333.0);
+        assert_eq!(Binance::quantity_for_quote(10.0, 0.0, 1.0), 0.0);
+    }
 
+    #[tokio::test]
+    async fn a_buy_recorded_by_the_market_loop_is_seen_by_the_stop_loop() {
+        let binance = Arc::new(Binance::with_credentials("key", "secret"));
 
-    
\ No newline at end of file
+        let market = Arc::clone(&binance);
+        tokio::spawn(async move { market.positions().lock().await.record_buy("SHAREDUSDT", 2.5, 40.0, Utc::now()) })
+            .await
+            .unwrap();
+
+        let stop_loop = Arc::clone(&binance);
+        let entry = tokio::spawn(async move { stop_loop.entry_price("SHAREDUSDT").await }).await.unwrap();
+        assert_eq!(entry, Some(2.5));
+        assert_eq!(binance.positions().lock().await.get("SHAREDUSDT").map(|p| p.quantity), Some(40.0));
+    }
+
+    #[test]
+    fn a_bnb_paid_commission_leaves_the_bought_quantity_unchanged() {
+        let fill = |qty: f64, commission: f64, asset: &str| OrderFill { price: 2.0, qty, commission, commission_asset: asset.to_string() };
+
+        let bnb_paid = [fill(30.0, 0.0001, "BNB"), fill(20.0, 0.00007, "BNB")];
+        assert_eq!(Binance::net_base_quantity(50.0, &bnb_paid, "XYZ"), 50.0);
+
+        let base_paid = [fill(30.0, 0.03, "XYZ"), fill(20.0, 0.02, "XYZ")];
+        assert!((Binance::net_base_quantity(50.0, &base_paid, "XYZ") - 49.95).abs() < 1e-9);
+
+        let mixed = [fill(30.0, 0.0001, "BNB"), fill(20.0, 0.02, "XYZ")];
+        assert!((Binance::net_base_quantity(50.0, &mixed, "XYZ") - 49.98).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_one_percent_stop_is_widened_to_the_three_percent_floor() {
+        let percent = Binance::floored_stop_percent(1.0, 3.0);
+        assert_eq!(percent, 3.0);
+        let stop = Binance::round_to_step(100.0 * (1.0 - percent / 100.0), 0.01);
+        assert!((stop - 97.0).abs() < 1e-9);
+
+        assert_eq!(Binance::floored_stop_percent(5.0, 3.0), 5.0);
+        assert_eq!(Binance::floored_stop_percent(1.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn the_curved_stop_tightens_monotonically_with_the_gain() {
+        let distances: Vec<f64> = (0..=20).map(|gain| Binance::curved_stop_percent(5.0, 0.1, gain as f64)).collect();
+        assert_eq!(distances[0], 5.0);
+        assert!(distances.windows(2).all(|w| w[1] < w[0]), "{:?}", distances);
+        assert!((distances[20] - 5.0 / 3.0).abs() < 1e-9);
+        // A loss does not widen the stop past the base distance.
+        assert_eq!(Binance::curved_stop_percent(5.0, 0.1, -4.0), 5.0);
+    }
+
+    #[test]
+    fn a_buffered_limit_still_fills_after_a_gap_through_the_trigger() {
+        // The stop triggers at 100 but the next trade prints at 99.2.
+        let (trigger, gapped_to) = (100.0, 99.2);
+        let fills = |limit: f64| gapped_to >= limit;
+
+        let unbuffered = Binance::stop_limit_price(trigger, 0.0, 0.01);
+        assert_eq!(unbuffered, trigger);
+        assert!(!fills(unbuffered));
+
+        let buffered = Binance::stop_limit_price(trigger, 1.0, 0.01);
+        assert!((buffered - 99.0).abs() < 1e-9);
+        assert!(fills(buffered));
+        // Never rounded down to a zero limit on a tiny price.
+        assert_eq!(Binance::stop_limit_price(0.015, 50.0, 0.01), 0.01);
+    }
+
+    #[test]
+    fn the_trailing_query_takes_the_callback_and_activation_from_separate_fields() {
+        let previous = {
+            let mut config = crate::config::SHARED_CONFIG.write().unwrap();
+            let previous = (config.trailing_callback_pct, config.trailing_activation_pct);
+            (config.trailing_callback_pct, config.trailing_activation_pct) = (1.5, 4.0);
+            previous
+        };
+        let params = Binance::trailing_stop_params(50.0, None, 0.01);
+        let overridden = Binance::trailing_stop_params(50.0, Some(55.0), 0.01);
+        {
+            let mut config = crate::config::SHARED_CONFIG.write().unwrap();
+            (config.trailing_callback_pct, config.trailing_activation_pct) = previous;
+        }
+
+        assert_eq!(params, (1.5, Some(52.0)));
+        assert_eq!(overridden, (1.5, Some(55.0)));
+        let (callback_rate, activation_price) = params;
+        let query = OrderRequest::new("ABCUSDT", "SELL", "TRAILING_STOP_MARKET")
+            .futures()
+            .quantity(3.0)
+            .callback_rate(callback_rate)
+            .activation_price(activation_price)
+            .to_query()
+            .unwrap();
+        assert!(query.contains("callbackRate=1.5&"), "{}", query);
+        assert!(query.contains("activationPrice=52&"), "{}", query);
+    }
+
+    #[tokio::test]
+    async fn sample_funding_and_open_interest_responses_are_parsed() {
+        let binance = local_binance(vec![
+            (
+                "/premiumIndex?symbol=BTCUSDT",
+                r#"{"symbol":"BTCUSDT","markPrice":"67321.50000000","indexPrice":"67300.1","lastFundingRate":"-0.00030000","nextFundingTime":1760000000000,"time":1759990000000}"#.to_string(),
+            ),
+            ("/openInterest?symbol=BTCUSDT", r#"{"openInterest":"81234.567","symbol":"BTCUSDT","time":1759990000000}"#.to_string()),
+        ])
+        .await;
+
+        let funding = binance.get_funding_rate("BTCUSDT").await.unwrap().unwrap();
+        assert_eq!(funding.symbol, "BTCUSDT");
+        assert!((funding.rate_pct + 0.03).abs() < 1e-12);
+        assert_eq!(funding.mark_price, 67321.5);
+        assert_eq!(funding.next_funding_time, 1760000000000);
+        let open_interest = binance.get_open_interest("BTCUSDT").await.unwrap().unwrap();
+        assert_eq!(open_interest, OpenInterest { symbol: "BTCUSDT".to_string(), open_interest: 81234.567, time: 1759990000000 });
+
+        // No perpetual for a spot-only symbol: the mock answers 404 as Binance answers 400.
+        assert_eq!(binance.get_funding_rate("SPOTONLYUSDT").await.unwrap(), None);
+        assert_eq!(binance.get_open_interest("SPOTONLYUSDT").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn a_two_asset_account_is_valued_at_the_mocked_prices() {
+        let binance = local_binance(vec![
+            (
+                "/account",
+                r#"{"balances":[{"asset":"USDT","free":"150.5","locked":"49.5"},{"asset":"ETH","free":"0.5","locked":"1.5"},{"asset":"DOGE","free":"0","locked":"0"}]}"#.to_string(),
+            ),
+            ("/ticker/price", r#"[{"symbol":"ETHUSDT","price":"2500.00"},{"symbol":"BTCUSDT","price":"60000.00"}]"#.to_string()),
+        ])
+        .await;
+        // 200 USDT + 2 ETH at 2500.
+        assert_eq!(binance.account_equity().await.unwrap(), 5_200.0);
+
+        // An asset without a USDT pair is valued through its BTC pair.
+        let balances: Vec<Balance> = serde_json::from_str(r#"[{"asset":"XYZ","free":"10","locked":"0"}]"#).unwrap();
+        let prices: HashMap<String, f64> = [("XYZBTC".to_string(), 0.0001), ("BTCUSDT".to_string(), 60_000.0)].into_iter().collect();
+        assert!((Binance::value_balances(&balances, &prices, "USDT") - 60.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trade_frames_parse_and_malformed_ones_are_skipped() {
+        let frame = r#"{"stream":"abcusdt@trade","data":{"e":"trade","s":"ABCUSDT","p":"101.25","q":"3"}}"#;
+        assert_eq!(parse_trade_price(frame), Some(("ABCUSDT".to_string(), 101.25)));
+        for malformed in [
+            "not json",
+            r#"{"e":"trade","s":"ABCUSDT","p":"101.25"}"#,
+            r#"{"data":{"s":"ABCUSDT"}}"#,
+            r#"{"data":{"s":"ABCUSDT","p":"abc"}}"#,
+            r#"{"data":{"s":"ABCUSDT","p":101.25}}"#,
+            r#"{"data":{"p":"101.25"}}"#,
+        ] {
+            assert_eq!(parse_trade_price(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_streamed_spike_and_drop_moves_the_stop_long_before_the_poll() {
+        let symbol = "STRMUSDT";
+        mark_tradable(&[symbol]).await;
+        SYMBOL_FILTERS.write().await.insert(symbol.to_string(), (Instant::now(), btc_filters()));
+        let (mut binance, requests) = local_binance_recording(vec![
+            ("DELETE /api/v3/order", "{}".to_string()),
+            ("POST /api/v3/order", r#"{"orderId":2,"executedQty":"0"}"#.to_string()),
+        ])
+        .await;
+        let trade = |price: &str| format!(r#"{{"stream":"strmusdt@trade","data":{{"s":"{}","p":"{}"}}}}"#, symbol, price);
+        super::mock::serve_trade_stream(&mut binance, vec![trade("120"), "garbage".to_string(), trade("101")]).await;
+
+        let mut stops = HashMap::from([(symbol.to_string(), streamed_stop(None))]);
+        let started = Instant::now();
+        let poll = Duration::from_secs(900);
+        tokio::time::timeout(Duration::from_secs(10), binance.follow_stop_stream(&mut stops, started + poll))
+            .await
+            .expect("the stream ends without waiting for the poll interval")
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+            // The spike raised the stop before the drop, so the drop meets the raised stop.
+        let stop = &stops[symbol];
+        assert_eq!(stop.order_id, 2);
+        assert!(stop.stop_price > 95.0 && stop.stop_price < 120.0, "{}", stop.stop_price);
+        assert_eq!(stop.high_water, 120.0);
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("DELETE /api/v3/order") && requests[0].contains("orderId=1"), "{:?}", requests);
+        assert!(requests[1].starts_with("POST /api/v3/order") && requests[1].contains("type=STOP_LOSS_LIMIT"), "{:?}", requests);
+        assert_eq!(requests.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn trimming_sells_the_step_rounded_excess_and_keeps_the_cap() {
+        let symbol = "TRIMUSDT";
+        mark_tradable(&[symbol]).await;
+        let (binance, requests) = local_binance_recording(vec![("POST /api/v3/order", r#"{"orderId":9,"executedQty":"0.18333"}"#.to_string())]).await;
+
+        let cap = std::mem::replace(&mut SHARED_CONFIG.write().unwrap().max_position_quote, 150.0);
+        // 0.3333333 units at 1000 is 333.33 quote; the cap keeps 0.15 of it.
+        let remaining = binance.trim_position(symbol, 0.3333333, 1000.0, &btc_filters()).await;
+        SHARED_CONFIG.write().unwrap().max_position_quote = cap;
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1, "{:?}", requests);
+        assert!(requests[0].contains("side=SELL") && requests[0].contains("type=MARKET"), "{}", requests[0]);
+        assert!(requests[0].contains("quantity=0.18333&"), "{}", requests[0]);
+        assert_eq!(remaining, 0.15);
+    }
+
+    #[tokio::test]
+    async fn the_exit_rsi_is_the_wilder_rsi_of_the_warmed_up_series() {
+        // A long slide, then a bounce over the last window: warmup changes the reading.
+        let closes: Vec<f64> = (0..2 * RSI_EXIT_PERIOD + 1)
+            .map(|i| if i <= RSI_EXIT_PERIOD { 200.0 - 5.0 * i as f64 } else { 130.0 + 2.0 * (i - RSI_EXIT_PERIOD) as f64 * (1.0 + (i % 2) as f64) })
+            .collect();
+        let klines: Vec<Value> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| json!([i as i64 * 3_600_000, close.to_string(), close.to_string(), close.to_string(), close.to_string(), "1", i as i64 * 3_600_000 + 3_599_999]))
+            .collect();
+        let (binance, requests) = local_binance_recording(vec![("/klines", Value::from(klines).to_string())]).await;
+
+        let rsi = binance.latest_rsi("RSIWUSDT").await.unwrap();
+        assert_eq!(Some(rsi), compute_wilder_rsi(&closes, RSI_EXIT_PERIOD));
+        let window_only = compute_wilder_rsi(&closes[RSI_EXIT_PERIOD..], RSI_EXIT_PERIOD).unwrap();
+        assert!((rsi - window_only).abs() > 1.0, "{} vs {}", rsi, window_only);
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains(&format!("limit={} ", 2 * RSI_EXIT_PERIOD + 1)), "{}", requests[0]);
+    }
+
+    #[tokio::test]
+    async fn a_timestamp_rejection_resyncs_the_clock_and_retries_once() {
+        for code in TIMESTAMP_ERROR_CODES {
+            let server_time = Utc::now().timestamp_millis() + 600_000;
+            let (binance, requests) = super::mock::local_binance_scripted(vec![
+                ("/api/v3/time", vec![(200, json!({ "serverTime": server_time }).to_string())]),
+                (
+                    "/api/v3/account",
+                    vec![
+                        (400, json!({ "code": code, "msg": "rejected" }).to_string()),
+                        (200, r#"{"balances":[{"asset":"USDT","free":"12.5","locked":"0"}]}"#.to_string()),
+                    ],
+                ),
+            ])
+            .await;
+
+            assert_eq!(binance.get_account_balance("USDT").await.unwrap(), 12.5);
+
+            let requests = requests.lock().unwrap();
+            assert_eq!(requests.len(), 3, "{:?}", requests);
+            assert!(requests[1].contains("/api/v3/time"), "{:?}", requests);
+            let timestamp = |request: &str| -> i64 {
+                let query = request.split('?').nth(1).unwrap();
+                query.split('&').find_map(|p| p.strip_prefix("timestamp=")).unwrap().parse().unwrap()
+            };
+            // The first attempt used the local clock; the retry is ten minutes ahead with the server.
+            assert!(timestamp(&requests[0]) < server_time - 500_000, "{:?}", requests);
+            assert!((timestamp(&requests[2]) - server_time).abs() < 5_000, "{:?}", requests);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_holding_below_min_notional_gets_no_stop_and_is_tallied_as_dust() {
+        let (dust_symbol, held_symbol) = ("DUSTXUSDT", "PROTXUSDT");
+        mark_tradable(&[dust_symbol, held_symbol]).await;
+        for symbol in [dust_symbol, held_symbol] {
+            SYMBOL_FILTERS.write().await.insert(symbol.to_string(), (Instant::now(), btc_filters()));
+        }
+        let (binance, requests) = local_binance_recording(vec![
+            ("/myTrades", "[]".to_string()),
+            ("POST /api/v3/order", r#"{"orderId":3,"executedQty":"0"}"#.to_string()),
+        ])
+        .await;
+
+        // 0.00005 units at 60000 is 3 quote, under the 5 quote minNotional; 0.0002 units clear it.
+        let unprotected = vec![
+            ("DUSTX".to_string(), dust_symbol.to_string(), 0.00005, 60_000.0),
+            ("PROTX".to_string(), held_symbol.to_string(), 0.0002, 60_000.0),
+        ];
+        let dust = binance.place_missing_stops(unprotected, 2).await;
+
+        assert_eq!(dust, HashMap::from([("DUSTX".to_string(), 0.00005 * 60_000.0)]));
+        assert!((dust.values().sum::<f64>() - 3.0).abs() < 1e-9);
+        let orders: Vec<String> = requests.lock().unwrap().iter().filter(|r| r.contains("/order")).cloned().collect();
+        assert_eq!(orders.len(), 1, "{:?}", orders);
+        assert!(orders[0].contains(&format!("symbol={}", held_symbol)) && orders[0].contains("type=STOP_LOSS_LIMIT"), "{}", orders[0]);
+    }
+}
@@ -0,0 +1,95 @@
+//! A local stand-in for the Binance REST API, so tests (including the binaries' tests, through
+//! the `mock` feature) can drive real client code against canned responses.
+
+use super::*;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Request lines served by a mock, in the order they arrived.
+pub type RecordedRequests = Arc<std::sync::Mutex<Vec<String>>>;
+
+/// Serves canned JSON on a free local port: each request gets the body of the first route
+/// whose path fragment appears in its request line, or a 404. Returns a client pointed at it.
+pub async fn local_binance(routes: Vec<(&'static str, String)>) -> Binance {
+    local_binance_recording(routes).await.0
+}
+
+/// `local_binance` that also records the request line of every request it serves.
+pub async fn local_binance_recording(routes: Vec<(&'static str, String)>) -> (Binance, RecordedRequests) {
+    let routes = routes
+        .into_iter()
+        .map(|(path, body)| (path, vec![(200, body)]))
+        .collect();
+    local_binance_scripted(routes).await
+}
+
+/// `local_binance_recording` where each route answers with its `(status, body)` responses in
+/// turn, repeating the last one once they run out.
+pub async fn local_binance_scripted(routes: Vec<(&'static str, Vec<(u16, String)>)>) -> (Binance, RecordedRequests) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&requests);
+    tokio::spawn(async move {
+        let mut served = vec![0usize; routes.len()];
+        while let Ok((mut socket, _)) = listener.accept().await {
+            let mut request = vec![0u8; 8192];
+            let read = socket.read(&mut request).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&request[..read]);
+            let request_line = request.lines().next().unwrap_or_default();
+            recorded.lock().unwrap().push(request_line.to_string());
+            let (status, body) = match routes.iter().position(|(path, _)| request_line.contains(path)) {
+                Some(index) => {
+                    let responses = &routes[index].1;
+                    let response = responses[served[index].min(responses.len() - 1)].clone();
+                    served[index] += 1;
+                    response
+                }
+                None => (404, r#"{"code":-1,"msg":"no route"}"#.to_string()),
+            };
+            let reason = StatusCode::from_u16(status)
+                .ok()
+                .and_then(|s| s.canonical_reason())
+                .unwrap_or("");
+            let response = format!(
+                "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reason,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    let mut binance = Binance::with_credentials("key", "secret");
+    binance.base_url = format!("http://{}/api/v3", address);
+    binance.futures_base_url = format!("http://{}/fapi/v1", address);
+    binance.sapi_base_url = format!("http://{}/sapi/v1", address);
+    (binance, requests)
+}
+
+/// Adds `symbols` to the tradable symbols cache, keeping what other tests put there.
+pub async fn mark_tradable(symbols: &[&str]) {
+    let mut cache = TRADABLE_SYMBOLS.write().await;
+    let mut tradable: HashSet<String> = cache.as_ref().map(|(_, s)| (**s).clone()).unwrap_or_default();
+    tradable.extend(symbols.iter().map(|s| s.to_string()));
+    *cache = Some((Instant::now(), Arc::new(tradable)));
+}
+
+/// Points `binance` at a local WebSocket that sends `frames` as text messages to its first
+/// subscriber, then closes the stream.
+pub async fn serve_trade_stream(binance: &mut Binance, frames: Vec<String>) {
+    use futures_util::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    binance.stream_base_url = format!("ws://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let Ok((socket, _)) = listener.accept().await else { return };
+        let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await else { return };
+        for frame in frames {
+            let _ = ws.send(Message::Text(frame)).await;
+        }
+        let _ = ws.close(None).await;
+    });
+}
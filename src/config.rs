@@ -1,12 +1,33 @@
 use dotenv::from_filename;
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, RwLock};
+use std::path::Path;
+use std::sync::{Arc, Once, RwLock};
 use notify::{Watcher};
 use chrono::Local;
 use chrono::Datelike;
+use tracing::warn;
 
 
+/// How much non-essential console output the binaries print. Tracing logs are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    pub fn parse(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "quiet" => Verbosity::Quiet,
+            "verbose" => Verbosity::Verbose,
+            _ => Verbosity::Normal,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub stop_loss_percent: f64,
@@ -31,14 +52,81 @@ pub struct Config {
     pub log_file: String,
     pub excluded_days: Vec<String>,
     pub excluded_tokens: Vec<String>,
+    pub excluded_base_assets: Vec<String>,
+    pub max_account_value: f64,
+    pub max_spread_pct: f64,
+    pub verbosity: Verbosity,
+    pub default_transaction_amount: f64,
+    pub stop_loss_stream: bool,
+    pub max_position_quote: f64,
+    pub report_timezone: String,
+    pub initial_stop_from_entry: bool,
+    pub closed_candles_only: bool,
+    pub take_profit_levels: Vec<(f64, f64)>,
+    pub scan_change_delta: f64,
+    pub futures_trailing_stops: bool,
+    pub rsi_period: u16,
+    pub rsi_max: f64,
+    pub trailing_activation_pct: f64,
+    pub buy_spacing_ms: u64,
+    pub http_connect_timeout_ms: u64,
+    pub http_request_timeout_ms: u64,
+    pub require_n_green: u32,
+    pub green_candle_min_pct: f64,
+    pub rsi_exit_threshold: f64,
+    pub rsi_exit_fraction: f64,
+    pub max_candidates_per_scan: usize,
+    pub stop_loss_concurrency: usize,
+    pub unprotected_alert_after: u32,
+    pub min_symbol_age_days: u32,
+    pub max_daily_spend: f64,
+    pub signal_recent_window: u16,
+    pub profit_lock_trail_pct: f64,
+    pub quote_rotation: bool,
+    pub min_stop_distance_pct: f64,
+    pub stop_curve_base_pct: f64,
+    pub stop_curve_k: f64,
+    pub stop_limit_buffer_pct: f64,
+    pub trailing_callback_pct: f64,
+    pub min_funding_rate_pct: Option<f64>,
+    pub trade_log_decimals: u32,
+    pub green_candle_min_body_ratio: f64,
+    pub enable_shorts: bool,
+    pub quote_precision_overrides: HashMap<String, u32>,
+}
+
+/// Loads "vars.env" into the process environment once, if it exists. Variables that are
+/// already set win, so env-only deployments need no file at all.
+pub fn load_env_file() {
+    static LOADED: Once = Once::new();
+    LOADED.call_once(|| {
+        let _ = from_filename("vars.env");
+    });
 }
 
 impl Config {
-    /// Loads configuration from the "vars.env" file.
+    /// Loads configuration from the "vars.env" file (if present) and the process environment.
     pub fn load() -> Self {
-        // Load the environment variables from vars.env.
+        // Re-read the file on every load so watch_config picks up edits.
         let _ = from_filename("vars.env");
+        Self::from_env()
+    }
+
+    /// Re-applies every variable in the env file at `path` over the process environment, so an
+    /// edit wins over the value loaded at startup, then builds the configuration.
+    pub fn reload_from(path: &Path) -> Self {
+        // The iterator is deprecated in favour of `from_path`, which never overrides a variable.
+        #[allow(deprecated)]
+        if let Ok(vars) = dotenv::from_path_iter(path) {
+            for (key, value) in vars.flatten() {
+                env::set_var(key, value);
+            }
+        }
+        Self::from_env()
+    }
 
+    /// Builds the configuration from process environment variables only, never touching disk.
+    pub fn from_env() -> Self {
         let stop_loss_percent = env::var("STOP_LOSS_PERCENT")
             .unwrap_or_else(|_| "10".to_string())
             .parse::<f64>()
@@ -114,6 +202,12 @@ impl Config {
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
             .collect::<Vec<_>>();
+        let excluded_base_assets = env::var("EXCLUDED_BASE_ASSETS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_uppercase())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
         let min_volume = env::var("MIN_VOLUME_USD")
             .unwrap_or_else(|_| "500000".to_string())
             .parse()
@@ -132,6 +226,175 @@ impl Config {
             .unwrap_or_else(|_| "logs/".to_string());
         let trade_log_folder = env::var("TRADE_LOG_FOLDER")
             .unwrap_or_else(|_| "logs/trades".to_string());
+        // 0 disables the safe-mode check.
+        let max_account_value = env::var("MAX_ACCOUNT_VALUE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        // 0 disables the order-book spread filter.
+        let max_spread_pct = env::var("MAX_SPREAD_PCT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let verbosity = Verbosity::parse(&env::var("VERBOSITY").unwrap_or_default());
+        let default_transaction_amount = env::var("DEFAULT_TRANSACTION_AMOUNT")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<f64>()
+            .unwrap_or(5.0);
+        let stop_loss_stream = env::var("STOP_LOSS_STREAM")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+        // 0 disables trimming of oversized positions.
+        let max_position_quote = env::var("MAX_POSITION_QUOTE")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let report_timezone = env::var("REPORT_TIMEZONE")
+            .unwrap_or_else(|_| "UTC".to_string());
+        let initial_stop_from_entry = env::var("INITIAL_STOP_FROM_ENTRY")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(true);
+        let closed_candles_only = env::var("CLOSED_CANDLES_ONLY")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+        let take_profit_levels = parse_take_profit_levels(&env::var("TAKE_PROFIT_LEVELS").unwrap_or_default());
+        // 0 re-evaluates every candidate on every scan.
+        let scan_change_delta = env::var("SCAN_CHANGE_DELTA")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let futures_trailing_stops = env::var("FUTURES_TRAILING_STOPS")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+        // 0 disables the RSI entry filter.
+        let rsi_period = env::var("RSI_PERIOD")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u16>()
+            .unwrap_or(0);
+        let rsi_max = env::var("RSI_MAX")
+            .unwrap_or_else(|_| "70".to_string())
+            .parse::<f64>()
+            .unwrap_or(70.0);
+        // 0 arms trailing stops immediately at entry.
+        let trailing_activation_pct = env::var("TRAILING_ACTIVATION_PCT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        // 0 places consecutive buys back-to-back.
+        let buy_spacing_ms = env::var("BUY_SPACING_MS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .unwrap_or(0);
+        // HTTP timeouts for Binance requests; 0 disables the respective timeout.
+        let http_connect_timeout_ms = env::var("HTTP_CONNECT_TIMEOUT_MS")
+            .unwrap_or_else(|_| "5000".to_string())
+            .parse::<u64>()
+            .unwrap_or(5000);
+        let http_request_timeout_ms = env::var("HTTP_REQUEST_TIMEOUT_MS")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse::<u64>()
+            .unwrap_or(10000);
+        // Entry confirmation: the last N candles must each gain at least GREEN_CANDLE_MIN_PCT.
+        let require_n_green = env::var("REQUIRE_N_GREEN")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse::<u32>()
+            .unwrap_or(2);
+        let green_candle_min_pct = env::var("GREEN_CANDLE_MIN_PCT")
+            .unwrap_or_else(|_| "0.5".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.5);
+        // Sell into strength: trim held positions once their 1h RSI exceeds this (0 disables).
+        let rsi_exit_threshold = env::var("RSI_EXIT_THRESHOLD")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let rsi_exit_fraction = env::var("RSI_EXIT_FRACTION")
+            .unwrap_or_else(|_| "0.5".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.5)
+            .clamp(0.0, 1.0);
+        // 0 evaluates every candidate; otherwise only the N highest 24h quote volumes.
+        let max_candidates_per_scan = env::var("MAX_CANDIDATES_PER_SCAN")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<usize>()
+            .unwrap_or(0);
+        let stop_loss_concurrency = env::var("STOP_LOSS_CONCURRENCY")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse::<usize>()
+            .unwrap_or(4)
+            .max(1);
+        let unprotected_alert_after = env::var("UNPROTECTED_ALERT_AFTER")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<u32>()
+            .unwrap_or(3);
+        let min_symbol_age_days = env::var("MIN_SYMBOL_AGE_DAYS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u32>()
+            .unwrap_or(0);
+        let max_daily_spend = env::var("MAX_DAILY_SPEND")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let signal_recent_window = env::var("SIGNAL_RECENT_WINDOW")
+            .ok()
+            .and_then(|v| v.parse::<u16>().ok())
+            .unwrap_or(last_hours_period);
+        let profit_lock_trail_pct = env::var("PROFIT_LOCK_TRAIL_PCT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        let quote_rotation = env::var("QUOTE_ROTATION")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+        let min_stop_distance_pct = env::var("MIN_STOP_DISTANCE_PCT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0);
+        // Continuous trailing curve once in profit: stop_pct = STOP_CURVE_BASE_PCT / (1 + STOP_CURVE_K * gain%).
+        let stop_curve_base_pct = env::var("STOP_CURVE_BASE_PCT")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(stop_loss_percent_profit);
+        let stop_curve_k = env::var("STOP_CURVE_K")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0)
+            .max(0.0);
+        // 0 places STOP_LOSS_LIMIT orders with the limit equal to the trigger.
+        let stop_limit_buffer_pct = env::var("STOP_LIMIT_BUFFER_PCT")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0)
+            .clamp(0.0, 50.0);
+        // How far a trailing stop trails the high; defaults to STOP_LOSS_PERCENT.
+        let trailing_callback_pct = env::var("TRAILING_CALLBACK_PCT")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .unwrap_or(stop_loss_percent);
+        // Unset disables the futures funding gate; e.g. -0.05 skips longs while funding is below -0.05%.
+        let min_funding_rate_pct = env::var("MIN_FUNDING_RATE_PCT")
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok());
+        // 0 logs prices and quantities at full precision.
+        let trade_log_decimals = env::var("TRADE_LOG_DECIMALS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u32>()
+            .unwrap_or(0);
+        // 0 ignores wicks; e.g. 0.5 rejects confirming candles whose body is under half their range.
+        let green_candle_min_body_ratio = env::var("GREEN_CANDLE_MIN_BODY_RATIO")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0);
+        let enable_shorts = env::var("ENABLE_SHORTS")
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+            .unwrap_or(false);
+        // Empty = every price keeps the precision derived from the symbol filters.
+        let quote_precision_overrides = parse_quote_precision_overrides(&env::var("QUOTE_PRECISION_OVERRIDES").unwrap_or_default());
+        for quote in quote_assets.iter().skip(transaction_amounts.len()) {
+            warn!("⚠️ TRANSACTION_AMOUNTS has no entry for {}; it will use DEFAULT_TRANSACTION_AMOUNT {}", quote, default_transaction_amount);
+            eprintln!("⚠️ TRANSACTION_AMOUNTS has no entry for {}; it will use DEFAULT_TRANSACTION_AMOUNT {}", quote, default_transaction_amount);
+        }
         Config {
             stop_loss_percent,
             max_open_trades,
@@ -155,7 +418,134 @@ impl Config {
             log_file,
             excluded_days,
             excluded_tokens,
+            excluded_base_assets,
+            max_account_value,
+            max_spread_pct,
+            verbosity,
+            default_transaction_amount,
+            stop_loss_stream,
+            max_position_quote,
+            report_timezone,
+            initial_stop_from_entry,
+            closed_candles_only,
+            take_profit_levels,
+            scan_change_delta,
+            futures_trailing_stops,
+            rsi_period,
+            rsi_max,
+            trailing_activation_pct,
+            buy_spacing_ms,
+            http_connect_timeout_ms,
+            http_request_timeout_ms,
+            require_n_green,
+            green_candle_min_pct,
+            rsi_exit_threshold,
+            rsi_exit_fraction,
+            max_candidates_per_scan,
+            stop_loss_concurrency,
+            unprotected_alert_after,
+            min_symbol_age_days,
+            max_daily_spend,
+            signal_recent_window,
+            profit_lock_trail_pct,
+            quote_rotation,
+            min_stop_distance_pct,
+            stop_curve_base_pct,
+            stop_curve_k,
+            stop_limit_buffer_pct,
+            trailing_callback_pct,
+            min_funding_rate_pct,
+            trade_log_decimals,
+            green_candle_min_body_ratio,
+            enable_shorts,
+            quote_precision_overrides,
+        }
+    }
+
+    /// Returns the transaction amount paired with `quote` in QUOTE_ASSETS/TRANSACTION_AMOUNTS,
+    /// falling back to DEFAULT_TRANSACTION_AMOUNT (with a warning) when there is none.
+    pub fn transaction_amount_for(&self, quote: &str) -> f64 {
+        self.configured_transaction_amount(quote).unwrap_or_else(|warning| {
+            warn!("⚠️ {}", warning);
+            eprintln!("⚠️ {}", warning);
+            self.default_transaction_amount
+        })
+    }
+
+    /// The amount paired with `quote`, or the fallback warning naming `quote` when there is none.
+    fn configured_transaction_amount(&self, quote: &str) -> Result<f64, String> {
+        self.quote_assets
+            .iter()
+            .position(|a| a == quote)
+            .and_then(|i| self.transaction_amounts.get(i).copied())
+            .ok_or_else(|| format!("No transaction amount configured for {}; falling back to {}", quote, self.default_transaction_amount))
+    }
+
+    /// Checks values that parse but cannot work together, plus the folders logs are written to.
+    /// Returns one message per problem; an empty list means the config is usable.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(self.stop_loss_percent > 0.0 && self.stop_loss_percent < 100.0) {
+            problems.push(format!("STOP_LOSS_PERCENT {} must be between 0 and 100", self.stop_loss_percent));
+        }
+        if self.min_stop_distance_pct >= 100.0 {
+            problems.push(format!("MIN_STOP_DISTANCE_PCT {} must be below 100", self.min_stop_distance_pct));
+        }
+        if self.quote_assets.iter().all(|q| q.is_empty()) {
+            problems.push("QUOTE_ASSETS is empty".to_string());
+        }
+        if let Some(amount) = self.transaction_amounts.iter().find(|a| **a <= 0.0) {
+            problems.push(format!("TRANSACTION_AMOUNTS contains a non-positive amount {}", amount));
+        }
+        if self.signal_recent_window > self.lookback_period {
+            problems.push(format!(
+                "SIGNAL_RECENT_WINDOW {} is longer than LOOKBACK_PERIOD {}",
+                self.signal_recent_window, self.lookback_period
+            ));
+        }
+        if self.max_open_trades == 0 {
+            problems.push("MAX_OPEN_TRADES is 0, so no trade can ever open".to_string());
+        }
+        if self.report_timezone.parse::<chrono_tz::Tz>().is_err() {
+            problems.push(format!("REPORT_TIMEZONE '{}' is not a known IANA timezone", self.report_timezone));
+        }
+        for (name, folder) in [("TRADE_LOG_FOLDER", &self.trade_log_folder), ("LOG_FOlDER", &self.log_folder)] {
+            if let Err(e) = check_writable_dir(Path::new(folder)) {
+                problems.push(format!("{} '{}': {}", name, folder, e));
+            }
         }
+        problems
+    }
+}
+
+/// Succeeds when files can be created in `dir`, or, if it does not exist yet, in the nearest
+/// existing ancestor it would be created under. Probes with a temporary file it removes again.
+pub fn check_writable_dir(dir: &Path) -> Result<(), String> {
+    let existing = dir
+        .ancestors()
+        .find(|p| p.as_os_str().is_empty() || p.exists())
+        .map(|p| if p.as_os_str().is_empty() { Path::new(".") } else { p })
+        .unwrap_or(Path::new("."));
+    if !existing.is_dir() {
+        return Err(format!("{} exists but is not a directory", existing.display()));
+    }
+    let probe = existing.join(format!(".stock_pred_write_check_{}", std::process::id()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) if existing == dir => Err(format!("not writable ({})", e)),
+        Err(e) => Err(format!("does not exist and cannot be created under {} ({})", existing.display(), e)),
+    }
+}
+
+/// Shows only the first four characters of a secret, enough to tell keys apart.
+pub fn redact(secret: &str) -> String {
+    if secret.is_empty() {
+        "<not set>".to_string()
+    } else {
+        format!("{}… ({} chars)", secret.chars().take(4).collect::<String>(), secret.chars().count())
     }
 }
 
@@ -164,7 +554,7 @@ pub static SHARED_CONFIG: Lazy<SharedConfig> = Lazy::new(|| Arc::new(RwLock::new
 
 /// Returns available transaction amounts.
 pub fn get_transaction_amounts() -> Vec<f64> {
-    let _ = from_filename("vars.env");
+    load_env_file();
     env::var("TRANSACTION_AMOUNTS")
         .unwrap_or_else(|_| "10".to_string())
         .split(',')
@@ -174,8 +564,7 @@ pub fn get_transaction_amounts() -> Vec<f64> {
 
 pub fn get_quote_amount_and_stop_loss(quote: &str) -> (f64, f64) {
     let config = SHARED_CONFIG.read().unwrap();
-    let i = config.quote_assets.iter().position(|a| a == quote).unwrap_or(0);
-    let quote_amount = config.transaction_amounts.get(i).copied().unwrap_or(5.0);
+    let quote_amount = config.transaction_amount_for(quote);
     let stop_loss_percent = config.stop_loss_percent;
     (quote_amount, stop_loss_percent)
 }
@@ -207,8 +596,7 @@ pub fn get_loop_time_seconds() -> u64 {
 
 /// Returns backtesting lookback options from the BT_LOOKBACK_OPTIONS env variable.
 pub fn get_lookback_options() -> Vec<u16> {
-    // Ensure vars.env is loaded
-    let _ = from_filename("vars.env");
+    load_env_file();
     let opts = env::var("BT_LOOKBACK_OPTIONS").unwrap_or_else(|_| "6,8,12".to_string());
     opts.split(',')
         .filter_map(|s| s.trim().parse::<u16>().ok())
@@ -217,7 +605,7 @@ pub fn get_lookback_options() -> Vec<u16> {
 
 /// Returns backtesting recent options from the BT_RECENT_OPTIONS env variable.
 pub fn get_recent_options() -> Vec<u16> {
-    let _ = from_filename("vars.env");
+    load_env_file();
     let opts = env::var("BT_RECENT_OPTIONS").unwrap_or_else(|_| "2,4,6".to_string());
     opts.split(',')
         .filter_map(|s| s.trim().parse::<u16>().ok())
@@ -225,8 +613,7 @@ pub fn get_recent_options() -> Vec<u16> {
 }
 
 pub fn get_bt_stop_loss_options() -> Vec<f64> {
-    // Load from vars.env if not already loaded.
-    let _ = from_filename("vars.env");
+    load_env_file();
     let opts = env::var("BT_STOP_LOSS_PERCENT").unwrap_or_else(|_| "3,5,10".to_string());
     opts.split(',')
         .filter_map(|s| s.trim().parse::<f64>().ok())
@@ -240,7 +627,7 @@ pub fn get_order_update_interval() -> u64 {
 
 /// Returns the quote assets list.
 pub fn get_quote_assets() -> Vec<String> {
-    let _ = from_filename("vars.env");
+    load_env_file();
     env::var("QUOTE_ASSETS")
         .unwrap_or_else(|_| "USDC".to_string())
         .split(',')
@@ -260,7 +647,7 @@ pub fn get_stop_loss_loop_seconds() -> u64 {
 
 /// Returns a list of assets excluded from spot trading.
 pub fn get_excluded_assets_spot() -> Vec<String> {
-    let _ = from_filename("vars.env");
+    load_env_file();
     env::var("EXCLUDED_ASSETS_SPOT")
         .unwrap_or_else(|_| "".to_string())
         .split(',')
@@ -271,7 +658,7 @@ pub fn get_excluded_assets_spot() -> Vec<String> {
 
 /// Returns a list of assets excluded from spot trading.s
 pub fn get_excluded_tokens() -> Vec<String> {
-    let _ = from_filename("vars.env");
+    load_env_file();
     env::var("EXCLUDED_TOKENS")
         .unwrap_or_else(|_| "".to_string())
         .split(',')
@@ -280,6 +667,11 @@ pub fn get_excluded_tokens() -> Vec<String> {
         .collect()
 }
 
+/// Returns base assets (e.g. LUNA) never traded against any quote asset.
+pub fn get_excluded_base_assets() -> Vec<String> {
+    SHARED_CONFIG.read().unwrap().excluded_base_assets.clone()
+}
+
 /// Returns the minimum 24h USD volume required for an asset.
 pub fn get_min_volume() -> u64 {
     SHARED_CONFIG.read().unwrap().min_volume
@@ -294,10 +686,22 @@ pub fn get_stop_loss_percent_profit_10() -> f64 {
     SHARED_CONFIG.read().unwrap().stop_loss_percent_profit_10
 }
 
+/// Set by `set_trade_log_folder`; wins over TRADE_LOG_FOLDER and survives config reloads.
+static TRADE_LOG_FOLDER_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Returns the trade log folder: the `--output-dir` override if set, else TRADE_LOG_FOLDER.
 pub fn get_trade_log_folder() -> String {
+    if let Some(folder) = TRADE_LOG_FOLDER_OVERRIDE.read().unwrap().as_ref() {
+        return folder.clone();
+    }
     SHARED_CONFIG.read().unwrap().trade_log_folder.clone()
 }
 
+/// Redirects trade logs to `folder` regardless of TRADE_LOG_FOLDER (e.g. `--output-dir`, tests).
+pub fn set_trade_log_folder(folder: &str) {
+    *TRADE_LOG_FOLDER_OVERRIDE.write().unwrap() = Some(folder.to_string());
+}
+
 pub fn get_log_folder() -> String {
     SHARED_CONFIG.read().unwrap().log_folder.clone()
 }
@@ -306,14 +710,281 @@ pub fn get_log_file() -> String {
     SHARED_CONFIG.read().unwrap().log_file.clone()
 }
 
+/// Returns the maximum expected quote balance; 0 disables the safe-mode check.
+pub fn get_max_account_value() -> f64 {
+    SHARED_CONFIG.read().unwrap().max_account_value
+}
+
+/// Returns the widest best bid/ask spread (%) allowed for a buy; 0 disables the filter.
+pub fn get_max_spread_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().max_spread_pct
+}
+
+/// Returns the console verbosity (Quiet | Normal | Verbose).
+pub fn get_verbosity() -> Verbosity {
+    SHARED_CONFIG.read().unwrap().verbosity
+}
+
+/// Returns true when the stop-loss manager should follow the WebSocket trade stream.
+pub fn get_stop_loss_stream() -> bool {
+    SHARED_CONFIG.read().unwrap().stop_loss_stream
+}
+
+/// Returns the largest notional (in quote) a single position may reach; 0 disables trimming.
+pub fn get_max_position_quote() -> f64 {
+    SHARED_CONFIG.read().unwrap().max_position_quote
+}
+
+/// Returns the IANA timezone name used to bucket reports (e.g. "Europe/Lisbon").
+pub fn get_report_timezone() -> String {
+    SHARED_CONFIG.read().unwrap().report_timezone.clone()
+}
+
+/// Returns true when the initial stop-loss is anchored to the entry price instead of the market price.
+pub fn get_initial_stop_from_entry() -> bool {
+    SHARED_CONFIG.read().unwrap().initial_stop_from_entry
+}
+
+/// Returns true when signals are evaluated on closed candles only, ignoring the one still forming.
+pub fn get_closed_candles_only() -> bool {
+    SHARED_CONFIG.read().unwrap().closed_candles_only
+}
+
+/// Returns the scale-out levels as (gain %, fraction of the position), lowest gain first.
+pub fn get_take_profit_levels() -> Vec<(f64, f64)> {
+    SHARED_CONFIG.read().unwrap().take_profit_levels.clone()
+}
+
+/// Returns the minimum move in 24h change (percentage points) before a symbol's klines are re-fetched; 0 disables.
+pub fn get_scan_change_delta() -> f64 {
+    SHARED_CONFIG.read().unwrap().scan_change_delta
+}
+
+/// Returns the RSI period used as an entry filter; 0 disables it.
+pub fn get_rsi_period() -> u16 {
+    SHARED_CONFIG.read().unwrap().rsi_period
+}
+
+/// Returns the RSI above which a symbol counts as overbought and is skipped.
+pub fn get_rsi_max() -> f64 {
+    SHARED_CONFIG.read().unwrap().rsi_max
+}
+
+/// Returns true when trailing stops are sent to the futures API; otherwise spot uses STOP_LOSS_LIMIT.
+pub fn get_futures_trailing_stops() -> bool {
+    SHARED_CONFIG.read().unwrap().futures_trailing_stops
+}
+
+/// Returns the profit % above entry at which trailing stops activate; 0 arms them at entry.
+pub fn get_trailing_activation_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().trailing_activation_pct
+}
+
+/// Returns the pause in milliseconds between consecutive buys in one market cycle; 0 disables it.
+pub fn get_buy_spacing_ms() -> u64 {
+    SHARED_CONFIG.read().unwrap().buy_spacing_ms
+}
+
+/// Returns the TCP connect timeout for Binance requests in milliseconds; 0 disables it.
+pub fn get_http_connect_timeout_ms() -> u64 {
+    SHARED_CONFIG.read().unwrap().http_connect_timeout_ms
+}
+
+/// Returns the total timeout for one Binance request in milliseconds; 0 disables it.
+pub fn get_http_request_timeout_ms() -> u64 {
+    SHARED_CONFIG.read().unwrap().http_request_timeout_ms
+}
+
+/// Returns how many of the latest candles must be strong green for a positive signal.
+pub fn get_require_n_green() -> u32 {
+    SHARED_CONFIG.read().unwrap().require_n_green
+}
+
+/// Returns the minimum open-to-close gain (%) of a confirming green candle.
+pub fn get_green_candle_min_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().green_candle_min_pct
+}
+
+/// Returns the RSI above which a held position is partially sold; 0 disables it.
+pub fn get_rsi_exit_threshold() -> f64 {
+    SHARED_CONFIG.read().unwrap().rsi_exit_threshold
+}
+
+/// Returns the fraction of a position sold on an overbought RSI reading.
+pub fn get_rsi_exit_fraction() -> f64 {
+    SHARED_CONFIG.read().unwrap().rsi_exit_fraction
+}
+
+/// Returns how many candidates (by 24h quote volume) a scan evaluates; 0 means all.
+pub fn get_max_candidates_per_scan() -> usize {
+    SHARED_CONFIG.read().unwrap().max_candidates_per_scan
+}
+
+/// Returns how many symbols a stop-loss cycle processes at once (at least 1).
+pub fn get_stop_loss_concurrency() -> usize {
+    SHARED_CONFIG.read().unwrap().stop_loss_concurrency
+}
+
+/// Returns after how many consecutive failed stop placements a holding is reported as unprotected (0 = never).
+pub fn get_unprotected_alert_after() -> u32 {
+    SHARED_CONFIG.read().unwrap().unprotected_alert_after
+}
+
+/// Returns the minimum days since listing for a symbol to be scanned (0 = no minimum).
+pub fn get_min_symbol_age_days() -> u32 {
+    SHARED_CONFIG.read().unwrap().min_symbol_age_days
+}
+
+/// Returns the most quote the bot may spend on buys per UTC day (0 = unlimited).
+pub fn get_max_daily_spend() -> f64 {
+    SHARED_CONFIG.read().unwrap().max_daily_spend
+}
+
+/// Returns how many candles the signal's recent-growth check spans (defaults to `LAST_HOURS_PERIOD`).
+pub fn get_signal_recent_window() -> u16 {
+    SHARED_CONFIG.read().unwrap().signal_recent_window
+}
+
+/// Returns the tight trail (%) used once a position is in profit; the stop then never drops below entry (0 = off).
+pub fn get_profit_lock_trail_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().profit_lock_trail_pct
+}
+
+/// Returns whether buys go to the quote asset with the most affordable buys left first.
+pub fn get_quote_rotation() -> bool {
+    SHARED_CONFIG.read().unwrap().quote_rotation
+}
+
+/// Returns the minimum distance (%) between price and a loss-side stop (0 = no floor).
+pub fn get_min_stop_distance_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().min_stop_distance_pct
+}
+
+/// Returns the trailing stop distance (%) the profit curve starts from.
+pub fn get_stop_curve_base_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().stop_curve_base_pct
+}
+
+/// Returns how fast the trailing distance shrinks per % of gain (0 = use the profit tiers).
+pub fn get_stop_curve_k() -> f64 {
+    SHARED_CONFIG.read().unwrap().stop_curve_k
+}
+
+/// Returns how far (%) below the stop trigger the limit of a STOP_LOSS_LIMIT order sits.
+pub fn get_stop_limit_buffer_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().stop_limit_buffer_pct
+}
+
+/// Returns the callback rate (%) trailing stops trail the high by.
+pub fn get_trailing_callback_pct() -> f64 {
+    SHARED_CONFIG.read().unwrap().trailing_callback_pct
+}
+
+/// Returns the lowest futures funding rate (%) a long entry tolerates, if the gate is on.
+pub fn get_min_funding_rate_pct() -> Option<f64> {
+    SHARED_CONFIG.read().unwrap().min_funding_rate_pct
+}
+
+/// Returns the decimals numeric trade log fields are rounded to (0 = full precision).
+pub fn get_trade_log_decimals() -> u32 {
+    SHARED_CONFIG.read().unwrap().trade_log_decimals
+}
+
+/// Returns the minimum body-to-range ratio of a confirming green candle (0 = off).
+pub fn get_green_candle_min_body_ratio() -> f64 {
+    SHARED_CONFIG.read().unwrap().green_candle_min_body_ratio
+}
+
+/// Returns true when neg_pred may open futures shorts; spot accounts cannot short.
+pub fn get_enable_shorts() -> bool {
+    SHARED_CONFIG.read().unwrap().enable_shorts
+}
+
+/// Returns the forced price decimals for `symbol` from QUOTE_PRECISION_OVERRIDES, if any.
+pub fn get_quote_precision(symbol: &str) -> Option<u32> {
+    SHARED_CONFIG.read().unwrap().quote_precision_overrides.get(symbol).copied()
+}
+
+/// Parses `TAKE_PROFIT_LEVELS` ("5:0.5,10:0.25" = sell 50% at +5%, 25% at +10%).
+/// Invalid entries are dropped, and so are levels that would leave nothing for the stop-loss.
+pub fn parse_take_profit_levels(raw: &str) -> Vec<(f64, f64)> {
+    let mut levels: Vec<(f64, f64)> = raw
+        .split(',')
+        .filter_map(|entry| {
+            let (pct, fraction) = entry.split_once(':')?;
+            let pct = pct.trim().parse::<f64>().ok()?;
+            let fraction = fraction.trim().parse::<f64>().ok()?;
+            (pct > 0.0 && fraction > 0.0).then_some((pct, fraction))
+        })
+        .collect();
+    levels.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut total = 0.0;
+    levels.retain(|(pct, fraction)| {
+        if total + fraction >= 1.0 {
+            warn!("⚠️ Ignoring take-profit level {}%: fractions must leave a remainder for the stop-loss", pct);
+            return false;
+        }
+        total += fraction;
+        true
+    });
+    levels
+}
+
+/// Parses `QUOTE_PRECISION_OVERRIDES` ("XYZUSDT:2,ABCBTC:8" = symbol:price decimals).
+/// Symbols are uppercased; invalid entries are dropped with a warning.
+pub fn parse_quote_precision_overrides(raw: &str) -> HashMap<String, u32> {
+    raw.split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| {
+            let parsed = entry
+                .split_once(':')
+                .and_then(|(symbol, decimals)| Some((symbol.trim().to_uppercase(), decimals.trim().parse::<u32>().ok()?)))
+                .filter(|(symbol, _)| !symbol.is_empty());
+            if parsed.is_none() {
+                warn!("⚠️ Ignoring QUOTE_PRECISION_OVERRIDES entry '{}': expected SYMBOL:decimals", entry.trim());
+            }
+            parsed
+        })
+        .collect()
+}
+
 pub fn is_trading_day() -> bool {
     let today = Local::now().weekday().to_string().to_ascii_lowercase();
     !SHARED_CONFIG.read().unwrap().excluded_days.contains(&today)
 }
 
+/// True when `event` changed, created or renamed something onto `file_name`; access events and
+/// other files in the watched directory are ignored.
+pub fn is_config_event(event: &notify::Event, file_name: &str) -> bool {
+    let touches_file = event
+        .paths
+        .iter()
+        .any(|p| p.file_name().is_some_and(|n| n == file_name));
+    touches_file && !matches!(event.kind, notify::EventKind::Access(_))
+}
+
 /// Spawns a file watcher that monitors "vars.env" for changes and reloads the configuration.
 pub fn watch_config(shared_config: SharedConfig) {
-    let config_file = "vars.env";
+    watch_config_file(shared_config, Path::new("vars.env"));
+}
+
+/// Watches the env file at `config_path` and reloads `shared_config` from it on every change.
+/// The parent directory is watched rather than the file, because editors that save by renaming
+/// a temp file over it replace the inode a file watch would hold.
+pub fn watch_config_file(shared_config: SharedConfig, config_path: &Path) {
+    if !config_path.exists() {
+        println!("No {} found; using process environment only (no hot reload).", config_path.display());
+        return;
+    }
+    let Some(config_file) = config_path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return;
+    };
+    let config_path = config_path.to_path_buf();
+    let watch_dir = match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
     let (tx, rx) = std::sync::mpsc::channel();
 
     let mut watcher: notify::RecommendedWatcher =
@@ -321,19 +992,19 @@ pub fn watch_config(shared_config: SharedConfig) {
             .expect("Failed to create watcher");
 
     watcher
-        .watch(std::path::Path::new(config_file), notify::RecursiveMode::NonRecursive)
-        .expect("Failed to watch config file");
+        .watch(&watch_dir, notify::RecursiveMode::NonRecursive)
+        .expect("Failed to watch config directory");
 
     // Spawn a thread to listen for file changes.
     std::thread::spawn(move || {
         // Bind the watcher to a variable so it remains in scope.
         let _watcher = watcher;
-        loop {
-            match rx.recv() {
-                Ok(event) => {
+        for result in rx {
+            match result {
+                Ok(event) if is_config_event(&event, &config_file) => {
                     println!("Configuration file changed. Reloading... Event: {:?}", event);
                     // Reload the configuration.
-                    let new_config = Config::load();
+                    let new_config = Config::reload_from(&config_path);
                     if let Ok(mut config) = shared_config.write() {
                         *config = new_config;
                         //println!("New configuration: {:?}", *config);
@@ -341,11 +1012,154 @@ pub fn watch_config(shared_config: SharedConfig) {
                     // Throttle rapid events.
                     std::thread::sleep(std::time::Duration::from_millis(200));
                 }
+                Ok(_) => {}
                 Err(e) => {
                     println!("Config watch error: {:?}", e);
-                    // Optionally, you can break the loop or continue.
                 }
             }
         }
     });
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_quote_without_an_amount_falls_back_with_a_named_warning() {
+        let mut config = Config::from_env();
+        config.quote_assets = vec!["USDT".to_string(), "FDUSD".to_string()];
+        config.transaction_amounts = vec![25.0];
+        config.default_transaction_amount = 7.0;
+
+        assert_eq!(config.configured_transaction_amount("USDT"), Ok(25.0));
+        let warning = config.configured_transaction_amount("FDUSD").unwrap_err();
+        assert!(warning.contains("FDUSD") && warning.contains('7'), "{}", warning);
+        assert_eq!(config.transaction_amount_for("FDUSD"), 7.0);
+    }
+
+    #[test]
+    fn from_env_builds_the_config_from_process_variables_alone() {
+        // MIN_VOLUME_USD is not in vars.env, so only the process environment can set it.
+        env::set_var("MIN_VOLUME_USD", "600000");
+        let config = Config::from_env();
+        env::remove_var("MIN_VOLUME_USD");
+        assert_eq!(config.min_volume, 600000);
+        assert_eq!(Config::from_env().min_volume, 500000);
+    }
+
+    #[test]
+    fn signal_recent_window_sets_the_recent_growth_slice() {
+        use crate::trading::discovery::explain;
+        use crate::types::{Kline, SignalParams, TrendDirection};
+
+        let defaulted = Config::from_env();
+        assert_eq!(defaulted.signal_recent_window, defaulted.last_hours_period);
+        env::set_var("SIGNAL_RECENT_WINDOW", "2");
+        let config = Config::from_env();
+        env::remove_var("SIGNAL_RECENT_WINDOW");
+        assert_eq!(config.signal_recent_window, 2);
+
+        // Ten candles sliding down to 82, then two that each close 10% up.
+        let closes = (0..10).map(|i| 100.0 - 2.0 * i as f64).chain([90.2, 99.22]).collect::<Vec<f64>>();
+        let candles: Vec<Kline> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| {
+                let open = if i == 0 { 100.0 } else { closes[i - 1] };
+                Kline { open_time: i as i64, open, high: close.max(open), low: close.min(open), close, volume: 1.0, close_time: i as i64 + 1 }
+            })
+            .collect();
+        let recent_growth = |recent: u16| {
+            let params = SignalParams { lookback: 12, recent: recent as u32, green_candles: 0, green_min_pct: 0.0, green_min_body_ratio: 0.0 };
+            explain(&candles, params, TrendDirection::Positive).unwrap().recent_growth
+        };
+        assert!((recent_growth(config.signal_recent_window) - 21.0).abs() < 1e-9);
+        // Six candles reach back to the close at 90, before the bounce.
+        assert!((recent_growth(6) - 10.244444444444444).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_atomically_replaced_config_is_still_seen_by_the_directory_watch() {
+        use notify::Watcher;
+
+        let dir = env::temp_dir().join(format!("stock_pred_watch_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("vars.env");
+        std::fs::write(&config_path, "LOOP_TIME_SECONDS=60\n").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = notify::RecommendedWatcher::new(tx, notify::Config::default()).unwrap();
+        watcher.watch(&dir, notify::RecursiveMode::NonRecursive).unwrap();
+
+        // Save the way vim and VS Code do, twice: the second save must still be seen.
+        for (round, contents) in ["LOOP_TIME_SECONDS=30\n", "LOOP_TIME_SECONDS=15\n"].iter().enumerate() {
+            let temp = dir.join(format!(".vars.env.swp{}", round));
+            std::fs::write(&temp, contents).unwrap();
+            std::fs::rename(&temp, &config_path).unwrap();
+
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            let mut reloaded = false;
+            while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+                match rx.recv_timeout(left) {
+                    Ok(Ok(event)) if is_config_event(&event, "vars.env") => {
+                        reloaded = true;
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+            assert!(reloaded, "no reload event for save {}", round + 1);
+            // Let the rest of this save's events arrive so they cannot stand in for the next one.
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            while rx.try_recv().is_ok() {}
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_unwritable_trade_log_folder_is_reported_by_name() {
+        // A folder below a regular file can never be created, even when running as root.
+        let blocker = env::temp_dir().join(format!("stock_pred_not_a_dir_{}", std::process::id()));
+        std::fs::write(&blocker, "").unwrap();
+        let mut config = Config::from_env();
+        config.trade_log_folder = blocker.join("trades").to_string_lossy().to_string();
+        config.log_folder = env::temp_dir().to_string_lossy().to_string();
+
+        let problems = config.validate();
+        std::fs::remove_file(&blocker).unwrap();
+        let folder_problems: Vec<&String> = problems.iter().filter(|p| p.contains("FOLDER") || p.contains("FOlDER")).collect();
+        assert_eq!(folder_problems.len(), 1, "{:?}", problems);
+        assert!(folder_problems[0].starts_with("TRADE_LOG_FOLDER"), "{}", folder_problems[0]);
+        assert!(folder_problems[0].contains("not a directory"), "{}", folder_problems[0]);
+        assert!(check_writable_dir(&env::temp_dir()).is_ok());
+    }
+
+    #[test]
+    fn an_edited_config_file_is_picked_up_by_the_watcher() {
+        let dir = std::env::temp_dir().join(format!("stock_pred_watch_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("watched.env");
+        std::fs::write(&path, "LOOP_TIME_SECONDS=111\n").unwrap();
+        let previous = env::var("LOOP_TIME_SECONDS").ok();
+        // A config of its own, so reloading does not clobber what other tests set on SHARED_CONFIG.
+        let shared: SharedConfig = Arc::new(RwLock::new(Config::reload_from(&path)));
+        assert_eq!(shared.read().unwrap().loop_time_seconds, 111);
+
+        watch_config_file(Arc::clone(&shared), &path);
+        std::fs::write(&path, "LOOP_TIME_SECONDS=222\n").unwrap();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while shared.read().unwrap().loop_time_seconds != 222 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let reloaded = shared.read().unwrap().loop_time_seconds;
+
+        match previous {
+            Some(value) => env::set_var("LOOP_TIME_SECONDS", value),
+            None => env::remove_var("LOOP_TIME_SECONDS"),
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reloaded, 222);
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::Notify;
+use tracing::{error, info};
+use crate::api::binance::Binance;
+
+/// A line sent to a running bot over stdin or the control socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Stop opening new trades; stops on existing holdings keep being managed.
+    Pause,
+    Resume,
+    /// Run the next market scan now instead of waiting out the loop sleep.
+    Scan,
+    /// Cancel the symbol's orders and market-sell its balance.
+    Liquidate(String),
+    Status,
+}
+
+impl Command {
+    /// Parses one line such as `pause` or `liquidate BTCUSDT`. Case-insensitive.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default().to_lowercase();
+        let arg = words.next();
+        if words.next().is_some() {
+            return Err(format!("too many arguments in '{}'", line.trim()));
+        }
+        match (name.as_str(), arg) {
+            ("pause", None) => Ok(Command::Pause),
+            ("resume", None) => Ok(Command::Resume),
+            ("scan", None) => Ok(Command::Scan),
+            ("status", None) => Ok(Command::Status),
+            ("liquidate", Some(symbol)) => Ok(Command::Liquidate(symbol.to_uppercase())),
+            ("liquidate", None) => Err("usage: liquidate <SYMBOL>".to_string()),
+            ("", _) => Err("empty command".to_string()),
+            _ => Err(format!("unknown command '{}' (pause, resume, scan, liquidate <SYMBOL>, status)", line.trim())),
+        }
+    }
+}
+
+/// State the command interface shares with the market loop.
+#[derive(Debug, Default)]
+pub struct Control {
+    paused: AtomicBool,
+    scan: Notify,
+}
+
+impl Control {
+    pub fn new() -> Self {
+        Control::default()
+    }
+
+    /// True while `pause` is in effect; the market loop opens no new trades.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Resolves when a `scan` command arrives. A request made while nobody waits is kept for
+    /// the next wait, so a scan asked for mid-cycle still runs.
+    pub async fn scan_requested(&self) {
+        self.scan.notified().await
+    }
+
+    /// Applies the commands that only touch shared state and returns the reply line.
+    /// `Liquidate` needs the exchange and returns None; see `handle_command`.
+    pub fn apply(&self, command: &Command) -> Option<String> {
+        match command {
+            Command::Pause => {
+                self.paused.store(true, Ordering::SeqCst);
+                Some("paused: no new trades until 'resume'".to_string())
+            }
+            Command::Resume => {
+                self.paused.store(false, Ordering::SeqCst);
+                Some("resumed".to_string())
+            }
+            Command::Scan => {
+                self.scan.notify_one();
+                Some("scan requested".to_string())
+            }
+            Command::Status => Some(if self.is_paused() { "paused" } else { "running" }.to_string()),
+            Command::Liquidate(_) => None,
+        }
+    }
+}
+
+/// Parses and runs one command line, returning the reply to send back.
+pub async fn handle_command(control: &Control, binance: &Binance, line: &str) -> String {
+    let command = match Command::parse(line) {
+        Ok(command) => command,
+        Err(e) => return format!("error: {}", e),
+    };
+    println!("🎛 Control command: {:?}", command);
+    info!("🎛 Control command: {:?}", command);
+    if let Some(reply) = control.apply(&command) {
+        return reply;
+    }
+    match command {
+        Command::Liquidate(symbol) => match binance.liquidate(&symbol).await {
+            Ok(quantity) => format!("liquidated {} {}", quantity, symbol),
+            Err(e) => format!("error: liquidating {} failed: {}", symbol, e),
+        },
+        _ => unreachable!("state-only commands are handled by Control::apply"),
+    }
+}
+
+/// Answers every line read from `reader` on `writer` until the reader closes.
+async fn serve<R, W>(control: &Control, binance: &Binance, reader: R, mut writer: W)
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => return,
+            Err(e) => {
+                error!("Control input read failed: {}", e);
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = handle_command(control, binance, &line).await;
+        if writer.write_all(format!("{}\n", reply).as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads commands from stdin, one per line, replying on stdout.
+pub async fn run_stdin(control: Arc<Control>, binance: Arc<Binance>) {
+    serve(&control, &binance, tokio::io::stdin(), tokio::io::stdout()).await;
+}
+
+/// Accepts line-based command connections on a Unix socket at `path` (e.g. via `nc -U`).
+/// A stale socket file left by a previous run is replaced.
+pub async fn run_unix_socket(path: &Path, control: Arc<Control>, binance: Arc<Binance>) {
+    let _ = std::fs::remove_file(path);
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!("❌ Could not open control socket {}: {}", path.display(), e);
+            error!("❌ Could not open control socket {}: {}", path.display(), e);
+            return;
+        }
+    };
+    println!("🎛 Listening for control commands on {}", path.display());
+    info!("🎛 Listening for control commands on {}", path.display());
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let (control, binance) = (Arc::clone(&control), Arc::clone(&binance));
+                tokio::spawn(async move {
+                    let (reader, writer) = stream.into_split();
+                    serve(&control, &binance, reader, writer).await;
+                });
+            }
+            Err(e) => error!("Control socket accept failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pause_halts_new_buys_until_resume() {
+        let (control, binance) = (Control::new(), Binance::with_credentials("key", "secret"));
+        let mut replies = Vec::new();
+        serve(&control, &binance, &b"pause\nstatus\n"[..], &mut replies).await;
+        assert!(control.is_paused());
+
+        serve(&control, &binance, &b"\nRESUME\nstatus\nliquidate\npause right now\n"[..], &mut replies).await;
+        assert!(!control.is_paused());
+        let replies = String::from_utf8(replies).unwrap();
+        let replies: Vec<&str> = replies.lines().collect();
+        assert_eq!(replies[..4], ["paused: no new trades until 'resume'", "paused", "resumed", "running"]);
+        assert_eq!(replies[4], "error: usage: liquidate <SYMBOL>");
+        assert!(replies[5].starts_with("error: too many arguments"), "{}", replies[5]);
+    }
+
+    #[tokio::test]
+    async fn a_scan_command_wakes_the_waiting_loop() {
+        let control = Control::new();
+        assert_eq!(control.apply(&Command::Scan).as_deref(), Some("scan requested"));
+        // Requested before anyone waited: the next wait returns at once.
+        tokio::time::timeout(std::time::Duration::from_secs(1), control.scan_requested()).await.unwrap();
+        assert_eq!(Command::parse("liquidate btcusdt"), Ok(Command::Liquidate("BTCUSDT".to_string())));
+    }
+}
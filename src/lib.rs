@@ -1,5 +1,7 @@
 pub mod api;
 pub mod config;
+pub mod control;
+pub mod math;
 pub mod trading;
 pub mod logging;
 pub mod types;
\ No newline at end of file
@@ -1,4 +1,3 @@
-use std::env;
 use tracing_subscriber;
 use std::fs::{OpenOptions, create_dir_all};
 use std::io::Write;
@@ -6,44 +5,200 @@ use crate::config::*;
 use chrono::{Duration, Utc};
 use std::time::UNIX_EPOCH;
 use std::fs;
+use crate::types::TRADE_IDS;
+use once_cell::sync::Lazy;
+use std::sync::mpsc;
 
 
+/// `println!` that is silenced when VERBOSITY=quiet. Use for routine status lines, not trades.
+#[macro_export]
+macro_rules! status_println {
+    ($($arg:tt)*) => {
+        if $crate::config::get_verbosity() >= $crate::config::Verbosity::Normal {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// `println!` that only prints when VERBOSITY=verbose.
+#[macro_export]
+macro_rules! verbose_println {
+    ($($arg:tt)*) => {
+        if $crate::config::get_verbosity() >= $crate::config::Verbosity::Verbose {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Formats a trade log number: the shortest exact form when `decimals` is 0, so BTC prices and
+/// tiny quantities read back unchanged, otherwise rounded to `decimals` places.
+pub fn format_log_number(value: f64, decimals: u32) -> String {
+    if decimals == 0 {
+        format!("{}", value)
+    } else {
+        format!("{:.*}", decimals as usize, value)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn log_trade_event(symbol: &str,action: &str,price: f64,qty: f64, quote: f64, stop_loss: f64, reason: &str, trend: &str,) {
     let timestamp = Utc::now().to_rfc3339();
     let date = Utc::now().format("%Y-%m-%d").to_string();
 
-    // Read folder path from env
-    let folder = env::var("TRADE_LOG_FOLDER").unwrap_or_else(|_| "logs/trades".to_string());
+    // Read the folder from the shared config so reloads and overrides apply to the next event.
+    let folder = get_trade_log_folder();
     let path = format!("{}/{}.csv", folder, date);
 
     //let mode = get_trading_mode().await;
+    let decimals = get_trade_log_decimals();
+    let num = |value: f64| format_log_number(value, decimals);
     let row = format!(
-        "{},{},{:.4},{:.4},{:.4},{:.4},{},{},{}\n",
-        timestamp, symbol, action, price, qty, quote, stop_loss, reason, trend
+        "{},{},{},{},{},{},{},{},{}\n",
+        timestamp, symbol, action, num(price), num(qty), num(quote), num(stop_loss), reason, trend
     );
 
+    let _ = TRADE_LOG_WRITER.send(TradeLogMessage::Row { path, header: Some(TRADE_LOG_HEADER), row });
+}
+
+const TRADE_LOG_HEADER: &str = "timestamp,symbol,action,price,qty,quote,stop_loss,reason,trend";
+
+enum TradeLogMessage {
+    /// `row` appended to `path`, preceded by `header` when the file is new.
+    Row { path: String, header: Option<&'static str>, row: String },
+    Flush(mpsc::Sender<()>),
+}
+
+/// One thread owns every log row write (trades, market breadth, equity, order audit), so concurrent writers
+/// cannot interleave rows or race on the header of a new file, and rows land in the order
+/// they were logged.
+static TRADE_LOG_WRITER: Lazy<mpsc::Sender<TradeLogMessage>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<TradeLogMessage>();
     std::thread::spawn(move || {
-        if let Err(e) = create_dir_all(&folder) {
+        for message in rx {
+            match message {
+                TradeLogMessage::Row { path, header, row } => write_log_row(&path, header, &row),
+                TradeLogMessage::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    tx
+});
+
+fn write_log_row(path: &str, header: Option<&str>, row: &str) {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        if let Err(e) = create_dir_all(dir) {
             eprintln!("❌ Failed to create log dir: {}", e);
             return;
         }
+    }
 
-        let new_file = !std::path::Path::new(&path).exists();
+    let new_file = !std::path::Path::new(path).exists();
 
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
-            if new_file {
-                let _ = writeln!(file, "timestamp,symbol,action,price,qty,quote,stop_loss,reason,trend");
-            }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        if let Some(header) = header.filter(|_| new_file) {
+            let _ = writeln!(file, "{}", header);
+        }
 
-            if let Err(e) = file.write_all(row.as_bytes()) {
-                eprintln!("❌ Failed to write log row: {}", e);
-            } else if let Err(e) = file.flush() {
-                eprintln!("❌ Failed to flush log file: {}", e);
-            }
-        } else {
-            eprintln!("❌ Could not open log file: {}", path);
+        if let Err(e) = file.write_all(row.as_bytes()) {
+            eprintln!("❌ Failed to write log row: {}", e);
+        } else if let Err(e) = file.flush() {
+            eprintln!("❌ Failed to flush log file: {}", e);
         }
-    });
+    } else {
+        eprintln!("❌ Could not open log file: {}", path);
+    }
+}
+
+/// Blocks until every row logged so far is on disk. Call before exiting.
+pub fn flush_trade_log() {
+    let (done_tx, done_rx) = mpsc::channel();
+    if TRADE_LOG_WRITER.send(TradeLogMessage::Flush(done_tx)).is_ok() {
+        let _ = done_rx.recv();
+    }
+}
+
+/// Market breadth history, in a subfolder so trade log scans don't pick it up.
+pub fn breadth_log_path() -> String {
+    format!("{}/regime/breadth.csv", get_trade_log_folder())
+}
+
+/// Appends one scan's breadth (share of green tickers, 0..1) and the resulting MARKET_TREND.
+pub fn log_market_breadth(breadth: f64, trend: &str) {
+    let path = breadth_log_path();
+    let row = format!("{},{:.4},{}\n", Utc::now().to_rfc3339(), breadth, trend);
+    let _ = TRADE_LOG_WRITER.send(TradeLogMessage::Row { path, header: Some("timestamp,breadth,trend"), row });
+}
+
+/// Daily account equity history, in a subfolder so trade log scans don't pick it up.
+pub fn equity_log_path() -> String {
+    format!("{}/equity/equity.csv", get_trade_log_folder())
+}
+
+/// True when the equity log at `path` has no snapshot for `today` (YYYY-MM-DD) yet.
+pub fn equity_snapshot_due(path: &str, today: &str) -> bool {
+    let log = fs::read_to_string(path).unwrap_or_default();
+    log.lines().skip(1).last().is_none_or(|row| !row.starts_with(today))
+}
+
+/// Appends one account equity snapshot (valued in USDT) to the equity log.
+pub fn log_equity_snapshot(equity: f64) {
+    let path = equity_log_path();
+    let row = format!("{},{:.2}\n", Utc::now().to_rfc3339(), equity);
+    let _ = TRADE_LOG_WRITER.send(TradeLogMessage::Row { path, header: Some("timestamp,equity_usdt"), row });
+}
+
+/// Append-only JSONL of raw order responses (ids, fills, commissions) for reconciliation.
+pub fn order_audit_path() -> String {
+    format!("{}/audit/orders.jsonl", get_trade_log_folder())
+}
+
+/// Appends one raw order `response` to the audit log, unrounded, with the time it was logged.
+pub fn log_order_audit(response: &serde_json::Value) {
+    let path = order_audit_path();
+    let line = serde_json::json!({
+        "logged_at": Utc::now().to_rfc3339(),
+        "response": response,
+    })
+    .to_string();
+    let _ = TRADE_LOG_WRITER.send(TradeLogMessage::Row { path, header: None, row: format!("{}\n", line) });
+}
+
+/// Symbols with at least one row in today's trade log.
+pub fn traded_symbols_today() -> Vec<String> {
+    let folder = get_trade_log_folder();
+    let path = format!("{}/{}.csv", folder, Utc::now().format("%Y-%m-%d"));
+    let mut symbols: Vec<String> = fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split(',').nth(1).map(str::to_string))
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    symbols
+}
+
+/// Id tying together the log lines of one position, e.g. `BTCUSDC-1760000000000`.
+pub fn new_trade_id(symbol: &str, opened_ms: i64) -> String {
+    format!("{}-{}", symbol, opened_ms)
+}
+
+/// Trade id of the open position in `symbol`. Holdings the bot did not buy in this run
+/// (e.g. before a restart) get a fresh id the first time they are seen.
+pub async fn trade_id_for(symbol: &str) -> String {
+    TRADE_IDS
+        .lock()
+        .await
+        .entry(symbol.to_string())
+        .or_insert_with(|| new_trade_id(symbol, Utc::now().timestamp_millis()))
+        .clone()
+}
+
+/// Span carrying `symbol` and `trade_id`; every tracing event emitted inside it shares both fields.
+pub fn trade_span(symbol: &str, trade_id: &str) -> tracing::Span {
+    tracing::info_span!("trade", symbol = %symbol, trade_id = %trade_id)
 }
 
 /// Initialize tracing
@@ -104,4 +259,216 @@ pub fn cleanup_old_logs(folder: &str) {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_thread_adds_the_header_once_per_new_file() {
+        let path = std::env::temp_dir().join(format!("stock_pred_breadth_{}", std::process::id())).join("regime/breadth.csv");
+        let path = path.to_string_lossy().to_string();
+        for row in ["t1,0.6000,bullish\n", "t2,0.4000,bearish\n"] {
+            TRADE_LOG_WRITER
+                .send(TradeLogMessage::Row { path: path.clone(), header: Some("timestamp,breadth,trend"), row: row.to_string() })
+                .unwrap();
+        }
+        flush_trade_log();
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written, "timestamp,breadth,trend\nt1,0.6000,bullish\nt2,0.4000,bearish\n");
+        let _ = fs::remove_dir_all(std::path::Path::new(&path).parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn audit_rows_are_written_without_a_header() {
+        let dir = std::env::temp_dir().join(format!("stock_pred_audit_{}", std::process::id()));
+        let path = dir.join("orders.jsonl").to_string_lossy().to_string();
+        write_log_row(&path, None, "{\"orderId\":1}\n");
+        write_log_row(&path, None, "{\"orderId\":2}\n");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"orderId\":1}\n{\"orderId\":2}\n");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    /// Serializes the tests that point the shared config's trade log folder somewhere else.
+    static LOG_FOLDER_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Points TRADE_LOG_FOLDER at `folder` the way a config reload does; returns the old folder.
+    fn set_trade_log_folder(folder: &str) -> String {
+        std::mem::replace(&mut crate::config::SHARED_CONFIG.write().unwrap().trade_log_folder, folder.to_string())
+    }
+
+    #[tokio::test]
+    async fn trade_events_follow_the_configured_folder_across_reloads() {
+        let _guard = LOG_FOLDER_LOCK.lock().await;
+        let root = std::env::temp_dir().join(format!("stock_pred_log_folder_{}", std::process::id()));
+        let (first, second) = (root.join("first"), root.join("second"));
+        let file = format!("{}.csv", Utc::now().format("%Y-%m-%d"));
+
+        let original = set_trade_log_folder(&first.to_string_lossy());
+        log_trade_event("ABCUSDT", "BUY", 1.0, 2.0, 2.0, 0.9, "test", "Positive").await;
+        flush_trade_log();
+        set_trade_log_folder(&second.to_string_lossy());
+        log_trade_event("ABCUSDT", "SELL", 1.1, 2.0, 2.2, 0.0, "test", "Positive").await;
+        flush_trade_log();
+        set_trade_log_folder(&original);
+
+        let first_log = fs::read_to_string(first.join(&file)).unwrap();
+        let second_log = fs::read_to_string(second.join(&file)).unwrap();
+        let _ = fs::remove_dir_all(&root);
+        assert!(first_log.starts_with(TRADE_LOG_HEADER) && first_log.contains(",BUY,") && !first_log.contains(",SELL,"));
+        assert!(second_log.contains(",SELL,") && !second_log.contains(",BUY,"));
+    }
+
+    /// Collects formatted tracing output for assertions.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn every_event_of_a_trade_carries_its_trade_id() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_ansi(false).with_writer(move || writer.clone()).finish();
+        let (btc_id, eth_id) = (new_trade_id("BTCUSDC", 1), new_trade_id("ETHUSDC", 2));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let (btc, eth) = (trade_span("BTCUSDC", &btc_id), trade_span("ETHUSDC", &eth_id));
+            // Interleaved like two symbols handled concurrently.
+            for event in ["BUY", "SET", "SELL"] {
+                btc.in_scope(|| tracing::info!("{} BTCUSDC", event));
+                eth.in_scope(|| tracing::info!("{} ETHUSDC", event));
+            }
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 6);
+        for (symbol, trade_id) in [("BTCUSDC", &btc_id), ("ETHUSDC", &eth_id)] {
+            let events: Vec<&&str> = lines.iter().filter(|l| l.ends_with(symbol)).collect();
+            assert_eq!(events.len(), 3);
+            assert!(events.iter().all(|l| l.contains(&format!("trade_id={}", trade_id))), "{:?}", events);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn a_hundred_concurrent_trade_events_give_a_hundred_whole_rows() {
+        let _guard = LOG_FOLDER_LOCK.lock().await;
+        let folder = std::env::temp_dir().join(format!("stock_pred_concurrent_log_{}", std::process::id()));
+        let original = set_trade_log_folder(&folder.to_string_lossy());
+
+        let events: Vec<_> = (0..100)
+            .map(|i| tokio::spawn(async move { log_trade_event(&format!("C{}USDT", i), "BUY", 1.5, i as f64, 1.5 * i as f64, 1.2, "concurrency", "Positive").await }))
+            .collect();
+        for event in events {
+            event.await.unwrap();
+        }
+        flush_trade_log();
+        set_trade_log_folder(&original);
+
+        let log = fs::read_to_string(folder.join(format!("{}.csv", Utc::now().format("%Y-%m-%d")))).unwrap();
+        let _ = fs::remove_dir_all(&folder);
+        let mut lines = log.lines();
+        assert_eq!(lines.next(), Some(TRADE_LOG_HEADER));
+        let rows: Vec<Vec<&str>> = lines.map(|l| l.split(',').collect()).collect();
+        assert_eq!(rows.len(), 100);
+        assert!(rows.iter().all(|r| r.len() == 9 && r[2] == "BUY" && r[7] == "concurrency"), "{:?}", rows);
+        let mut symbols: Vec<String> = rows.iter().map(|r| r[1].to_string()).collect();
+        symbols.sort();
+        let mut expected: Vec<String> = (0..100).map(|i| format!("C{}USDT", i)).collect();
+        expected.sort();
+        assert_eq!(symbols, expected);
+    }
+
+    #[tokio::test]
+    async fn a_logged_btc_trade_reads_back_at_full_precision() {
+        let _guard = LOG_FOLDER_LOCK.lock().await;
+        let folder = std::env::temp_dir().join(format!("stock_pred_precision_log_{}", std::process::id()));
+        let original = set_trade_log_folder(&folder.to_string_lossy());
+        let (price, qty) = (67_321.57, 0.000_148_54);
+        log_trade_event("BTCUSDT", "BUY", price, qty, price * qty, 65_302.93, "precision", "Positive").await;
+        flush_trade_log();
+        set_trade_log_folder(&original);
+
+        let log = fs::read_to_string(folder.join(format!("{}.csv", Utc::now().format("%Y-%m-%d")))).unwrap();
+        let _ = fs::remove_dir_all(&folder);
+        let row: Vec<&str> = log.lines().nth(1).unwrap().split(',').collect();
+        let read_back = |i: usize| row[i].parse::<f64>().unwrap();
+        assert!((read_back(3) - price).abs() < 0.01, "{}", row[3]);
+        assert_eq!(read_back(4), qty);
+        assert_eq!(read_back(6), 65_302.93);
+
+        // The old fixed four decimals lost the tail of small quantities.
+        assert_eq!(format_log_number(qty, 4), "0.0001");
+        assert_eq!(format_log_number(price, 2), "67321.57");
+    }
+
+    /// Prints through both console macros at the verbosity named by STOCK_PRED_PRINT_AT. Run as a
+    /// child process by `quiet_verbosity_prints_nothing`; a no-op in the normal test run.
+    #[test]
+    fn print_at_requested_verbosity() {
+        let Ok(level) = std::env::var("STOCK_PRED_PRINT_AT") else { return };
+        crate::config::SHARED_CONFIG.write().unwrap().verbosity = crate::config::Verbosity::parse(&level);
+        crate::status_println!("status line printed");
+        crate::verbose_println!("verbose line printed");
+    }
+
+    /// Stdout of `print_at_requested_verbosity` run in a child process at `level`.
+    fn printed_at(level: &str) -> String {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "logging::tests::print_at_requested_verbosity", "--nocapture", "--test-threads=1"])
+            .env("STOCK_PRED_PRINT_AT", level)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn quiet_verbosity_prints_nothing() {
+        let quiet = printed_at("quiet");
+        assert!(quiet.contains("1 passed"), "{}", quiet);
+        assert!(!quiet.contains("line printed"), "{}", quiet);
+
+        let normal = printed_at("normal");
+        assert!(normal.contains("status line printed") && !normal.contains("verbose line printed"), "{}", normal);
+        let verbose = printed_at("verbose");
+        assert!(verbose.contains("status line printed") && verbose.contains("verbose line printed"), "{}", verbose);
+    }
+
+    #[tokio::test]
+    async fn equity_snapshots_go_through_the_log_writer() {
+        let _guard = LOG_FOLDER_LOCK.lock().await;
+        let folder = std::env::temp_dir().join(format!("stock_pred_equity_log_{}", std::process::id()));
+        let original = set_trade_log_folder(&folder.to_string_lossy());
+        let path = equity_log_path();
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let due_before = equity_snapshot_due(&path, &today);
+        log_equity_snapshot(1_234.567);
+        log_equity_snapshot(1_300.0);
+        flush_trade_log();
+        set_trade_log_folder(&original);
+
+        let log = fs::read_to_string(&path).unwrap();
+        let due_after = equity_snapshot_due(&path, &today);
+        let _ = fs::remove_dir_all(&folder);
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", log);
+        assert_eq!(lines[0], "timestamp,equity_usdt");
+        assert!(lines[1].starts_with(&today) && lines[1].ends_with(",1234.57"), "{}", lines[1]);
+        assert!(lines[2].ends_with(",1300.00"), "{}", lines[2]);
+        assert!(due_before && !due_after);
+    }
+}
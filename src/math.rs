@@ -0,0 +1,22 @@
+/// Percentage change from `from` to `to`, e.g. 100 → 110 is 10.0. None when `from` is zero or
+/// either value is not finite, instead of the inf/NaN the raw division would produce.
+pub fn percent_change(from: f64, to: f64) -> Option<f64> {
+    if from == 0.0 || !from.is_finite() || !to.is_finite() {
+        return None;
+    }
+    Some((to - from) / from * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_change_is_none_on_a_zero_base() {
+        assert!((percent_change(100.0, 110.0).unwrap() - 10.0).abs() < 1e-9);
+        assert!((percent_change(50.0, 25.0).unwrap() + 50.0).abs() < 1e-9);
+        assert_eq!(percent_change(0.0, 5.0), None);
+        assert_eq!(percent_change(1.0, f64::NAN), None);
+        assert_eq!(percent_change(f64::INFINITY, 1.0), None);
+    }
+}
@@ -1,16 +1,99 @@
-use crate::types::{Signal, TrendDirection};
+use crate::types::{Kline, Signal, SignalParams, TrendDirection};
 use chrono::Utc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use serde_json::Value;
 use log::{info, error};
-use crate::api::binance::Binance;
-use std::collections::HashSet;
-use crate::types::MARKET_TREND;
+use crate::api::binance::{Binance, FundingRate};
+use crate::math::percent_change;
+use crate::trading::strategy::Strategy;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use crate::types::{LAST_SCAN_CHANGES, LISTING_TIMES, MARKET_TREND};
 use crate::config;
+use crate::trading::indicators::compute_wilder_rsi;
+use crate::logging::log_market_breadth;
+use crate::{status_println, verbose_println};
 
-pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_amounts: &[f64], trend: TrendDirection,) -> Vec<Signal> {
+/// Why a scan produced nothing usable, as opposed to simply finding no signals.
+#[derive(Debug)]
+pub enum DiscoveryError {
+    /// The 24hr ticker fetch failed, so no symbols could be scanned.
+    Tickers(reqwest::Error),
+    /// Every kline request in the cycle failed.
+    Klines { failed: usize },
+}
+
+impl fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiscoveryError::Tickers(e) => write!(f, "failed to fetch 24hr tickers: {}", e),
+            DiscoveryError::Klines { failed } => write!(f, "all {} kline requests failed", failed),
+        }
+    }
+}
+
+impl std::error::Error for DiscoveryError {}
+
+/// Health summary of one discovery cycle, logged as a single event when the scan completes.
+#[derive(Debug, Clone, Default)]
+pub struct ScanResult {
+    /// Tickers returned by the 24hr endpoint.
+    pub scanned: usize,
+    /// Symbols that passed the volume/exclusion filters for a funded quote asset.
+    pub candidates: usize,
+    pub signals: usize,
+    /// Tickers skipped because the symbol is already held or has an open order.
+    pub skipped_invested: usize,
+    /// Candidates not re-evaluated because their 24h change barely moved (`SCAN_CHANGE_DELTA`).
+    pub skipped_unchanged: usize,
+    /// Lower-volume candidates dropped by `MAX_CANDIDATES_PER_SCAN`.
+    pub skipped_capped: usize,
+    /// Tickers whose symbol is halted, in break or not spot-tradable per `exchangeInfo`.
+    pub skipped_untradable: usize,
+    /// Candidates listed more recently than `MIN_SYMBOL_AGE_DAYS`.
+    pub skipped_new: usize,
+    /// Balance and kline requests that failed.
+    pub errors: usize,
+    pub duration: Duration,
+    /// Share of tickers with a positive 24h change (0.0 – 1.0).
+    pub breadth: f64,
+}
+
+impl fmt::Display for ScanResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "scanned={} candidates={} signals={} skipped_invested={} skipped_unchanged={} skipped_capped={} skipped_untradable={} skipped_new={} errors={} duration={:.1}s breadth={:.1}%",
+            self.scanned,
+            self.candidates,
+            self.signals,
+            self.skipped_invested,
+            self.skipped_unchanged,
+            self.skipped_capped,
+            self.skipped_untradable,
+            self.skipped_new,
+            self.errors,
+            self.duration.as_secs_f64(),
+            self.breadth * 100.0,
+        )
+    }
+}
+
+pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_amounts: &[f64], trend: TrendDirection, strategy: &dyn Strategy,) -> Result<Vec<Signal>, DiscoveryError> {
+    scan_market(binance, assets, transaction_amounts, trend, strategy).await.map(|(signals, _)| signals)
+}
+
+/// `discover_signals` that also returns the scan's `ScanResult`.
+pub async fn scan_market(binance: &Binance, assets: &[String], transaction_amounts: &[f64], trend: TrendDirection, strategy: &dyn Strategy,) -> Result<(Vec<Signal>, ScanResult), DiscoveryError> {
     let mut signals = Vec::new();
+    let mut kline_requests = 0usize;
+    let mut kline_failures = 0usize;
+    let started = Instant::now();
+    let mut result = ScanResult::default();
+    let scan_change_delta = config::get_scan_change_delta();
+    let min_symbol_age_days = config::get_min_symbol_age_days();
 
     let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
     info!("[{}] Starting market scan...", timestamp);
@@ -42,7 +125,7 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
         Err(e) => {
             error!("Error fetching 24hr tickers: {}", e);
             sleep(Duration::from_secs(1)).await;
-            return signals;
+            return Err(DiscoveryError::Tickers(e));
         }
     };
     let positive_count = all_tickers.iter()
@@ -50,6 +133,12 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
     .count();
     let total = all_tickers.len();
     let ratio = positive_count as f64 / total as f64;
+    result.scanned = total;
+    result.breadth = ratio;
+    result.skipped_invested = all_tickers
+        .iter()
+        .filter(|t| invested_tokens.contains(&t.symbol))
+        .count();
 
     let trend_str = if ratio >= 0.5 {
         "Positive"
@@ -59,6 +148,7 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
 
     let mut mt = MARKET_TREND.write().await;
     *mt = trend_str.to_string();
+    log_market_breadth(ratio, trend_str);
 
     /* match trend {
         TrendDirection::Positive => {
@@ -78,8 +168,9 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
 
     let min_volume = config::get_min_volume() as f64;
     let excluded_tokens = config::get_excluded_tokens();
+    let excluded_bases = config::get_excluded_base_assets();
 
-    let tradable_tokens: Vec<(String, f64)> = all_tickers
+    let tradable_tokens: Vec<(String, f64, f64)> = all_tickers
         .into_iter()
         .filter_map(|ticker| {
             let volume = ticker.quote_volume.parse::<f64>().unwrap_or(0.0);
@@ -89,38 +180,80 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
                 if volume >= min_volume
                     && !invested_tokens.contains(&ticker.symbol)
                     && !excluded_tokens.contains(&ticker.symbol)
+                    && assets.iter().any(|a| symbol.ends_with(a.as_str()))
+                    && !split_symbol(&symbol, assets).is_some_and(|(base, _)| excluded_bases.iter().any(|b| b == base))
                 {
-                    Some((symbol, change))
+                    Some((symbol, change, volume))
                 } else {
                     None
                 }
             })
         })
         .collect();
+    // Tickers also cover halted and margin-only pairs; their klines would be fetched for nothing.
+    let tradable_tokens = match binance.tradable_symbols().await {
+        Ok(tradable) => {
+            let (kept, untradable): (Vec<_>, Vec<_>) = tradable_tokens
+                .into_iter()
+                .partition(|(symbol, _, _)| tradable.contains(symbol));
+            result.skipped_untradable = untradable.len();
+            kept
+        }
+        Err(e) => {
+            error!("Could not fetch tradable symbols; scanning all tickers: {}", e);
+            tradable_tokens
+        }
+    };
+    let before_cap = tradable_tokens.len();
+    let tradable_tokens = top_by_volume(tradable_tokens, config::get_max_candidates_per_scan());
+    result.skipped_capped = before_cap - tradable_tokens.len();
 
     for (i, asset) in assets.iter().enumerate() {
         let balance = match binance.get_account_balance(asset).await {
             Ok(b) => b,
             Err(e) => {
                 error!("Error fetching balance for {}: {}", asset, e);
+                result.errors += 1;
                 continue;
             }
         };
 
-        if balance < transaction_amounts.get(i).copied().unwrap_or(10.0) {
+        let transaction_amount = match transaction_amounts.get(i) {
+            Some(amount) => *amount,
+            None => config::SHARED_CONFIG.read().unwrap().transaction_amount_for(asset),
+        };
+        if balance < transaction_amount {
             continue;
         }   
 
         let lookback = config::get_lookback_period();
-        let recent = config::get_last_hours_period();
+        let closed_only = config::get_closed_candles_only();
+        let rsi_period = config::get_rsi_period();
+        let limit = kline_fetch_limit(lookback, indicator_warmup(rsi_period), closed_only);
 
-        let candidates: Vec<String> = tradable_tokens
+        let candidates: Vec<(String, f64)> = tradable_tokens
             .iter()
-            .filter(|(symbol, _)| symbol.ends_with(asset))
-            .map(|(symbol, _)| symbol.clone())
+            .filter(|(symbol, _, _)| symbol.ends_with(asset))
+            .map(|(symbol, change, _)| (symbol.clone(), *change))
             .collect();
+        result.candidates += candidates.len();
+
+        for (symbol, change) in candidates {
+            let last_change = LAST_SCAN_CHANGES.lock().await.get(&symbol).copied();
+            if !should_rescan(last_change, change, scan_change_delta) {
+                result.skipped_unchanged += 1;
+                continue;
+            }
+
+            if min_symbol_age_days > 0 {
+                let listed_at = symbol_listed_at(binance, &symbol).await;
+                if listed_at.is_some_and(|t| is_newly_listed(t, Utc::now().timestamp_millis(), min_symbol_age_days)) {
+                    verbose_println!("🐣 Skipping {}: listed less than {} days ago", symbol, min_symbol_age_days);
+                    result.skipped_new += 1;
+                    continue;
+                }
+            }
 
-        for symbol in candidates {
            /*  
            let supported = match binance.symbol_supports_order_type(&symbol, "TRAILING_STOP_MARKET").await {
                 Ok(v) => v,
@@ -138,65 +271,225 @@ pub async fn discover_signals(binance: &Binance, assets: &[String], transaction_
             
 
 
-            match binance.get_klines(&symbol, "1h", lookback).await {
+            kline_requests += 1;
+            match binance.get_klines_typed(&symbol, "1h", limit).await {
                 Ok(klines) => {
-                    if let Some(signal) = evaluate_klines(
-                        &symbol,
-                        &klines,
-                        lookback as u32,
-                        recent as u32,
-                        trend,
-                    ) {
-                        signals.push(signal);
+                    LAST_SCAN_CHANGES.lock().await.insert(symbol.clone(), change);
+                    let history = if closed_only {
+                        closed_klines(&klines, Utc::now().timestamp_millis())
+                    } else {
+                        &klines[..]
+                    };
+                    if let Some(signal) = strategy_signal(strategy, &symbol, history, lookback, trend) {
+                        if passes_rsi_filter(&symbol, history, rsi_period)
+                            && passes_spread_filter(binance, &symbol).await
+                            && passes_funding_filter(binance, &symbol).await
+                        {
+                            signals.push(signal);
+                        }
                     }
                 }
                 Err(e) => {
+                    kline_failures += 1;
                     error!("Error fetching klines for {}: {}", symbol, e);
                 }
             }
         }
     }
-    signals
+
+    result.signals = signals.len();
+    result.errors += kline_failures;
+    result.duration = started.elapsed();
+    info!("Scan complete: {}", result);
+    status_println!("🔎 Scan complete: {}", result);
+
+    if kline_requests > 0 && kline_failures == kline_requests {
+        return Err(DiscoveryError::Klines { failed: kline_failures });
+    }
+    rank_signals(&mut signals);
+    Ok((signals, result))
+}
+
+/// Runs `strategy` on the last `lookback` candles of `history` and tags the signal with
+/// `symbol`. The extra warmup candles before the window only feed indicators.
+pub fn strategy_signal(strategy: &dyn Strategy, symbol: &str, history: &[Kline], lookback: u16, trend: TrendDirection) -> Option<Signal> {
+    let window = &history[history.len().saturating_sub(lookback as usize)..];
+    strategy
+        .evaluate(window, trend)
+        .map(|s| Signal { symbol: symbol.to_string(), ..s })
+}
+
+/// Binance's maximum klines per request.
+const MAX_KLINE_LIMIT: u16 = 1000;
+
+/// Weights of overall growth, recent growth and calmness in `signal_score`.
+const SIGNAL_SCORE_WEIGHTS: (f64, f64, f64) = (0.5, 0.3, 0.2);
+
+/// Extra candles the enabled indicators need before the lookback window (0 when none are enabled).
+pub fn indicator_warmup(rsi_period: u16) -> u16 {
+    [rsi_period].into_iter().max().unwrap_or(0)
+}
+
+/// Klines to request: the lookback plus indicator warmup, plus one more when the still-forming
+/// candle is dropped, clamped to what Binance serves in one call.
+pub fn kline_fetch_limit(lookback: u16, warmup: u16, closed_only: bool) -> u16 {
+    let limit = lookback
+        .saturating_add(warmup)
+        .saturating_add(closed_only as u16);
+    limit.min(MAX_KLINE_LIMIT)
+}
+
+/// Whether the Wilder RSI over all of `closes` is above `rsi_max`; None without enough candles.
+pub fn is_overbought(closes: &[f64], rsi_period: u16, rsi_max: f64) -> Option<bool> {
+    compute_wilder_rsi(closes, rsi_period as usize).map(|rsi| rsi > rsi_max)
+}
+
+/// Rejects overbought symbols whose RSI is above `RSI_MAX`; always passes when `RSI_PERIOD` is 0.
+/// The RSI runs over the whole fetched history, warmup candles included.
+fn passes_rsi_filter(symbol: &str, klines: &[Kline], rsi_period: u16) -> bool {
+    if rsi_period == 0 {
+        return true;
+    }
+    let closes: Vec<f64> = klines.iter().map(|k| k.close).collect();
+    let rsi_max = config::get_rsi_max();
+    match is_overbought(&closes, rsi_period, rsi_max) {
+        Some(true) => {
+            info!("Skipping {}: RSI({}) above {}", symbol, rsi_period, rsi_max);
+            false
+        }
+        Some(false) => true,
+        None => {
+            info!("Skipping {}: not enough candles for RSI({})", symbol, rsi_period);
+            false
+        }
+    }
+}
+
+/// Keeps the `cap` candidates `(symbol, change, quote_volume)` with the highest 24h quote volume,
+/// highest first. A cap of 0 keeps all of them.
+pub fn top_by_volume(mut candidates: Vec<(String, f64, f64)>, cap: usize) -> Vec<(String, f64, f64)> {
+    if cap == 0 {
+        return candidates;
+    }
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+    candidates.truncate(cap);
+    candidates
+}
+
+/// The last `n` items, or None when `n` is 0 or `items` holds fewer than `n` of them.
+pub fn tail_window<T>(items: &[T], n: usize) -> Option<&[T]> {
+    if n == 0 || items.len() < n {
+        return None;
+    }
+    Some(&items[items.len() - n..])
+}
+
+/// True when a symbol first traded (`listed_at_ms`) less than `min_age_days` before `now_ms`.
+pub fn is_newly_listed(listed_at_ms: i64, now_ms: i64, min_age_days: u32) -> bool {
+    now_ms - listed_at_ms < min_age_days as i64 * 24 * 60 * 60 * 1000
+}
+
+/// Listing time of `symbol` from its first daily kline, fetched once per symbol and then cached.
+/// None when it cannot be fetched, so the symbol is not skipped on a transient error.
+async fn symbol_listed_at(binance: &Binance, symbol: &str) -> Option<i64> {
+    if let Some(t) = LISTING_TIMES.lock().await.get(symbol) {
+        return Some(*t);
+    }
+    match binance.get_first_kline_time(symbol).await {
+        Ok(Some(t)) => {
+            LISTING_TIMES.lock().await.insert(symbol.to_string(), t);
+            Some(t)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            error!("Error fetching listing time for {}: {}", symbol, e);
+            None
+        }
+    }
+}
+
+/// True when a symbol's klines should be fetched: incremental scanning is off (`delta` <= 0),
+/// it was never evaluated, or its 24h change moved at least `delta` points since then.
+pub fn should_rescan(last_change: Option<f64>, change: f64, delta: f64) -> bool {
+    delta <= 0.0 || last_change.is_none_or(|last| (change - last).abs() >= delta)
+}
+
+/// Drops the final kline if it is still forming, i.e. its close time is after `now_ms`.
+pub fn closed_klines(klines: &[Kline], now_ms: i64) -> &[Kline] {
+    match klines.last() {
+        Some(last) if last.close_time > now_ms => &klines[..klines.len() - 1],
+        _ => klines,
+    }
+}
+
+/// Applies the live entry rules to a window of klines; `Some` means a signal would fire.
+pub fn evaluate_klines(symbol: &str,klines: &[Vec<Value>],params: SignalParams,trend: TrendDirection,) -> Option<Signal> {
+    let candles: Vec<Kline> = klines.iter().map(|k| Kline::from_raw(k)).collect::<Option<_>>()?;
+    let signal = evaluate(&candles, params, trend)?;
+    Some(Signal { symbol: symbol.to_string(), ..signal })
+}
+
+/// Pure entry rules on typed candles (oldest first). The returned signal has an empty `symbol`;
+/// callers fill it in.
+pub fn evaluate(candles: &[Kline], params: SignalParams, trend: TrendDirection) -> Option<Signal> {
+    let evaluation = explain(candles, params, trend)?;
+    if !evaluation.fires {
+        return None;
+    }
+
+    let (avg_fluct_raw, avg_fluct_pct) = calculate_fluctuations(candles);
+
+    Some(Signal {
+        symbol: String::new(),
+        overall_growth: evaluation.overall_growth,
+        recent_growth: evaluation.recent_growth,
+        avg_fluct_raw,
+        avg_fluct_pct,
+        score: signal_score(evaluation.overall_growth, evaluation.recent_growth, avg_fluct_pct),
+    })
+}
+
+/// Intermediate values of the entry rules for one window, kept for replays and debugging.
+#[derive(Debug, Clone, Copy)]
+pub struct Evaluation {
+    pub overall_growth: f64,
+    pub recent_growth: f64,
+    pub current_trend_up: bool,
+    /// The latest `green_candles` candles each closed up by at least `green_min_pct`.
+    pub strong_green: bool,
+    /// True when every rule for the trend direction holds, i.e. `evaluate` returns a signal.
+    pub fires: bool,
 }
 
-fn evaluate_klines(symbol: &str,klines: &[Vec<Value>],lookback: u32,recent: u32,trend: TrendDirection,) -> Option<Signal> {
-    if klines.len() < lookback as usize {
+/// Runs the entry rules and returns every intermediate value; None when the window is too short.
+pub fn explain(candles: &[Kline], params: SignalParams, trend: TrendDirection) -> Option<Evaluation> {
+    let len = candles.len();
+    if len < params.lookback as usize || len < 2 || params.recent == 0 || params.recent as usize > len {
         return None;
     }
 
-    let open = parse_f64(&klines[0][1])?;
-    let prev_close = parse_f64(&klines[klines.len() - 2][4])?;
-    let last_close = parse_f64(&klines[klines.len() - 1][4])?;
+    let open = candles[0].open;
+    let prev_close = candles[len - 2].close;
+    let last_close = candles[len - 1].close;
 
-    let overall_growth = ((last_close - open) / open) * 100.0;
+    let overall_growth = percent_change(open, last_close)?;
     let current_trend_up = last_close > prev_close;
 
-    let recent_candles = &klines[klines.len() - recent as usize..];
-    let recent_open = parse_f64(&recent_candles[0][1])?;
-    let recent_close = parse_f64(&recent_candles.last().unwrap()[4])?;
-    let recent_growth = ((recent_close - recent_open) / recent_open) * 100.0;
-
-    // 2 strong green candles check
-    let last2_open = parse_f64(&klines[klines.len() - 2][1])?;
-    let last2_close = parse_f64(&klines[klines.len() - 2][4])?;
-    let last1_open = parse_f64(&klines[klines.len() - 1][1])?;
-    let last1_close = last_close;
-    let last2_pct = ((last2_close - last2_open) / last2_open) * 100.0;
-    let last1_pct = ((last1_close - last1_open) / last1_open) * 100.0;
-
-    let two_strong_green =
-        last2_close > last2_open &&
-        last1_close > last1_open &&
-        last2_pct >= 0.5 &&
-        last1_pct >= 0.5;
+    let recent_candles = &candles[len - params.recent as usize..];
+    let recent_open = recent_candles[0].open;
+    let recent_close = recent_candles[recent_candles.len() - 1].close;
+    let recent_growth = percent_change(recent_open, recent_close)?;
+
+    // N strong green candles check
+    let strong_green = strong_green_run(candles, params.green_candles as usize, params.green_min_pct, params.green_min_body_ratio);
 
      // Final validation
-     let valid = match trend {
+     let fires = match trend {
         TrendDirection::Positive => {
             overall_growth >= 10.0 &&
             current_trend_up &&
             recent_growth > 0.0 &&
-            two_strong_green
+            strong_green
         },
         TrendDirection::Negative => {
             overall_growth <= -10.0 &&
@@ -205,36 +498,152 @@ fn evaluate_klines(symbol: &str,klines: &[Vec<Value>],lookback: u32,recent: u32,
         },
     };
 
-    if !valid {
-        return None;
-    }
-
-    let (avg_fluct_raw, avg_fluct_pct) = calculate_fluctuations(klines);
-
-    Some(Signal {
-        symbol: symbol.to_string(),
+    Some(Evaluation {
         overall_growth,
         recent_growth,
-        avg_fluct_raw,
-        avg_fluct_pct,
+        current_trend_up,
+        strong_green,
+        fires,
     })
 }
 
-fn parse_f64(value: &Value) -> Option<f64> {
-    value.as_str()?.parse::<f64>().ok()
+/// True when the last `n` candles each closed above their open by at least `min_pct` percent,
+/// with a body of at least `min_body_ratio` of the candle's range, so a green candle with a
+/// long rejection wick does not count. False when there are fewer than `n` candles.
+pub fn strong_green_run(candles: &[Kline], n: usize, min_pct: f64, min_body_ratio: f64) -> bool {
+    candles.len() >= n
+        && candles[candles.len() - n..].iter().all(|k| {
+            k.close > k.open && percent_change(k.open, k.close).is_some_and(|pct| pct >= min_pct) && body_ratio(k) >= min_body_ratio
+        })
+}
+
+/// |close - open| / (high - low): 1.0 for a candle with no wicks, near 0 for a doji.
+/// A candle with no range counts as all body.
+pub fn body_ratio(k: &Kline) -> f64 {
+    let range = k.high - k.low;
+    if range <= 0.0 {
+        return 1.0;
+    }
+    ((k.close - k.open).abs() / range).min(1.0)
+}
+
+/// Loads recorded klines for a replay: a JSON array of raw `/klines` rows (`.json`), or a CSV
+/// with an `open_time,open,high,low,close,volume,close_time` header. Sorted oldest first.
+pub fn load_klines_file(path: &Path) -> Result<Vec<Kline>, Box<dyn std::error::Error>> {
+    let mut klines: Vec<Kline> = if path.extension().is_some_and(|ext| ext == "json") {
+        let raw: Vec<Vec<Value>> = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        raw.iter()
+            .map(|k| Kline::from_raw(k).ok_or_else(|| format!("Malformed kline row: {:?}", k)))
+            .collect::<Result<_, _>>()?
+    } else {
+        csv::Reader::from_path(path)?.deserialize().collect::<Result<_, _>>()?
+    };
+    klines.sort_by_key(|k| k.open_time);
+    Ok(klines)
+}
+
+/// Weighted blend of overall growth, recent growth and calmness (10 / (1 + avg fluctuation %)).
+/// Growth is signed so negative-trend signals rank by the size of the move, not its direction.
+pub fn signal_score(overall_growth: f64, recent_growth: f64, avg_fluct_pct: f64) -> f64 {
+    let (w_overall, w_recent, w_calm) = SIGNAL_SCORE_WEIGHTS;
+    w_overall * overall_growth.abs() + w_recent * recent_growth.abs() + w_calm * 10.0 / (1.0 + avg_fluct_pct.max(0.0))
 }
 
-fn calculate_fluctuations(klines: &[Vec<Value>]) -> (f64, f64) {
+/// Sorts signals strongest first so they get the trade budget before weaker ones.
+pub fn rank_signals(signals: &mut [Signal]) {
+    signals.sort_by(|a, b| b.score.total_cmp(&a.score));
+}
+
+/// Reorders ranked `signals` so each buy goes to the quote asset with the most headroom left,
+/// where `headroom` maps a quote to how many buys its balance still covers. Every pick uses one
+/// unit; signals of quotes with none left are dropped. Ties keep the signal ranking.
+pub fn rotate_by_headroom(signals: Vec<Signal>, headroom: &HashMap<String, u32>, quotes: &[String]) -> Vec<Signal> {
+    let mut remaining = headroom.clone();
+    let mut pending: Vec<(Signal, String)> = signals
+        .into_iter()
+        .filter_map(|s| {
+            let (_, quote) = split_symbol(&s.symbol, quotes)?;
+            let quote = quote.to_string();
+            Some((s, quote))
+        })
+        .collect();
+    let mut rotated = Vec::new();
+
+    loop {
+        let best = pending
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, q))| remaining.get(q).copied().unwrap_or(0) > 0)
+            .max_by(|(i, (_, a)), (j, (_, b))| remaining[a].cmp(&remaining[b]).then(j.cmp(i)))
+            .map(|(i, _)| i);
+        let Some(i) = best else { break };
+        let (signal, quote) = pending.remove(i);
+        *remaining.get_mut(&quote).unwrap() -= 1;
+        rotated.push(signal);
+    }
+    rotated
+}
+
+/// Rejects thin books whose best bid/ask spread exceeds `MAX_SPREAD_PCT`.
+async fn passes_spread_filter(binance: &Binance, symbol: &str) -> bool {
+    let max_spread_pct = config::get_max_spread_pct();
+    if max_spread_pct <= 0.0 {
+        return true;
+    }
+
+    match binance.get_order_book(symbol, 5).await {
+        Ok(book) => {
+            if book.within_spread(max_spread_pct) {
+                true
+            } else {
+                info!("Skipping {}: spread {:?}% exceeds max {:.2}%", symbol, book.spread_pct(), max_spread_pct);
+                false
+            }
+        }
+        Err(e) => {
+            error!("Error fetching order book for {}: {}", symbol, e);
+            false
+        }
+    }
+}
+
+/// False when longing into funding below `min_funding_pct`: deeply negative funding means
+/// shorts are crowded and paying to stay in. A symbol without a perpetual always passes.
+pub fn passes_funding_gate(funding: Option<&FundingRate>, min_funding_pct: f64) -> bool {
+    funding.is_none_or(|f| f.rate_pct >= min_funding_pct)
+}
+
+/// Applies `passes_funding_gate` with MIN_FUNDING_RATE_PCT; a no-op while that is unset.
+async fn passes_funding_filter(binance: &Binance, symbol: &str) -> bool {
+    let Some(min_funding_pct) = config::get_min_funding_rate_pct() else {
+        return true;
+    };
+
+    match binance.get_funding_rate(symbol).await {
+        Ok(funding) => {
+            if passes_funding_gate(funding.as_ref(), min_funding_pct) {
+                true
+            } else {
+                info!("Skipping {}: funding {:?}% below min {:.4}%", symbol, funding.map(|f| f.rate_pct), min_funding_pct);
+                false
+            }
+        }
+        Err(e) => {
+            error!("Error fetching funding rate for {}: {}", symbol, e);
+            false
+        }
+    }
+}
+
+fn calculate_fluctuations(candles: &[Kline]) -> (f64, f64) {
     let mut raw = vec![];
     let mut pct = vec![];
 
-    for candle in klines {
-        let high = parse_f64(&candle[2]).unwrap_or(0.0);
-        let low = parse_f64(&candle[3]).unwrap_or(0.0);
-        if high > 0.0 && low > 0.0 {
-            let diff = high - low;
+    for candle in candles {
+        if candle.high > 0.0 && candle.low > 0.0 {
+            let diff = candle.high - candle.low;
             raw.push(diff);
-            pct.push((diff / low) * 100.0);
+            pct.push((diff / candle.low) * 100.0);
         }
     }
 
@@ -244,6 +653,14 @@ fn calculate_fluctuations(klines: &[Vec<Value>]) -> (f64, f64) {
     (avg_raw, avg_pct)
 }
 
+/// Splits `symbol` into (base, quote) using the first of `quote_assets` it ends with.
+pub fn split_symbol<'a>(symbol: &'a str, quote_assets: &[String]) -> Option<(&'a str, &'a str)> {
+    quote_assets
+        .iter()
+        .filter(|quote| symbol.len() > quote.len())
+        .find_map(|quote| symbol.strip_suffix(quote.as_str()).map(|base| (base, &symbol[base.len()..])))
+}
+
 pub fn expand_holdings_to_pairs( holdings: &[(String, f64)], quote_assets: &[String],) -> Vec<String> {
     let mut pairs = Vec::new();
 
@@ -258,4 +675,418 @@ pub fn expand_holdings_to_pairs( holdings: &[(String, f64)], quote_assets: &[Str
     }
 
     pairs
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::binance::mock::{local_binance, local_binance_recording, mark_tradable};
+
+    #[test]
+    fn rsi_warmup_extends_the_kline_fetch() {
+        assert!(kline_fetch_limit(48, indicator_warmup(14), false) >= 62);
+        assert_eq!(kline_fetch_limit(48, indicator_warmup(0), true), 49);
+        assert_eq!(kline_fetch_limit(990, indicator_warmup(14), false), 1000);
+    }
+
+    #[test]
+    fn overbought_rsi_is_detected_over_the_full_history() {
+        let rising: Vec<f64> = (0..62).map(|i| 100.0 + i as f64).collect();
+        assert_eq!(is_overbought(&rising, 14, 70.0), Some(true));
+        let falling: Vec<f64> = rising.iter().rev().copied().collect();
+        assert_eq!(is_overbought(&falling, 14, 70.0), Some(false));
+        assert_eq!(is_overbought(&rising[..10], 14, 70.0), None);
+    }
+
+    fn rising_candles(n: usize, step_pct: f64) -> Vec<Kline> {
+        let mut open = 100.0;
+        (0..n)
+            .map(|i| {
+                let close = open * (1.0 + step_pct / 100.0);
+                let k = Kline { open_time: i as i64, open, high: close, low: open, close, volume: 1.0, close_time: i as i64 + 1 };
+                open = close;
+                k
+            })
+            .collect()
+    }
+
+    #[test]
+    fn green_candle_rule_follows_the_params_passed_in() {
+        let candles = rising_candles(20, 1.0);
+        let params = SignalParams { lookback: 20, recent: 5, green_candles: 3, green_min_pct: 0.5, green_min_body_ratio: 0.5 };
+        assert!(evaluate(&candles, params, TrendDirection::Positive).is_some());
+
+        let stricter = SignalParams { green_min_pct: 2.0, ..params };
+        let evaluation = explain(&candles, stricter, TrendDirection::Positive).unwrap();
+        assert!(!evaluation.strong_green);
+        assert!(!evaluation.fires);
+    }
+
+    /// Signals on every window, reporting how many candles it was shown.
+    struct AlwaysSignal;
+
+    impl Strategy for AlwaysSignal {
+        fn evaluate(&self, candles: &[Kline], _trend: TrendDirection) -> Option<Signal> {
+            Some(Signal {
+                symbol: String::new(),
+                overall_growth: candles.len() as f64,
+                recent_growth: 0.0,
+                avg_fluct_raw: 0.0,
+                avg_fluct_pct: 0.0,
+                score: 1.0,
+            })
+        }
+    }
+
+    #[test]
+    fn an_injected_strategy_drives_the_scan_output() {
+        let history = rising_candles(62, 0.0);
+        let signal = strategy_signal(&AlwaysSignal, "ABCUSDT", &history, 48, TrendDirection::Negative).unwrap();
+        assert_eq!(signal.symbol, "ABCUSDT");
+        assert_eq!(signal.overall_growth, 48.0);
+
+        // The momentum rule never fires on flat candles.
+        let flat = strategy_signal(&crate::trading::strategy::MomentumStrategy, "ABCUSDT", &history, 48, TrendDirection::Positive);
+        assert!(flat.is_none());
+    }
+
+    fn account_routes() -> Vec<(&'static str, String)> {
+        vec![("/openOrders", "[]".to_string()), ("/account", r#"{"balances":[]}"#.to_string())]
+    }
+
+    #[tokio::test]
+    async fn a_failed_ticker_fetch_is_an_error_not_an_empty_scan() {
+        let binance = local_binance(account_routes()).await;
+        let scan = discover_signals(&binance, &["USDT".to_string()], &[10.0], TrendDirection::Positive, &AlwaysSignal).await;
+        assert!(matches!(scan, Err(DiscoveryError::Tickers(_))));
+    }
+
+    #[tokio::test]
+    async fn a_scan_without_matches_is_ok_and_empty() {
+        let mut routes = account_routes();
+        routes.push(("/ticker/24hr", r#"[{"symbol":"ABCEUR","priceChangePercent":"3.5","quoteVolume":"9000000"}]"#.to_string()));
+        let binance = local_binance(routes).await;
+        let scan = discover_signals(&binance, &["USDT".to_string()], &[10.0], TrendDirection::Positive, &AlwaysSignal).await;
+        assert!(scan.unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_forming_last_candle_is_dropped_when_evaluating_closed_candles_only() {
+        let mut candles = rising_candles(10, 1.0);
+        let now_ms = 100;
+        assert_eq!(closed_klines(&candles, now_ms).len(), 10);
+
+        candles.last_mut().unwrap().close_time = now_ms + 1;
+        let closed = closed_klines(&candles, now_ms);
+        assert_eq!(closed.len(), 9);
+        assert_eq!(closed.last().unwrap().close, candles[8].close);
+    }
+
+    #[tokio::test]
+    async fn an_unchanged_ticker_is_not_refetched_on_the_next_scan() {
+        mark_tradable(&["INCRUSDT"]).await;
+        let (binance, requests) = local_binance_recording(vec![
+            ("/openOrders", "[]".to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"}]}"#.to_string()),
+            ("/ticker/24hr", r#"[{"symbol":"INCRUSDT","priceChangePercent":"3.5","quoteVolume":"9000000"}]"#.to_string()),
+            ("/klines", "[]".to_string()),
+        ])
+        .await;
+        let kline_requests = || requests.lock().unwrap().iter().filter(|r| r.contains("/klines")).count();
+        let assets = ["USDT".to_string()];
+
+        let previous = std::mem::replace(&mut config::SHARED_CONFIG.write().unwrap().scan_change_delta, 0.5);
+        let first = discover_signals(&binance, &assets, &[10.0], TrendDirection::Positive, &AlwaysSignal).await;
+        let after_first = kline_requests();
+        let second = discover_signals(&binance, &assets, &[10.0], TrendDirection::Positive, &AlwaysSignal).await;
+        config::SHARED_CONFIG.write().unwrap().scan_change_delta = previous;
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(after_first, 1);
+        assert_eq!(kline_requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn scan_result_counts_add_up_for_a_known_ticker_set() {
+        mark_tradable(&["SCANAUSDT", "SCANBUSDT"]).await;
+        let tickers = r#"[
+            {"symbol":"SCANAUSDT","priceChangePercent":"4.0","quoteVolume":"9000000"},
+            {"symbol":"SCANBUSDT","priceChangePercent":"2.0","quoteVolume":"8000000"},
+            {"symbol":"SCANHUSDT","priceChangePercent":"1.0","quoteVolume":"7000000"},
+            {"symbol":"SCANLUSDT","priceChangePercent":"-1.0","quoteVolume":"10"},
+            {"symbol":"SCANEUR","priceChangePercent":"-2.0","quoteVolume":"9000000"}
+        ]"#;
+        let binance = local_binance(vec![
+            ("/openOrders", "[]".to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"},{"asset":"SCANH","free":"5","locked":"0"}]}"#.to_string()),
+            ("/ticker/24hr", tickers.to_string()),
+            ("/klines", "[]".to_string()),
+        ])
+        .await;
+
+        let (signals, result) = scan_market(&binance, &["USDT".to_string()], &[10.0], TrendDirection::Positive, &AlwaysSignal).await.unwrap();
+        assert_eq!(result.scanned, 5);
+        assert_eq!(result.skipped_invested, 1);
+        assert_eq!(result.candidates, 2);
+        assert_eq!(result.signals, signals.len());
+        assert_eq!(result.signals, 2);
+        assert_eq!(result.errors, 0);
+        assert_eq!(result.breadth, 0.6);
+        assert!(result.duration > Duration::ZERO);
+    }
+
+    /// One candle per step, each opening at the previous close and moving `step_pct` percent.
+    fn candles_with_steps(steps: &[f64]) -> Vec<Kline> {
+        let mut open = 100.0;
+        steps
+            .iter()
+            .enumerate()
+            .map(|(i, step_pct)| {
+                let close = open * (1.0 + step_pct / 100.0);
+                let k = Kline { open_time: i as i64, open, high: open.max(close), low: open.min(close), close, volume: 1.0, close_time: i as i64 + 1 };
+                open = close;
+                k
+            })
+            .collect()
+    }
+
+    #[test]
+    fn each_entry_rule_can_veto_the_signal() {
+        let params = SignalParams { lookback: 20, recent: 5, green_candles: 2, green_min_pct: 0.4, green_min_body_ratio: 0.5 };
+
+        let strong = candles_with_steps(&[2.0; 20]);
+        assert!(evaluate(&strong, params, TrendDirection::Positive).is_some());
+
+        // Growth: green all the way, but under the 10% overall growth bar.
+        let weak = explain(&candles_with_steps(&[0.45; 20]), params, TrendDirection::Positive).unwrap();
+        assert!(weak.overall_growth < 10.0 && weak.strong_green && !weak.fires);
+
+        // Two-green: a red last candle breaks the run and the short-term trend.
+        let mut steps = [2.0; 20];
+        steps[19] = -0.5;
+        let red_last = explain(&candles_with_steps(&steps), params, TrendDirection::Positive).unwrap();
+        assert!(!red_last.strong_green && !red_last.current_trend_up && !red_last.fires);
+
+        // Recent growth: the last two candles are green, but the recent window is net down.
+        let mut steps = vec![2.0; 15];
+        steps.extend([-2.0, -2.0, -2.0, 0.5, 0.5]);
+        let faded = explain(&candles_with_steps(&steps), params, TrendDirection::Positive).unwrap();
+        assert!(faded.overall_growth >= 10.0 && faded.strong_green && faded.recent_growth < 0.0 && !faded.fires);
+
+        // Negative trend mirrors the growth and recent rules.
+        assert!(evaluate(&candles_with_steps(&[-2.0; 20]), params, TrendDirection::Negative).is_some());
+        assert!(evaluate(&strong, params, TrendDirection::Negative).is_none());
+        assert!(explain(&strong[..10], params, TrendDirection::Positive).is_none());
+    }
+
+    #[tokio::test]
+    async fn an_excluded_base_asset_is_dropped_under_every_quote() {
+        config::SHARED_CONFIG.write().unwrap().excluded_base_assets = vec!["LUNA".to_string()];
+        mark_tradable(&["LUNAUSDT", "LUNAUSDC", "KEEPUSDT"]).await;
+        let tickers = r#"[
+            {"symbol":"LUNAUSDT","priceChangePercent":"9.0","quoteVolume":"9000000"},
+            {"symbol":"LUNAUSDC","priceChangePercent":"9.0","quoteVolume":"9000000"},
+            {"symbol":"KEEPUSDT","priceChangePercent":"3.0","quoteVolume":"9000000"}
+        ]"#;
+        let binance = local_binance(vec![
+            ("/openOrders", "[]".to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"},{"asset":"USDC","free":"100","locked":"0"}]}"#.to_string()),
+            ("/ticker/24hr", tickers.to_string()),
+            ("/klines", "[]".to_string()),
+        ])
+        .await;
+
+        let assets = ["USDT".to_string(), "USDC".to_string()];
+        let (signals, result) = scan_market(&binance, &assets, &[10.0, 10.0], TrendDirection::Positive, &AlwaysSignal).await.unwrap();
+        config::SHARED_CONFIG.write().unwrap().excluded_base_assets.clear();
+        assert_eq!(result.candidates, 1);
+        assert_eq!(signals.iter().map(|s| s.symbol.as_str()).collect::<Vec<_>>(), vec!["KEEPUSDT"]);
+    }
+
+    fn scored_signal(symbol: &str, overall_growth: f64, recent_growth: f64, avg_fluct_pct: f64) -> Signal {
+        Signal {
+            symbol: symbol.to_string(),
+            overall_growth,
+            recent_growth,
+            avg_fluct_raw: 0.0,
+            avg_fluct_pct,
+            score: signal_score(overall_growth, recent_growth, avg_fluct_pct),
+        }
+    }
+
+    #[test]
+    fn the_strongest_signal_gets_a_single_trade_slot() {
+        let mut signals = vec![
+            scored_signal("WEAKUSDT", 11.0, 0.5, 6.0),
+            scored_signal("BESTUSDT", 30.0, 8.0, 1.0),
+            scored_signal("MIDUSDT", 18.0, 3.0, 2.0),
+        ];
+        rank_signals(&mut signals);
+        let ranked: Vec<&str> = signals.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(ranked, vec!["BESTUSDT", "MIDUSDT", "WEAKUSDT"]);
+
+        let headroom: HashMap<String, u32> = [("USDT".to_string(), 1)].into_iter().collect();
+        let executed = rotate_by_headroom(signals, &headroom, &["USDT".to_string()]);
+        assert_eq!(executed.len(), 1);
+        assert_eq!(executed[0].symbol, "BESTUSDT");
+    }
+
+    #[test]
+    fn a_replayed_kline_file_reproduces_the_live_signal() {
+        let mut steps = vec![1.0; 14];
+        steps.extend([4.0, 6.0, 8.0]);
+        let raw: Vec<Vec<Value>> = candles_with_steps(&steps)
+            .iter()
+            .map(|k| {
+                serde_json::json!([k.open_time, k.open.to_string(), k.high.to_string(), k.low.to_string(), k.close.to_string(), "1.0", k.close_time])
+                    .as_array()
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+        let params = SignalParams { lookback: 17, recent: 4, green_candles: 2, green_min_pct: 0.5, green_min_body_ratio: 0.5 };
+        let live = evaluate_klines("PUMPUSDT", &raw, params, TrendDirection::Positive).expect("the pump fires live");
+
+        // The capture is stored newest first; the replay sorts it back.
+        let path = std::env::temp_dir().join(format!("stock_pred_replay_{}.json", std::process::id()));
+        let reversed: Vec<&Vec<Value>> = raw.iter().rev().collect();
+        std::fs::write(&path, serde_json::to_string(&reversed).unwrap()).unwrap();
+        let replayed = load_klines_file(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let signal = evaluate(&replayed, params, TrendDirection::Positive).expect("the replay fires too");
+        assert_eq!(signal.overall_growth, live.overall_growth);
+        assert_eq!(signal.recent_growth, live.recent_growth);
+        assert_eq!(signal.score, live.score);
+    }
+
+    #[tokio::test]
+    async fn a_candidate_cap_fetches_klines_for_the_highest_volume_pairs_only() {
+        let symbols: Vec<String> = (0..300).map(|i| format!("VOL{}USDT", i)).collect();
+        let symbol_refs: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        mark_tradable(&symbol_refs).await;
+        let tickers: Vec<serde_json::Value> = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, s)| serde_json::json!({"symbol": s, "priceChangePercent": "3.0", "quoteVolume": (10_000_000 + i).to_string()}))
+            .collect();
+        let (binance, requests) = local_binance_recording(vec![
+            ("/openOrders", "[]".to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"}]}"#.to_string()),
+            ("/ticker/24hr", serde_json::to_string(&tickers).unwrap()),
+            ("/klines", "[]".to_string()),
+        ])
+        .await;
+
+        let previous = std::mem::replace(&mut config::SHARED_CONFIG.write().unwrap().max_candidates_per_scan, 50);
+        let scanned = scan_market(&binance, &["USDT".to_string()], &[10.0], TrendDirection::Positive, &AlwaysSignal).await;
+        config::SHARED_CONFIG.write().unwrap().max_candidates_per_scan = previous;
+        scanned.unwrap();
+
+        let kline_requests: Vec<String> = requests.lock().unwrap().iter().filter(|r| r.contains("/klines")).cloned().collect();
+        assert_eq!(kline_requests.len(), 50);
+        for i in 250..300 {
+            assert!(kline_requests.iter().any(|r| r.contains(&format!("symbol=VOL{}USDT&", i)) || r.contains(&format!("symbol=VOL{}USDT ", i))), "VOL{}USDT", i);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_symbol_with_a_few_days_of_history_is_too_new() {
+        let now = Utc::now().timestamp_millis();
+        let day = 24 * 60 * 60 * 1000;
+        let daily_kline = |open_time: i64| serde_json::json!([[open_time, "1.0", "1.1", "0.9", "1.05", "100", open_time + day - 1]]).to_string();
+        let binance = local_binance(vec![
+            ("symbol=FRESHUSDT&interval=1d", daily_kline(now - 3 * day)),
+            ("symbol=SEASONEDUSDT&interval=1d", daily_kline(now - 400 * day)),
+        ])
+        .await;
+
+        let fresh = binance.get_first_kline_time("FRESHUSDT").await.unwrap().unwrap();
+        assert!(is_newly_listed(fresh, now, 30));
+        let seasoned = binance.get_first_kline_time("SEASONEDUSDT").await.unwrap().unwrap();
+        assert!(!is_newly_listed(seasoned, now, 30));
+        assert!(!is_newly_listed(fresh, now, 0));
+    }
+
+    #[test]
+    fn empty_and_short_kline_sets_are_skipped_without_panicking() {
+        let params = SignalParams { lookback: 20, recent: 5, green_candles: 2, green_min_pct: 0.4, green_min_body_ratio: 0.5 };
+        let short: Vec<Vec<Value>> = vec![serde_json::json!([0, "1.0", "1.1", "0.9", "1.05", "100", 1]).as_array().unwrap().clone(); 3];
+
+        assert_eq!(tail_window::<Vec<Value>>(&[], 5), None);
+        assert_eq!(tail_window(&short, 5), None);
+        assert_eq!(tail_window(&short, 0), None);
+        assert_eq!(tail_window(&short, 2).map(<[_]>::len), Some(2));
+
+        for klines in [Vec::new(), short] {
+            for trend in [TrendDirection::Positive, TrendDirection::Negative] {
+                assert!(evaluate_klines("SHORTUSDT", &klines, params, trend).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn an_exhausted_quote_routes_the_next_buy_to_the_other() {
+        let quotes = vec!["USDC".to_string(), "USDT".to_string()];
+        let signals = vec![
+            scored_signal("ABCUSDC", 30.0, 8.0, 1.0),
+            scored_signal("ABCUSDT", 25.0, 6.0, 1.0),
+            scored_signal("XYZUSDC", 20.0, 4.0, 1.0),
+        ];
+        let headroom: HashMap<String, u32> = [("USDC".to_string(), 0), ("USDT".to_string(), 2)].into_iter().collect();
+        let routed: Vec<String> = rotate_by_headroom(signals.clone(), &headroom, &quotes).into_iter().map(|s| s.symbol).collect();
+        assert_eq!(routed, vec!["ABCUSDT"]);
+
+        // With both available, each pick goes to the quote with more buys left.
+        let headroom: HashMap<String, u32> = [("USDC".to_string(), 1), ("USDT".to_string(), 2)].into_iter().collect();
+        let routed: Vec<String> = rotate_by_headroom(signals, &headroom, &quotes).into_iter().map(|s| s.symbol).collect();
+        assert_eq!(routed, vec!["ABCUSDT", "ABCUSDC"]);
+    }
+
+    #[tokio::test]
+    async fn a_halted_symbol_in_the_ticker_feed_gets_no_kline_fetch() {
+        // HALTEDUSDT is in the 24h feed but not among the TRADING symbols.
+        mark_tradable(&["LISTEDUSDT"]).await;
+        let (binance, requests) = local_binance_recording(vec![
+            ("/openOrders", "[]".to_string()),
+            ("/account", r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"}]}"#.to_string()),
+            (
+                "/ticker/24hr",
+                r#"[{"symbol":"LISTEDUSDT","priceChangePercent":"3.0","quoteVolume":"9000000"},{"symbol":"HALTEDUSDT","priceChangePercent":"9.0","quoteVolume":"9000000"}]"#.to_string(),
+            ),
+            ("/klines", "[]".to_string()),
+        ])
+        .await;
+
+        let (_, result) = scan_market(&binance, &["USDT".to_string()], &[10.0], TrendDirection::Positive, &AlwaysSignal).await.unwrap();
+        assert_eq!(result.skipped_untradable, 1);
+        let kline_requests: Vec<String> = requests.lock().unwrap().iter().filter(|r| r.contains("/klines")).cloned().collect();
+        assert_eq!(kline_requests.len(), 1);
+        assert!(kline_requests[0].contains("symbol=LISTEDUSDT"));
+    }
+
+    #[test]
+    fn deeply_negative_funding_suppresses_the_signal() {
+        let funding = |rate: &str| FundingRate::from_premium_index(&serde_json::json!({"symbol": "ABCUSDT", "lastFundingRate": rate})).unwrap();
+        assert!(!passes_funding_gate(Some(&funding("-0.00100000")), -0.05));
+        assert!(passes_funding_gate(Some(&funding("-0.00010000")), -0.05));
+        assert!(passes_funding_gate(Some(&funding("0.00010000")), -0.05));
+        // Without a perpetual there is nothing to gate on.
+        assert!(passes_funding_gate(None, -0.05));
+    }
+
+    #[test]
+    fn a_long_upper_wick_fails_the_strong_green_check() {
+        let candle = |open: f64, high: f64, close: f64| Kline { open_time: 0, open, high, low: open, close, volume: 1.0, close_time: 1 };
+        // Both close 2% up; the first spiked to +10% and was sold back down.
+        let wicked = candle(100.0, 110.0, 102.0);
+        let full_bodied = candle(100.0, 102.2, 102.0);
+        assert!((body_ratio(&wicked) - 0.2).abs() < 1e-9);
+        assert!(body_ratio(&full_bodied) > 0.9);
+
+        assert!(!strong_green_run(&[wicked], 1, 1.0, 0.5));
+        assert!(strong_green_run(&[full_bodied], 1, 1.0, 0.5));
+        // A ratio of 0 turns the wick check off.
+        assert!(strong_green_run(&[wicked], 1, 1.0, 0.0));
+    }
+}
@@ -1,62 +1,278 @@
 // src/trading/execution.rs
 
-#[derive(Debug)]
-pub struct Order {
+use std::error::Error as StdError;
+use tracing::{error, info, warn};
+use crate::api::binance::Binance;
+use crate::config::{get_quote_assets, get_stop_limit_buffer_pct};
+use crate::trading::discovery::split_symbol;
+use crate::trading::paper::PaperPortfolio;
+
+/// A bought token and its trailing stop, as tracked by this module. Not to be confused with
+/// `types::Order`, which is Binance's order record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagedPosition {
     pub token: String,
     pub purchase_price: f64,
     pub stop_loss_price: f64,
+    pub quantity: f64,
+    /// The live STOP_LOSS_LIMIT order guarding the position; None for paper positions.
+    pub stop_order_id: Option<u64>,
+}
+
+/// The outcome of a buy: what was filled, at what price, and where its first stop sits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillSummary {
+    pub symbol: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub stop_loss_price: f64,
 }
 
-/// Simulate buying a token. In a real system, this would call the Binance API.
-/// 
+impl From<FillSummary> for ManagedPosition {
+    fn from(fill: FillSummary) -> Self {
+        ManagedPosition {
+            token: fill.symbol,
+            purchase_price: fill.price,
+            stop_loss_price: fill.stop_loss_price,
+            quantity: fill.quantity,
+            stop_order_id: None,
+        }
+    }
+}
+
+/// Buys a token at the live price and sets its initial stop.
+///
+/// With `paper`, the buy and stop are simulated on that portfolio (as in `pred simulate`);
+/// otherwise a market buy and a STOP_LOSS_LIMIT sell are placed on Binance.
+///
 /// # Arguments
 /// * `token_symbol` - The trading pair (e.g. "BTCUSDT").
-/// * `transaction_amount` - The dollar amount to invest.
+/// * `transaction_amount` - The quote amount to invest.
 /// * `stop_loss_percent` - The stop loss percentage to use.
-pub async fn buy_token(token_symbol: &str, transaction_amount: f64, stop_loss_percent: f64) -> Result<Order, &'static str> {
-    // Simulate fetching the current market price.
-    // Replace with an API call to get the current price.
-    let current_price = 100.0;  // for example purposes
-
-    // Calculate stop loss price.
-    let stop_loss_price = current_price * (1.0 - stop_loss_percent / 100.0);
-    
+pub async fn buy_token(
+    binance: &Binance,
+    paper: Option<&mut PaperPortfolio>,
+    token_symbol: &str,
+    transaction_amount: f64,
+    stop_loss_percent: f64,
+) -> Result<ManagedPosition, Box<dyn StdError>> {
+    let current_price = binance.get_price(token_symbol).await?;
+    let quote_assets = get_quote_assets();
+    let (base, quote) = split_symbol(token_symbol, &quote_assets)
+        .ok_or_else(|| format!("{} is not quoted in any of QUOTE_ASSETS", token_symbol))?;
+
+    if let Some(paper) = paper {
+        let fill = paper
+            .buy(token_symbol, quote, current_price, transaction_amount, stop_loss_percent)
+            .ok_or_else(|| format!("paper buy of {} rejected (already held or balance too low)", token_symbol))?;
+        let stop_loss_price = paper.positions[token_symbol].stop_price;
+        println!(
+            "🧪 Paper buy {} for {:.2} at {:.4} per unit. Initial stop loss at {:.4} ({}% below purchase price).",
+            token_symbol, transaction_amount, fill.price, stop_loss_price, stop_loss_percent
+        );
+        return Ok(ManagedPosition::from(FillSummary {
+            symbol: fill.symbol,
+            price: fill.price,
+            quantity: fill.quantity,
+            stop_loss_price,
+        }));
+    }
+
+    let filters = binance.cached_symbol_filters(token_symbol).await?;
+    let buy = binance.place_market_buy_quote(token_symbol, transaction_amount).await?;
+    let purchase_price = buy.avg_price().unwrap_or(current_price);
+    let stop_loss_price = Binance::round_to_step(purchase_price * (1.0 - stop_loss_percent / 100.0), filters.tick_size);
+    let limit_price = Binance::stop_limit_price(stop_loss_price, get_stop_limit_buffer_pct(), filters.tick_size);
+    // A commission paid in the base asset is not held, so the stop may only sell what is left.
+    let quantity = Binance::round_to_step(Binance::net_base_quantity(buy.executed_qty, &buy.fills, base), filters.step_size);
+    let stop_order_id = binance
+        .place_stop_loss_limit_order(token_symbol, quantity, stop_loss_price, limit_price, None, None)
+        .await?;
+
     println!(
-        "Buying {} for ${:.2} at ${:.2} per unit. Initial stop loss set at ${:.2} ({}% below purchase price).",
-        token_symbol, transaction_amount, current_price, stop_loss_price, stop_loss_percent
+        "Bought {} {} for {:.2} at {:.4} per unit. Initial stop loss set at {:.4} ({}% below purchase price).",
+        quantity, token_symbol, transaction_amount, purchase_price, stop_loss_price, stop_loss_percent
     );
-
-    // Here, you would send a market order to Binance.
-    // For now, we simulate a successful order by returning an Order struct.
-    Ok(Order {
+    info!(
+        "Bought {} {} for {:.2} at {:.4} per unit. Initial stop loss set at {:.4} ({}% below purchase price).",
+        quantity, token_symbol, transaction_amount, purchase_price, stop_loss_price, stop_loss_percent
+    );
+    Ok(ManagedPosition {
         token: token_symbol.to_string(),
-        purchase_price: current_price,
+        purchase_price,
         stop_loss_price,
+        quantity,
+        stop_order_id: Some(stop_order_id),
     })
 }
 
-/// Check the current market price and update the trailing stop loss if the price has increased.
-/// In a real implementation, this would cancel the old stop loss order and place a new one via the API.
+/// The stop `stop_loss_percent` below `current_price`, if the price is above the purchase price
+/// and that stop is higher than the current one. Stops only ever move up.
+pub fn raised_stop(position: &ManagedPosition, current_price: f64, stop_loss_percent: f64) -> Option<f64> {
+    if current_price <= position.purchase_price {
+        return None;
+    }
+    let new_stop_loss = current_price * (1.0 - stop_loss_percent / 100.0);
+    (new_stop_loss > position.stop_loss_price).then_some(new_stop_loss)
+}
+
+/// Raises the trailing stop when the price has moved up enough. Live positions have their
+/// stop order cancelled and replaced at the new price; when the new stop is rejected, the old
+/// one is put back so the position stays protected. Paper positions are ratcheted on `paper`.
+/// Returns whether the stop moved.
 ///
 /// # Arguments
-/// * `order` - The current open order.
+/// * `position` - The position whose stop is trailed.
 /// * `current_price` - The latest market price fetched from an API.
 /// * `stop_loss_percent` - The same percentage used to calculate the trailing stop loss.
-pub async fn update_stop_loss(order: &mut Order, current_price: f64, stop_loss_percent: f64) {
-    // Only update if the current price is above the purchase price.
-    if current_price > order.purchase_price {
-        let new_stop_loss = current_price * (1.0 - stop_loss_percent / 100.0);
-        if new_stop_loss > order.stop_loss_price {
-            println!(
-                "Updating stop loss for {}: Old stop loss ${:.2} -> New stop loss ${:.2}",
-                order.token, order.stop_loss_price, new_stop_loss
-            );
-            // In a real implementation, cancel the existing stop loss order and submit a new one.
-            order.stop_loss_price = new_stop_loss;
-        } else {
-            println!("{}: Current price ${:.2} did not move enough to adjust stop loss (current stop loss remains ${:.2}).", order.token, current_price, order.stop_loss_price);
+pub async fn update_stop_loss(
+    binance: &Binance,
+    paper: Option<&mut PaperPortfolio>,
+    position: &mut ManagedPosition,
+    current_price: f64,
+    stop_loss_percent: f64,
+) -> Result<bool, Box<dyn StdError>> {
+    let Some(new_stop_loss) = raised_stop(position, current_price, stop_loss_percent) else {
+        println!(
+            "{}: Current price {:.4} does not raise the stop loss (remains {:.4}).",
+            position.token, current_price, position.stop_loss_price
+        );
+        return Ok(false);
+    };
+    println!(
+        "Updating stop loss for {}: Old stop loss {:.4} -> New stop loss {:.4}",
+        position.token, position.stop_loss_price, new_stop_loss
+    );
+
+    if let Some(paper) = paper {
+        position.stop_loss_price = paper.ratchet_stop(&position.token, new_stop_loss).unwrap_or(new_stop_loss);
+        return Ok(true);
+    }
+
+    let filters = binance.cached_symbol_filters(&position.token).await?;
+    let new_stop_loss = Binance::round_to_step(new_stop_loss, filters.tick_size);
+    if let Some(order_id) = position.stop_order_id {
+        // Only forget the order once it is really gone.
+        binance.cancel_order(&position.token, order_id).await?;
+        position.stop_order_id = None;
+    }
+    let limit_price = Binance::stop_limit_price(new_stop_loss, get_stop_limit_buffer_pct(), filters.tick_size);
+    let placed = binance
+        .place_stop_loss_limit_order(&position.token, position.quantity, new_stop_loss, limit_price, None, None)
+        .await
+        .map_err(|e| e.to_string());
+    let error = match placed {
+        Ok(order_id) => {
+            position.stop_order_id = Some(order_id);
+            position.stop_loss_price = new_stop_loss;
+            return Ok(true);
+        }
+        Err(e) => e,
+    };
+
+    let old_limit = Binance::stop_limit_price(position.stop_loss_price, get_stop_limit_buffer_pct(), filters.tick_size);
+    match binance
+        .place_stop_loss_limit_order(&position.token, position.quantity, position.stop_loss_price, old_limit, None, None)
+        .await
+        .map_err(|e| e.to_string())
+    {
+        Ok(order_id) => {
+            position.stop_order_id = Some(order_id);
+            println!("⚠️ Could not raise the stop for {} ({}); kept it at {:.4}", position.token, error, position.stop_loss_price);
+            warn!("⚠️ Could not raise the stop for {} ({}); kept it at {:.4}", position.token, error, position.stop_loss_price);
+        }
+        Err(restore_error) => {
+            println!("🚨 UNPROTECTED HOLDING: {} has no stop; placing {:.4} failed ({}) and restoring {:.4} failed ({})", position.token, new_stop_loss, error, position.stop_loss_price, restore_error);
+            error!("🚨 UNPROTECTED HOLDING: {} has no stop; placing {:.4} failed ({}) and restoring {:.4} failed ({})", position.token, new_stop_loss, error, position.stop_loss_price, restore_error);
         }
-    } else {
-        println!("{}: Current price ${:.2} is not above purchase price ${:.2}. No stop loss update.", order.token, current_price, order.purchase_price);
     }
-}
\ No newline at end of file
+    Err(format!("could not raise the stop for {}: {}", position.token, error).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use crate::types::SYMBOL_FILTERS;
+    use crate::api::binance::SymbolFilters;
+    use crate::api::binance::mock::{local_binance_recording, mark_tradable};
+
+    #[test]
+    fn managed_position_is_built_from_a_fill() {
+        let fill = FillSummary { symbol: "ABCUSDT".to_string(), price: 2.5, quantity: 40.0, stop_loss_price: 2.375 };
+        let position = ManagedPosition::from(fill);
+        assert_eq!(position.token, "ABCUSDT");
+        assert_eq!(position.purchase_price, 2.5);
+        assert_eq!(position.quantity, 40.0);
+        assert_eq!(position.stop_loss_price, 2.375);
+        assert_eq!(position.stop_order_id, None);
+    }
+
+    #[test]
+    fn stops_are_only_raised_above_the_purchase_price() {
+        let position = ManagedPosition {
+            token: "ABCUSDT".to_string(),
+            purchase_price: 100.0,
+            stop_loss_price: 95.0,
+            quantity: 1.0,
+            stop_order_id: Some(7),
+        };
+        assert_eq!(raised_stop(&position, 99.0, 5.0), None);
+        assert_eq!(raised_stop(&position, 100.0, 5.0), None);
+        let raised = raised_stop(&position, 110.0, 5.0).unwrap();
+        assert!((raised - 104.5).abs() < 1e-9);
+        assert_eq!(raised_stop(&ManagedPosition { stop_loss_price: 105.0, ..position }, 110.0, 5.0), None);
+    }
+
+    #[tokio::test]
+    async fn a_live_buy_places_the_market_order_and_a_step_rounded_stop() {
+        let filters = SymbolFilters {
+            tick_size: 0.01,
+            step_size: 0.001,
+            min_qty: 0.001,
+            max_qty: 9000.0,
+            min_price: 0.01,
+            min_notional: 5.0,
+            quote_precision: 8,
+        };
+        SYMBOL_FILTERS.write().await.insert("EXECUSDC".to_string(), (Instant::now(), filters));
+        mark_tradable(&["EXECUSDC"]).await;
+        let (binance, requests) = local_binance_recording(vec![
+            ("/ticker/price?symbol=EXECUSDC", r#"{"symbol":"EXECUSDC","price":"20.00"}"#.to_string()),
+            ("POST /api/v3/order", r#"{"orderId":7,"executedQty":"0.4987654","cummulativeQuoteQty":"10.0"}"#.to_string()),
+        ])
+        .await;
+
+        let position = buy_token(&binance, None, "EXECUSDC", 10.0, 5.0).await.unwrap();
+
+        let requests = requests.lock().unwrap().clone();
+        assert!(requests.iter().any(|r| r.contains("/ticker/price?symbol=EXECUSDC")), "{:?}", requests);
+        let orders: Vec<&String> = requests.iter().filter(|r| r.starts_with("POST /api/v3/order")).collect();
+        assert_eq!(orders.len(), 2, "{:?}", orders);
+        assert!(orders[0].contains("side=BUY") && orders[0].contains("quoteOrderQty=10"), "{}", orders[0]);
+        assert!(orders[1].contains("type=STOP_LOSS_LIMIT") && orders[1].contains("quantity=0.498&"), "{}", orders[1]);
+        assert_eq!(position.quantity, 0.498);
+        assert!((position.purchase_price - 10.0 / 0.4987654).abs() < 1e-9);
+        assert_eq!(position.stop_loss_price, 19.04);
+        assert_eq!(position.stop_order_id, Some(7));
+    }
+
+    #[tokio::test]
+    async fn a_paper_buy_debits_the_portfolio_without_placing_orders() {
+        let (binance, requests) = local_binance_recording(vec![
+            ("/ticker/price?symbol=PAPERUSDC", r#"{"symbol":"PAPERUSDC","price":"20.00"}"#.to_string()),
+            ("POST /api/v3/order", r#"{"orderId":7}"#.to_string()),
+        ])
+        .await;
+        let mut paper = PaperPortfolio::new(&["USDC".to_string()], 100.0, 0.0, 0.0);
+
+        let position = buy_token(&binance, Some(&mut paper), "PAPERUSDC", 10.0, 5.0).await.unwrap();
+
+        assert!(!requests.lock().unwrap().iter().any(|r| r.contains("/order")));
+        assert_eq!(paper.balances["USDC"], 90.0);
+        assert_eq!(paper.positions["PAPERUSDC"].quantity, 0.5);
+        assert_eq!(position.quantity, 0.5);
+        assert_eq!(position.purchase_price, 20.0);
+        assert_eq!(position.stop_order_id, None);
+    }
+}
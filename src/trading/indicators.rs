@@ -1,4 +1,5 @@
 use serde_json::Value;
+use crate::types::CrossDirection;
 
 pub fn compute_rsi(prices: &[f64], period: usize) -> Option<f64> {
     if prices.len() < period + 1 {
@@ -25,6 +26,26 @@ pub fn compute_rsi(prices: &[f64], period: usize) -> Option<f64> {
     Some(100.0 - (100.0 / (1.0 + rs)))
 }
 
+/// Wilder-smoothed RSI over the whole series: seeded with the first `period` changes, then
+/// smoothed through every later one, so extra history before the last window sharpens it.
+pub fn compute_wilder_rsi(prices: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || prices.len() < period + 1 {
+        return None;
+    }
+    let changes: Vec<f64> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+    let (seed, rest) = changes.split_at(period);
+    let mut avg_gain = seed.iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = seed.iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+    for change in rest {
+        avg_gain = (avg_gain * (period - 1) as f64 + change.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-change).max(0.0)) / period as f64;
+    }
+    if avg_loss == 0.0 {
+        return Some(100.0);
+    }
+    Some(100.0 - 100.0 / (1.0 + avg_gain / avg_loss))
+}
+
 pub fn compute_average_volume(klines: &[Vec<Value>]) -> Option<f64> {
     let mut total = 0.0;
     let mut count = 0;
@@ -41,4 +62,92 @@ pub fn compute_average_volume(klines: &[Vec<Value>]) -> Option<f64> {
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+/// Simple moving average of the last `period` prices.
+pub fn compute_sma(prices: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || prices.len() < period {
+        return None;
+    }
+    let window = &prices[prices.len() - period..];
+    Some(window.iter().sum::<f64>() / period as f64)
+}
+
+/// SMA at every bar that has a full `period` window, oldest first.
+pub fn compute_sma_series(prices: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
+    prices
+        .windows(period)
+        .map(|w| w.iter().sum::<f64>() / period as f64)
+        .collect()
+}
+
+/// Checks whether the last bar is a cross of `fast` over `slow`. Both series are aligned
+/// on their most recent value, so they may have different lengths.
+pub fn detect_cross(fast: &[f64], slow: &[f64]) -> Option<CrossDirection> {
+    if fast.len() < 2 || slow.len() < 2 {
+        return None;
+    }
+    let (fast_prev, fast_last) = (fast[fast.len() - 2], fast[fast.len() - 1]);
+    let (slow_prev, slow_last) = (slow[slow.len() - 2], slow[slow.len() - 1]);
+
+    if fast_prev <= slow_prev && fast_last > slow_last {
+        Some(CrossDirection::Golden)
+    } else if fast_prev >= slow_prev && fast_last < slow_last {
+        Some(CrossDirection::Death)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wilder_rsi_uses_the_whole_history() {
+        let rising: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        assert_eq!(compute_wilder_rsi(&rising, 14), Some(100.0));
+        assert_eq!(compute_wilder_rsi(&rising[..14], 14), None);
+
+        // Same last 15 closes, different warmup: the smoothed RSI must differ.
+        let mut falling_then_flat: Vec<f64> = (0..20).map(|i| 200.0 - 2.0 * i as f64).collect();
+        let mut rising_then_flat: Vec<f64> = (0..20).map(|i| 100.0 + 2.0 * i as f64).collect();
+        let tail: Vec<f64> = (0..15).map(|i| 150.0 + if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        falling_then_flat.extend(&tail);
+        rising_then_flat.extend(&tail);
+        let a = compute_wilder_rsi(&falling_then_flat, 14).unwrap();
+        let b = compute_wilder_rsi(&rising_then_flat, 14).unwrap();
+        assert!(a < b, "{} vs {}", a, b);
+    }
+
+    #[test]
+    fn sma_matches_hand_computed_values() {
+        let prices = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let cases: [(usize, Option<f64>); 5] = [(1, Some(5.0)), (2, Some(4.5)), (5, Some(3.0)), (6, None), (0, None)];
+        for (period, expected) in cases {
+            assert_eq!(compute_sma(&prices, period), expected, "period {}", period);
+        }
+        assert_eq!(compute_sma_series(&prices, 3), vec![2.0, 3.0, 4.0]);
+        assert!(compute_sma_series(&prices, 6).is_empty());
+    }
+
+    #[test]
+    fn crosses_are_detected_on_the_last_bar_only() {
+        let cases: [(&[f64], &[f64], Option<CrossDirection>); 6] = [
+            (&[1.0, 3.0], &[2.0, 2.0], Some(CrossDirection::Golden)),
+            (&[3.0, 1.0], &[2.0, 2.0], Some(CrossDirection::Death)),
+            (&[2.0, 3.0], &[2.0, 2.0], Some(CrossDirection::Golden)),
+            (&[1.0, 3.0, 4.0], &[2.0, 2.0, 2.0], None),
+            (&[1.0, 1.5], &[2.0, 2.0], None),
+            (&[3.0], &[2.0], None),
+        ];
+        for (fast, slow, expected) in cases {
+            assert_eq!(detect_cross(fast, slow), expected, "fast {:?} slow {:?}", fast, slow);
+        }
+        // Series of different lengths are aligned on their latest value.
+        assert_eq!(detect_cross(&[9.0, 9.0, 1.0, 3.0], &[2.0, 2.0]), Some(CrossDirection::Golden));
+    }
+}
@@ -1,3 +1,5 @@
 pub mod indicators;
 pub mod execution;
-pub mod discovery;
\ No newline at end of file
+pub mod discovery;
+pub mod paper;
+pub mod strategy;
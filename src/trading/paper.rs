@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+/// An open simulated position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperPosition {
+    pub symbol: String,
+    pub quote: String,
+    /// Fill price including slippage.
+    pub entry_price: f64,
+    /// Base units held after the buy fee.
+    pub quantity: f64,
+    pub stop_price: f64,
+}
+
+/// A simulated fill, ready to be written to the trade log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaperFill {
+    pub symbol: String,
+    pub price: f64,
+    pub quantity: f64,
+    /// Quote spent (buy) or received after fees (sell).
+    pub quote_amount: f64,
+}
+
+/// In-memory portfolio for `pred simulate`: market orders fill at the quoted price moved
+/// `slippage_pct` against us, and `fee_pct` is charged on every fill.
+#[derive(Debug, Clone)]
+pub struct PaperPortfolio {
+    pub balances: HashMap<String, f64>,
+    pub positions: HashMap<String, PaperPosition>,
+    pub fee_pct: f64,
+    pub slippage_pct: f64,
+    pub realized_pnl: f64,
+}
+
+impl PaperPortfolio {
+    /// Starts with `starting_balance` of each quote asset and no positions.
+    pub fn new(quotes: &[String], starting_balance: f64, fee_pct: f64, slippage_pct: f64) -> Self {
+        PaperPortfolio {
+            balances: quotes.iter().map(|q| (q.clone(), starting_balance)).collect(),
+            positions: HashMap::new(),
+            fee_pct,
+            slippage_pct,
+            realized_pnl: 0.0,
+        }
+    }
+
+    /// Buys `quote_amount` of `symbol` at `price` with a stop `stop_pct` below the fill. None
+    /// when the symbol is already held, the price is unusable or the balance is short.
+    pub fn buy(&mut self, symbol: &str, quote: &str, price: f64, quote_amount: f64, stop_pct: f64) -> Option<PaperFill> {
+        if self.positions.contains_key(symbol) || price <= 0.0 || quote_amount <= 0.0 {
+            return None;
+        }
+        let balance = self.balances.get_mut(quote)?;
+        if *balance < quote_amount {
+            return None;
+        }
+        *balance -= quote_amount;
+
+        let fill_price = price * (1.0 + self.slippage_pct / 100.0);
+        let quantity = quote_amount * (1.0 - self.fee_pct / 100.0) / fill_price;
+        self.positions.insert(
+            symbol.to_string(),
+            PaperPosition {
+                symbol: symbol.to_string(),
+                quote: quote.to_string(),
+                entry_price: fill_price,
+                quantity,
+                stop_price: fill_price * (1.0 - stop_pct / 100.0),
+            },
+        );
+        Some(PaperFill { symbol: symbol.to_string(), price: fill_price, quantity, quote_amount })
+    }
+
+    /// Raises `symbol`'s stop to `new_stop` if that is higher. Returns the stop now in effect.
+    pub fn ratchet_stop(&mut self, symbol: &str, new_stop: f64) -> Option<f64> {
+        let position = self.positions.get_mut(symbol)?;
+        position.stop_price = position.stop_price.max(new_stop);
+        Some(position.stop_price)
+    }
+
+    /// Sells `symbol` at its stop when `price` has reached it. The fill is the lower of `price`
+    /// and the stop (a gap through the stop fills at the market), moved by slippage, less the
+    /// fee; proceeds go back to the quote balance.
+    pub fn check_stop(&mut self, symbol: &str, price: f64) -> Option<PaperFill> {
+        let position = self.positions.get(symbol)?;
+        if price > position.stop_price {
+            return None;
+        }
+        let position = self.positions.remove(symbol)?;
+        let fill_price = price.min(position.stop_price) * (1.0 - self.slippage_pct / 100.0);
+        let proceeds = position.quantity * fill_price * (1.0 - self.fee_pct / 100.0);
+        let cost = position.quantity * position.entry_price / (1.0 - self.fee_pct / 100.0);
+        self.realized_pnl += proceeds - cost;
+        *self.balances.entry(position.quote).or_insert(0.0) += proceeds;
+        Some(PaperFill { symbol: position.symbol, price: fill_price, quantity: position.quantity, quote_amount: proceeds })
+    }
+
+    /// Free quote balances plus open positions marked at `prices` (entry when unpriced).
+    pub fn equity(&self, prices: &HashMap<String, f64>) -> HashMap<String, f64> {
+        let mut equity = self.balances.clone();
+        for position in self.positions.values() {
+            let price = prices.get(&position.symbol).copied().unwrap_or(position.entry_price);
+            *equity.entry(position.quote.clone()).or_insert(0.0) += position.quantity * price;
+        }
+        equity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn portfolio() -> PaperPortfolio {
+        PaperPortfolio::new(&["USDT".to_string()], 1000.0, 0.1, 0.05)
+    }
+
+    #[test]
+    fn paper_buy_then_stop_books_fees_slippage_and_pnl() {
+        let mut paper = portfolio();
+        let buy = paper.buy("ABCUSDT", "USDT", 100.0, 100.0, 5.0).unwrap();
+        assert_eq!(buy.price, 100.0 * 1.0005);
+        assert_eq!(paper.balances["USDT"], 900.0);
+        assert!(paper.buy("ABCUSDT", "USDT", 100.0, 100.0, 5.0).is_none());
+
+        let stop = paper.positions["ABCUSDT"].stop_price;
+        assert!(paper.check_stop("ABCUSDT", stop + 0.01).is_none());
+        let sell = paper.check_stop("ABCUSDT", stop).unwrap();
+        assert!((sell.price - stop * 0.9995).abs() < 1e-9);
+        assert!(paper.positions.is_empty());
+        assert!(paper.realized_pnl < -5.0 && paper.realized_pnl > -6.0);
+        assert!((paper.balances["USDT"] - (900.0 + sell.quote_amount)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_gap_through_the_stop_fills_at_the_market() {
+        let mut paper = portfolio();
+        paper.buy("ABCUSDT", "USDT", 100.0, 100.0, 5.0).unwrap();
+        let sell = paper.check_stop("ABCUSDT", 80.0).unwrap();
+        assert!((sell.price - 80.0 * 0.9995).abs() < 1e-9);
+    }
+}
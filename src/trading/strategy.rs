@@ -0,0 +1,27 @@
+use crate::config;
+use crate::trading::discovery::evaluate;
+use crate::types::{Kline, Signal, SignalParams, TrendDirection};
+
+/// An entry rule. `discover_signals` hands it each candidate's lookback window (oldest first);
+/// the returned signal's `symbol` is filled in by the caller.
+pub trait Strategy: Send + Sync {
+    fn evaluate(&self, candles: &[Kline], trend: TrendDirection) -> Option<Signal>;
+}
+
+/// The growth + recent-growth + green-candle rule of `discovery::evaluate`, with its parameters
+/// read from the live config on every call so hot reloads apply.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MomentumStrategy;
+
+impl Strategy for MomentumStrategy {
+    fn evaluate(&self, candles: &[Kline], trend: TrendDirection) -> Option<Signal> {
+        let params = SignalParams {
+            lookback: config::get_lookback_period() as u32,
+            recent: config::get_signal_recent_window() as u32,
+            green_candles: config::get_require_n_green(),
+            green_min_pct: config::get_green_candle_min_pct(),
+            green_min_body_ratio: config::get_green_candle_min_body_ratio(),
+        };
+        evaluate(candles, params, trend)
+    }
+}
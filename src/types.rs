@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::time::Instant;
 use chrono::NaiveDate;
-use chrono::Utc;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use lazy_static::lazy_static;
+use crate::api::binance::SymbolFilters;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenOrder {
@@ -51,6 +54,31 @@ pub struct Order {
     pub update_time: u64,
 }
 
+/// One fill from `/myTrades`. Binance sends the decimal fields as strings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyTrade {
+    pub symbol: String,
+    pub order_id: u64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub price: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub quote_qty: f64,
+    #[serde(deserialize_with = "f64_from_str")]
+    pub commission: f64,
+    pub commission_asset: String,
+    pub time: u64,
+    pub is_buyer: bool,
+    pub is_maker: bool,
+}
+
+fn f64_from_str<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    raw.parse::<f64>().map_err(serde::de::Error::custom)
+}
+
 #[derive(Debug, Clone)]
 pub struct Signal {
     pub symbol: String,
@@ -58,6 +86,48 @@ pub struct Signal {
     pub recent_growth: f64,
     pub avg_fluct_raw: f64,
     pub avg_fluct_pct: f64,
+    /// Ranking strength from `signal_score`; higher signals are executed first.
+    pub score: f64,
+}
+
+/// Window sizes for the entry rules: candles in the overall and in the recent trend.
+#[derive(Debug, Clone, Copy)]
+pub struct SignalParams {
+    pub lookback: u32,
+    pub recent: u32,
+    /// Latest candles that must each be green by at least `green_min_pct`.
+    pub green_candles: u32,
+    pub green_min_pct: f64,
+    /// Minimum body / (high - low) of each confirming candle; 0 accepts any wick.
+    pub green_min_body_ratio: f64,
+}
+
+/// One `/klines` candlestick with its prices parsed; times are epoch milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub struct Kline {
+    pub open_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub close_time: i64,
+}
+
+impl Kline {
+    /// Parses one raw kline array (`[openTime, "open", "high", "low", "close", "volume", closeTime, ...]`).
+    pub fn from_raw(raw: &[Value]) -> Option<Self> {
+        let price = |i: usize| raw.get(i)?.as_str()?.parse::<f64>().ok();
+        Some(Kline {
+            open_time: raw.first()?.as_i64()?,
+            open: price(1)?,
+            high: price(2)?,
+            low: price(3)?,
+            close: price(4)?,
+            volume: price(5)?,
+            close_time: raw.get(6)?.as_i64()?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -66,6 +136,106 @@ pub enum TrendDirection {
     Negative,
 }
 
+/// Direction of a moving-average cross on the most recent bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossDirection {
+    /// Fast average crossed up through the slow one.
+    Golden,
+    /// Fast average crossed down through the slow one.
+    Death,
+}
+
+/// A position opened by this process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub symbol: String,
+    pub entry_price: f64,
+    pub quantity: f64,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Positions bought in this run, shared by the market and stop-loss loops through `Binance`
+/// so the stop loop knows a fresh buy's entry without asking the exchange.
+#[derive(Debug, Default)]
+pub struct PositionStore {
+    positions: HashMap<String, Position>,
+}
+
+impl PositionStore {
+    pub fn record_buy(&mut self, symbol: &str, entry_price: f64, quantity: f64, opened_at: DateTime<Utc>) {
+        self.positions.insert(
+            symbol.to_string(),
+            Position { symbol: symbol.to_string(), entry_price, quantity, opened_at },
+        );
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    pub fn entry_price(&self, symbol: &str) -> Option<f64> {
+        self.positions.get(symbol).map(|p| p.entry_price)
+    }
+
+    pub fn remove(&mut self, symbol: &str) -> Option<Position> {
+        self.positions.remove(symbol)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
+
+/// Quote spent on buys during one UTC day, for the `MAX_DAILY_SPEND` budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailySpend {
+    pub day: NaiveDate,
+    pub spent: f64,
+}
+
+/// What one `pred` market cycle did, printed as a one-line recap at its end.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CycleSummary {
+    pub signals: usize,
+    pub bought: usize,
+    pub failed: usize,
+    /// Signals left untried because of a pause or the daily budget.
+    pub skipped: usize,
+    /// Positions open in this run after the cycle.
+    pub open: usize,
+}
+
+impl CycleSummary {
+    /// Counts one executed signal as bought or failed.
+    pub fn record_attempt(&mut self, bought: bool) {
+        if bought {
+            self.bought += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+
+    /// Fills in what the buy loop did not reach and the positions still open.
+    pub fn finish(&mut self, open: usize) {
+        self.skipped = self.signals - self.bought - self.failed;
+        self.open = open;
+    }
+}
+
+impl std::fmt::Display for CycleSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} signals, {} bought, {} failed, {} skipped, {} open",
+            self.signals, self.bought, self.failed, self.skipped, self.open
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct GlobalLossTracker {
     pub consecutive_losses: u32,
@@ -76,6 +246,26 @@ pub struct GlobalLossTracker {
 lazy_static! {
     pub static ref PURCHASE_PRICES: Mutex<HashMap<String, f64>> = Mutex::new(HashMap::new());
     pub static ref MARKET_TREND: RwLock<String> = RwLock::new("Unknown".to_string());
+    /// TRADING spot symbols and when they were fetched, for `Binance::validate_symbol`.
+    pub static ref TRADABLE_SYMBOLS: RwLock<Option<(Instant, Arc<HashSet<String>>)>> = RwLock::new(None);
+    /// 24h change (%) per symbol as of its last kline evaluation, for incremental scans.
+    pub static ref LAST_SCAN_CHANGES: Mutex<HashMap<String, f64>> = Mutex::new(HashMap::new());
+    /// Symbols already trimmed during their current overbought RSI run.
+    pub static ref RSI_EXITED: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    /// Consecutive failed initial stop placements per symbol, for the unprotected-holdings alert.
+    pub static ref STOP_FAILURES: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+    /// Trade id of the open position per symbol, shared by its buy, stop and sell log lines.
+    pub static ref TRADE_IDS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    /// Open time (ms) of each symbol's first daily kline, i.e. roughly when it was listed.
+    pub static ref LISTING_TIMES: Mutex<HashMap<String, i64>> = Mutex::new(HashMap::new());
+    /// Exchange filters per symbol and when they were fetched, for `Binance::cached_symbol_filters`.
+    pub static ref SYMBOL_FILTERS: RwLock<HashMap<String, (Instant, SymbolFilters)>> = RwLock::new(HashMap::new());
+}
+
+impl Default for GlobalLossTracker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GlobalLossTracker {
@@ -112,4 +302,88 @@ impl GlobalLossTracker {
             None => false,
         }
     }
-}
\ No newline at end of file
+}
+
+impl DailySpend {
+    pub fn new(day: NaiveDate) -> Self {
+        Self { day, spent: 0.0 }
+    }
+
+    /// Starts a fresh budget when `today` is a new day.
+    pub fn roll(&mut self, today: NaiveDate) {
+        if self.day != today {
+            self.day = today;
+            self.spent = 0.0;
+        }
+    }
+
+    /// True when spending `amount` more today stays within `budget`; a budget of 0 never blocks.
+    pub fn allows(&mut self, today: NaiveDate, amount: f64, budget: f64) -> bool {
+        self.roll(today);
+        budget <= 0.0 || self.spent + amount <= budget
+    }
+
+    pub fn record(&mut self, today: NaiveDate, amount: f64) {
+        self.roll(today);
+        self.spent += amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn daily_spend_blocks_past_the_budget_and_resets_the_next_day() {
+        let day = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let mut spend = DailySpend::new(day);
+        assert!(spend.allows(day, 40.0, 100.0));
+        spend.record(day, 39.7);
+        spend.record(day, 40.2);
+        assert!(!spend.allows(day, 40.0, 100.0));
+        assert!(spend.allows(day, 40.0, 0.0));
+
+        let next = day.succ_opt().unwrap();
+        assert!(spend.allows(next, 40.0, 100.0));
+        assert_eq!(spend.spent, 0.0);
+    }
+
+    #[test]
+    fn a_my_trades_payload_fills_every_field() {
+        let payload = r#"[{
+            "symbol": "BNBBTC", "id": 28457, "orderId": 100234, "orderListId": -1,
+            "price": "4.00000100", "qty": "12.00000000", "quoteQty": "48.000012",
+            "commission": "10.10000000", "commissionAsset": "BNB", "time": 1499865549590,
+            "isBuyer": true, "isMaker": false, "isBestMatch": true
+        }]"#;
+        let trades: Vec<MyTrade> = serde_json::from_str(payload).unwrap();
+        let trade = &trades[0];
+        assert_eq!(trade.symbol, "BNBBTC");
+        assert_eq!(trade.order_id, 100234);
+        assert_eq!(trade.price, 4.000001);
+        assert_eq!(trade.qty, 12.0);
+        assert_eq!(trade.quote_qty, 48.000012);
+        assert_eq!(trade.commission, 10.1);
+        assert_eq!(trade.commission_asset, "BNB");
+        assert_eq!(trade.time, 1499865549590);
+        assert!(trade.is_buyer);
+        assert!(!trade.is_maker);
+    }
+
+    #[test]
+    fn a_cycle_with_one_buy_and_one_failure_is_summarised() {
+        let executed: [Result<f64, String>; 2] = [Ok(25.0), Err("insufficient balance".to_string())];
+        let mut summary = CycleSummary { signals: executed.len(), ..CycleSummary::default() };
+        for result in &executed {
+            summary.record_attempt(result.is_ok());
+        }
+        summary.finish(1);
+        assert_eq!(summary.to_string(), "2 signals, 1 bought, 1 failed, 0 skipped, 1 open");
+
+        // A pause after the first signal leaves the rest untried.
+        let mut paused = CycleSummary { signals: 3, ..CycleSummary::default() };
+        paused.record_attempt(true);
+        paused.finish(1);
+        assert!(paused.to_string().starts_with("3 signals, 1 bought, 0 failed, 2 skipped"));
+    }
+}